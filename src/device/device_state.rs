@@ -14,12 +14,14 @@ use log::{info, warn};
 use parking_lot::{RwLock};
 
 
-use wgpu::{Adapter, BindGroup, CommandEncoder, COPY_BYTES_PER_ROW_ALIGNMENT, Device, Extent3d, Features, Instance, LoadOp, Operations, Queue, RenderPass, RenderPassColorAttachment, RenderPassDescriptor, StoreOp, Texture, TextureFormat, TextureView, TextureViewDescriptor};
+use wgpu::{Adapter, BindGroup, CommandEncoder, COPY_BYTES_PER_ROW_ALIGNMENT, Device, Extent3d, Features, Instance, LoadOp, Operations, Queue, RenderPass, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, StoreOp, Texture, TextureFormat, TextureView, TextureViewDescriptor};
 use winit::dpi::PhysicalSize;
 
 use crate::device::message_controller::{MessageController, SnapMode};
 use crate::device::window_state::WindowState;
 use crate::gui::camera_base::CameraMode;
+use crate::scene::cable_network::{CABLE_HIGHLIGHT_MAX_POINTS, CABLE_HIGHLIGHT_STEP, CABLE_PACK_ID};
+use crate::scene::gpu_mem::GpuMem;
 use crate::shared::dimension::Dimension;
 
 use crate::shared::screen_capture::ScreenCapture;
@@ -84,6 +86,10 @@ impl DeviceState {
         let ws = _ws.read();
         let scale_factor: f64 = ws.get_scale_factor();
         self.update_shared_buffers(scale_factor);
+        self.update_pack_groups(&ws);
+        self.update_highlight(&ws);
+        self.update_outline(&ws);
+        self.update_ssao(&ws);
         let device = self.device.read();
         let queue = self.queue.read();
         match ws.surface.get_current_texture() {
@@ -106,11 +112,18 @@ impl DeviceState {
                     sample_count: 1,
                     dimension: wgpu::TextureDimension::D2,
                     format: wgpu::TextureFormat::Depth32Float,
-                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    //the ssao pass reads it
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
                     label: None,
                     view_formats: &vec![],
                 });
                 let depth_view: TextureView = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+                ws.outline_pipeline.write().fit_edge_buffer(&device, gw, gh);
+                let ol = ws.outline_pipeline.read();
+                ws.ssao_pipeline.write().fit_occlusion_buffer(&device, gw, gh);
+                let so = ws.ssao_pipeline.read();
+                ws.oit_pipeline.write().fit_targets(&device, gw, gh);
+                let oit = ws.oit_pipeline.read();
                 let handl = ws.smaa_target.clone();
                 let mut handl_w = handl.write();
                 let smaa_frame = handl_w.start_frame(&device, &queue, &view);
@@ -143,10 +156,98 @@ impl DeviceState {
                     });
                 }
                 //SCENE_RENDERING
+                let bsh: BindGroup = pl.shadow_map.bind_shadow_group(&device);
+
+                //depth seen from the sun, read by the hull and translucent passes
+                if (mc.scene_state.sun.is_on) {
+                    let mut render_pass: RenderPass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("Render Pass SHADOW"),
+                        color_attachments: &[],
+                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                            view: &pl.shadow_map.view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(1.0),
+                                store: StoreOp::Store,
+                            }),
+                            stencil_ops: None,
+                        }),
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+                    render_pass.set_bind_group(0, &bg, &[]);
+                    //every pack into the shadow map, the pipeline drops what casts no shadow
+                    draw_packs(&mut render_pass, &mc, (&pl.shadow_render_pipeline, &pl.compact_shadow_render_pipeline, &pl.instanced_shadow_render_pipeline), false);
+                }
 
                 {
                     let mut render_pass: RenderPass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                         label: Some("Render Pass HULL"),
+                        color_attachments: &[
+                            Some(wgpu::RenderPassColorAttachment {
+                                view: &smaa_frame,
+                                resolve_target: None,
+                                ops: wgpu::Operations {
+                                    load: wgpu::LoadOp::Load,
+                                    store: StoreOp::Store,
+                                },
+                            }),
+                            //normals, distances and part keys for the outline pass
+                            Some(wgpu::RenderPassColorAttachment {
+                                view: &ol.edge_view,
+                                resolve_target: None,
+                                ops: wgpu::Operations {
+                                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                                    store: StoreOp::Store,
+                                },
+                            }),
+                        ],
+                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                            view: &depth_view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: StoreOp::Store,
+                            }),
+                            stencil_ops: None,
+                        }),
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+                    render_pass.set_bind_group(0, &bg, &[]);
+                    render_pass.set_bind_group(2, &bsh, &[]);
+
+                    //back faces of cut parts are drawn again as section caps after the faces in front of them
+                    let is_capped = mc.scene_state.is_section_capped();
+                    draw_packs(&mut render_pass, &mc, (&pl.mesh_render_pipeline, &pl.compact_mesh_render_pipeline, &pl.instanced_mesh_render_pipeline), false);
+                    if (is_capped) {
+                        draw_packs(&mut render_pass, &mc, (&pl.cap_render_pipeline, &pl.compact_cap_render_pipeline, &pl.instanced_cap_render_pipeline), false);
+                    }
+                }
+
+                //ambient occlusion from the depth and edge buffers, blurred and multiplied into the frame
+                if (mc.scene_state.ssao.is_on) {
+                    let bs: BindGroup = so.bind_ssao_group(&device, &ol.edge_view, &depth_view);
+                    {
+                        let mut render_pass: RenderPass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                            label: Some("Render Pass SSAO"),
+                            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                                view: &so.occlusion_view,
+                                resolve_target: None,
+                                ops: wgpu::Operations {
+                                    load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                                    store: StoreOp::Store,
+                                },
+                            })],
+                            depth_stencil_attachment: None,
+                            timestamp_writes: None,
+                            occlusion_query_set: None,
+                        });
+                        render_pass.set_pipeline(&so.ssao_render_pipeline);
+                        render_pass.set_bind_group(0, &bs, &[]);
+                        render_pass.draw(Range { start: 0, end: 3 }, Range { start: 0, end: 1 });
+                    }
+                    let bb: BindGroup = so.bind_blur_group(&device, &ol.edge_view);
+                    let mut render_pass: RenderPass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("Render Pass SSAO BLUR"),
                         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                             view: &smaa_frame,
                             resolve_target: None,
@@ -155,28 +256,136 @@ impl DeviceState {
                                 store: StoreOp::Store,
                             },
                         })],
-                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                            view: &depth_view,
-                            depth_ops: Some(wgpu::Operations {
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+                    render_pass.set_pipeline(&so.blur_render_pipeline);
+                    render_pass.set_bind_group(0, &bb, &[]);
+                    render_pass.draw(Range { start: 0, end: 3 }, Range { start: 0, end: 1 });
+                }
+
+                //silhouettes, part borders and creases found in the edge buffer of the hull pass
+                if (mc.scene_state.outline.is_on) {
+                    let bo: BindGroup = ol.bind_outline_group(&device);
+                    let mut render_pass: RenderPass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("Render Pass OUTLINE"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &smaa_frame,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
                                 load: wgpu::LoadOp::Load,
                                 store: StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+                    render_pass.set_pipeline(&ol.outline_render_pipeline);
+                    render_pass.set_bind_group(0, &bo, &[]);
+                    render_pass.draw(Range { start: 0, end: 3 }, Range { start: 0, end: 1 });
+                }
+
+                //translucent materials over the opaque frame, summed in any order and blended in one go
+                if (mc.has_translucent_materials()) {
+                    {
+                        let mut render_pass: RenderPass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                            label: Some("Render Pass TRANSLUCENT"),
+                            color_attachments: &[
+                                Some(wgpu::RenderPassColorAttachment {
+                                    view: &oit.accum_view,
+                                    resolve_target: None,
+                                    ops: wgpu::Operations {
+                                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                                        store: StoreOp::Store,
+                                    },
+                                }),
+                                Some(wgpu::RenderPassColorAttachment {
+                                    view: &oit.revealage_view,
+                                    resolve_target: None,
+                                    ops: wgpu::Operations {
+                                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                                        store: StoreOp::Store,
+                                    },
+                                }),
+                            ],
+                            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                                view: &depth_view,
+                                depth_ops: Some(wgpu::Operations {
+                                    load: wgpu::LoadOp::Load,
+                                    store: StoreOp::Store,
+                                }),
+                                stencil_ops: None,
                             }),
-                            stencil_ops: None,
-                        }),
+                            timestamp_writes: None,
+                            occlusion_query_set: None,
+                        });
+                        render_pass.set_bind_group(0, &bg, &[]);
+                        render_pass.set_bind_group(2, &bsh, &[]);
+                        //every pack again, opaque fragments are dropped in fs_translucent
+                        draw_packs(&mut render_pass, &mc, (&pl.translucent_render_pipeline, &pl.compact_translucent_render_pipeline, &pl.instanced_translucent_render_pipeline), false);
+                    }
+                    let bt: BindGroup = oit.bind_composite_group(&device);
+                    let mut render_pass: RenderPass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("Render Pass TRANSLUCENT COMPOSITE"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &smaa_frame,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
                         timestamp_writes: None,
                         occlusion_query_set: None,
                     });
-                    render_pass.set_pipeline(&pl.mesh_render_pipeline);
-                    render_pass.set_bind_group(0, &bg, &[]);
+                    render_pass.set_pipeline(&oit.composite_render_pipeline);
+                    render_pass.set_bind_group(0, &bt, &[]);
+                    render_pass.draw(Range { start: 0, end: 3 }, Range { start: 0, end: 1 });
+                }
 
-                    mc.scene_state.gpu_mems.iter().for_each(|mem| {
-                        if (mem.is_renderable) {
-                            render_pass.set_vertex_buffer(0, mem.v_buffer.slice(..));
-                            render_pass.set_index_buffer(mem.i_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                            let indx_count = (mem.i_buffer.size() / mem::size_of::<i32>() as u64) as u32;
-                            render_pass.draw_indexed(Range { start: 0, end: indx_count }, 0, Range { start: 0, end: 1 });
+                //cable route lit over the cable pack
+                let hl = ws.highlight_pipeline.read();
+                if (CABLE_HILIGHT_ENABLE && hl.cab_nodes_count > 0) {
+                    match mc.scene_state.gpu_mems.get(&CABLE_PACK_ID).filter(|mem| mem.is_renderable && mem.compact_buffers.is_none()) {
+                        None => {}
+                        Some(mem) => {
+                            let mut render_pass: RenderPass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                                label: Some("Render Pass CABLE HIGHLIGHT"),
+                                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                                    view: &smaa_frame,
+                                    resolve_target: None,
+                                    ops: wgpu::Operations {
+                                        load: wgpu::LoadOp::Load,
+                                        store: StoreOp::Store,
+                                    },
+                                })],
+                                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                                    view: &depth_view,
+                                    depth_ops: Some(wgpu::Operations {
+                                        load: wgpu::LoadOp::Load,
+                                        store: StoreOp::Store,
+                                    }),
+                                    stencil_ops: None,
+                                }),
+                                timestamp_writes: None,
+                                occlusion_query_set: None,
+                            });
+                            render_pass.set_bind_group(0, &hl.hl_uniform_bind_group, &[]);
+                            if (mem.instanced.is_some()) {
+                                render_pass.set_pipeline(&hl.hl_instanced_render_pipeline);
+                                draw_instanced(&mut render_pass, mem);
+                            } else {
+                                render_pass.set_pipeline(&hl.hl_render_pipeline);
+                                render_pass.set_vertex_buffer(0, mem.v_buffer.slice(..));
+                                render_pass.set_index_buffer(mem.i_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                                let indx_count = (mem.i_buffer.size() / mem::size_of::<i32>() as u64) as u32;
+                                render_pass.draw_indexed(Range { start: 0, end: indx_count }, 0, Range { start: 0, end: 1 });
+                            }
                         }
-                    });
+                    }
                 }
 
                 if (!mc.is_mouse_btn_active && mc.active_point.x<f32::max_value() && mc.scene_state.camera.mode==CameraMode::ORBIT) {
@@ -291,7 +500,7 @@ impl DeviceState {
             let pl = ws.mesh_pipeline.read();
             let bg: BindGroup = pl.bind_mesh_group(&self.device.read(), &mc.shared_buffers);
             let queue = self.queue.read();
-            let mut is_done = false;
+            let is_done: bool;
 
             {
                 let _render_pass: RenderPass = sel_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -340,19 +549,10 @@ impl DeviceState {
                     timestamp_writes: None,
                     occlusion_query_set: None,
                 });
-                sel_render_pass.set_pipeline(&pl.selection_render_pipeline);
                 sel_render_pass.set_bind_group(0, &bg, &[]);
-                mc.scene_state.gpu_mems.iter().for_each(|mem| {
-                    if (mem.is_renderable) {
-                        {
-                            let indx_count = (mem.i_buffer.size() / mem::size_of::<i32>() as u64) as u32;
-                            sel_render_pass.set_vertex_buffer(0, mem.v_buffer.slice(..));
-                            sel_render_pass.set_index_buffer(mem.i_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                            sel_render_pass.draw_indexed(Range { start: 0, end: indx_count }, 0, Range { start: 0, end: 1 });
-                            is_done = true;
-                        }
-                    }
-                });
+                //vs_instanced writes the index the vertex would have in the full layout,
+                //compact packs are not indexed, vs_compact pulls vertices so the written id is the index position
+                is_done = draw_packs(&mut sel_render_pass, &mc, (&pl.selection_render_pipeline, &pl.compact_selection_render_pipeline, &pl.instanced_selection_render_pipeline), true);
             }
 
             //Copy Buffer to Host
@@ -397,66 +597,83 @@ impl DeviceState {
         }
     }
 
-    fn update_shared_buffers(&self, scale_factor: f64) {
-        let slicer_is_dirty = self.mc.read().scene_state.slicer.is_dirty;
-        let materials_is_dirty = self.mc.read().is_materials_dirty;
-        let _snap_mode: SnapMode = self.mc.read().snap_mode.clone();
-
-        let is_metadata0_dirty = self.mc.read().scene_state.gpu_mems[0].is_metadata_dirty;
-        let is_metadata1_dirty = self.mc.read().scene_state.gpu_mems[1].is_metadata_dirty;
-        let is_metadata2_dirty = self.mc.read().scene_state.gpu_mems[2].is_metadata_dirty;
-        let is_metadata3_dirty = self.mc.read().scene_state.gpu_mems[3].is_metadata_dirty;
-        let is_metadata4_dirty = self.mc.read().scene_state.gpu_mems[4].is_metadata_dirty;
-        let is_metadata5_dirty = self.mc.read().scene_state.gpu_mems[5].is_metadata_dirty;
-        let is_metadata6_dirty = self.mc.read().scene_state.gpu_mems[6].is_metadata_dirty;
-        let is_metadata7_dirty = self.mc.read().scene_state.gpu_mems[7].is_metadata_dirty;
-
-        if is_metadata0_dirty {
-            let hmd = &self.mc.read().scene_state.gpu_mems[0].metadata.clone();
-            self.mc.write().shared_buffers.update_metadata0(self.device.clone(), self.queue.clone(), hmd);
-            self.mc.write().scene_state.gpu_mems[0].reset_dirty_metadata();
+    //points of the highlighted cable when it changes, camera and materials every frame it is shown
+    fn update_highlight(&self, ws: &WindowState) {
+        let device = self.device.read();
+        let queue = self.queue.read();
+        let mut hl = ws.highlight_pipeline.write();
+        {
+            let mut mcw = self.mc.write();
+            if (mcw.scene_state.is_cable_trace_dirty) {
+                let points: Vec<[f32; 4]> = match &mcw.scene_state.cable_trace {
+                    None => { vec![] }
+                    Some(trace) => { trace.highlight_points(CABLE_HIGHLIGHT_STEP, CABLE_HIGHLIGHT_MAX_POINTS) }
+                };
+                hl.set_cab_nodes(&device, &queue, points.as_slice());
+                mcw.scene_state.is_cable_trace_dirty = false;
+            }
         }
-
-        if is_metadata1_dirty {
-            let hmd = &self.mc.read().scene_state.gpu_mems[1].metadata.clone();
-            self.mc.write().shared_buffers.update_metadata1(self.device.clone(), self.queue.clone(), hmd);
-            self.mc.write().scene_state.gpu_mems[1].reset_dirty_metadata();
+        if (hl.cab_nodes_count > 0) {
+            let mc = self.mc.read();
+            let eye = *mc.scene_state.camera.eye.clone().read();
+            let eye_position: &[f32; 3] = eye.as_ref();
+            hl.update_uniforms(&queue, &mc.scene_state.camera.get_mvp_buffer(), &mc.scene_state.camera.get_norm_buffer(), &mc.scene_state.camera.get_forward_dir_buffer(), eye_position, &mc.materials);
         }
+    }
 
-        if is_metadata2_dirty {
-            let hmd = &self.mc.read().scene_state.gpu_mems[2].metadata.clone();
-            self.mc.write().shared_buffers.update_metadata2(self.device.clone(), self.queue.clone(), hmd);
-            self.mc.write().scene_state.gpu_mems[2].reset_dirty_metadata();
+    fn update_outline(&self, ws: &WindowState) {
+        if (self.mc.read().scene_state.outline.is_dirty) {
+            let mut mcw = self.mc.write();
+            ws.outline_pipeline.read().update_style(&self.queue.read(), &mcw.scene_state.outline.style);
+            mcw.scene_state.outline.reset_dirty();
         }
+    }
 
-        if is_metadata3_dirty {
-            let hmd = &self.mc.read().scene_state.gpu_mems[3].metadata.clone();
-            self.mc.write().shared_buffers.update_metadata3(self.device.clone(), self.queue.clone(), hmd);
-            self.mc.write().scene_state.gpu_mems[3].reset_dirty_metadata();
+    fn update_ssao(&self, ws: &WindowState) {
+        let mc = self.mc.read();
+        if (mc.scene_state.ssao.is_on) {
+            let eye = *mc.scene_state.camera.eye.read();
+            ws.ssao_pipeline.read().update_uniforms(&self.queue.read(), mc.scene_state.camera.get_mvp_matrix(), eye, &mc.scene_state.ssao.style);
         }
+    }
 
-        if is_metadata4_dirty {
-            let hmd = &self.mc.read().scene_state.gpu_mems[4].metadata.clone();
-            self.mc.write().shared_buffers.update_metadata4(self.device.clone(), self.queue.clone(), hmd);
-            self.mc.write().scene_state.gpu_mems[4].reset_dirty_metadata();
-        }
+    //bind groups of new packs and of packs whose buffers were replaced, the rest are kept between frames
+    fn update_pack_groups(&self, ws: &WindowState) {
+        let pl = ws.mesh_pipeline.read();
+        let device = self.device.read();
+        let mut mcw = self.mc.write();
+        let mcw = &mut *mcw;
+        let metadata_buffers = &mcw.shared_buffers.metadata_buffers;
+        mcw.scene_state.gpu_mems.values_mut().filter(|mem| mem.is_renderable && mem.bind_group.is_none()).for_each(|mem| {
+            mem.bind_group = match (metadata_buffers.get(&mem.id), &mem.compact_buffers) {
+                (None, _) => None,
+                (Some(metadata_buffer), None) => Some(pl.bind_metadata_group(&device, &metadata_buffer.read())),
+                (Some(metadata_buffer), Some(cb)) => Some(pl.bind_compact_group(&device, &metadata_buffer.read(), &cb.quantization_buffer, &cb.parts_buffer, &mem.v_buffer, &mem.i_buffer)),
+            };
+        });
+    }
 
-        if is_metadata5_dirty {
-            let hmd = &self.mc.read().scene_state.gpu_mems[5].metadata.clone();
-            self.mc.write().shared_buffers.update_metadata5(self.device.clone(), self.queue.clone(), hmd);
-            self.mc.write().scene_state.gpu_mems[5].reset_dirty_metadata();
-        }
-        if is_metadata6_dirty {
-            let hmd = &self.mc.read().scene_state.gpu_mems[6].metadata.clone();
-            self.mc.write().shared_buffers.update_metadata6(self.device.clone(), self.queue.clone(), hmd);
-            self.mc.write().scene_state.gpu_mems[6].reset_dirty_metadata();
-        }
-        if is_metadata7_dirty {
-            let hmd = &self.mc.read().scene_state.gpu_mems[7].metadata.clone();
-            self.mc.write().shared_buffers.update_metadata7(self.device.clone(), self.queue.clone(), hmd);
-            self.mc.write().scene_state.gpu_mems[7].reset_dirty_metadata();
-        }
+    fn update_shared_buffers(&self, scale_factor: f64) {
+        let slicer_is_dirty = self.mc.read().scene_state.slicer.is_dirty;
+        let materials_is_dirty = self.mc.read().is_materials_dirty;
+        let _snap_mode: SnapMode = self.mc.read().snap_mode.clone();
 
+        {
+            let dirty_packs: Vec<u32> = self.mc.read().scene_state.gpu_mems.values().filter(|mem| mem.is_metadata_dirty).map(|mem| mem.id).collect();
+            dirty_packs.iter().for_each(|pack_id| {
+                let mut mcw = self.mc.write();
+                let mcw = &mut *mcw;
+                match mcw.scene_state.gpu_mems.get_mut(pack_id) {
+                    None => {}
+                    Some(mem) => {
+                        if (mcw.shared_buffers.update_metadata(self.device.clone(), self.queue.clone(), mem.id, &mem.metadata)) {
+                            mem.bind_group = None;
+                        }
+                        mem.reset_dirty_metadata();
+                    }
+                }
+            });
+        }
 
         if materials_is_dirty {
             self.mc.write().reset_material_dirty();
@@ -473,6 +690,18 @@ impl DeviceState {
             self.mc.write().scene_state.slicer.reset_dirty();
         };
 
+        if self.mc.read().scene_state.section_caps.is_dirty {
+            self.mc.write().scene_state.section_caps.reset_dirty();
+            let mcr = self.mc.read();
+            mcr.shared_buffers.update_caps(self.queue.clone(), &mcr.scene_state.section_caps.styles);
+        };
+
+        if self.mc.read().scene_state.clipping.is_dirty {
+            let clip = self.mc.read().scene_state.clipping.to_uniform();
+            self.mc.write().shared_buffers.update_clipping(self.queue.clone(), &clip);
+            self.mc.write().scene_state.clipping.reset_dirty();
+        };
+
         {
             let active_point: Point3<f32> = self.mc.read().active_point.clone();
             let dimension: Dimension = self.mc.read().dimension.clone();
@@ -501,8 +730,64 @@ impl DeviceState {
             #[cfg(target_arch = "wasm32")]
                 let h = self.mc.read().scene_state.camera.screen_h / scale_factor as f32;
 
-            self.mc.write().shared_buffers.update_lights(self.queue.clone(), light_position, eye_position, w, h);
+            let light_color = self.mc.read().scene_state.light_temperature.color();
+            self.mc.write().shared_buffers.update_lights(self.queue.clone(), light_position, eye_position, w, h, &light_color);
+        }
+
+        {
+            let mc = self.mc.read();
+            mc.shared_buffers.update_sun(self.queue.clone(), &mc.scene_state.sun.uniform(&mc.scene_state.tot_bbx));
+        }
+    }
+}
+
+//every renderable pack with the full, compact and instanced pipeline of a pass, false if nothing was drawn.
+//Compact packs are drawn without indices if the pipeline pulls vertices from storage (selection)
+fn draw_packs(render_pass: &mut RenderPass, mc: &MessageController, pipelines: (&RenderPipeline, &RenderPipeline, &RenderPipeline), is_compact_pulled: bool) -> bool {
+    let (full, compact, instanced) = pipelines;
+    let mut is_drawn = false;
+    mc.scene_state.gpu_mems.values().for_each(|mem| {
+        match (mem.is_renderable, &mem.bind_group, &mem.compact_buffers) {
+            (true, Some(bm), None) if (mem.instanced.is_some()) => {
+                render_pass.set_pipeline(instanced);
+                render_pass.set_bind_group(1, bm, &[]);
+                draw_instanced(render_pass, mem);
+                is_drawn = true;
+            }
+            (true, Some(bm), compact_buffers) => {
+                let is_compact = compact_buffers.is_some();
+                let indx_count = (mem.i_buffer.size() / mem::size_of::<i32>() as u64) as u32;
+                render_pass.set_pipeline(if (is_compact) { compact } else { full });
+                render_pass.set_bind_group(1, bm, &[]);
+                if (is_compact && is_compact_pulled) {
+                    render_pass.draw(Range { start: 0, end: indx_count }, Range { start: 0, end: 1 });
+                } else {
+                    render_pass.set_vertex_buffer(0, mem.v_buffer.slice(..));
+                    render_pass.set_index_buffer(mem.i_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    render_pass.draw_indexed(Range { start: 0, end: indx_count }, 0, Range { start: 0, end: 1 });
+                }
+                is_drawn = true;
+            }
+            _ => {}
+        }
+    });
+    is_drawn
+}
+
+//one draw per template of an instanced pack, the pipeline and its bind groups are set by the caller
+fn draw_instanced(render_pass: &mut RenderPass, mem: &GpuMem) {
+    match (&mem.instanced, &mem.instance_buffer) {
+        (Some(instanced), Some(instance_buffer)) => {
+            render_pass.set_vertex_buffer(0, mem.v_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            instanced.templates.iter().filter(|t| t.vertex_count > 0 && t.instance_count > 0).for_each(|t| {
+                render_pass.draw(
+                    Range { start: t.first_vertex, end: t.first_vertex + t.vertex_count },
+                    Range { start: t.first_instance, end: t.first_instance + t.instance_count },
+                );
+            });
         }
+        _ => {}
     }
 }
 
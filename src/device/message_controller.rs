@@ -1,25 +1,35 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 
 use std::rc::Rc;
 
 
-use cgmath::{Point3};
+use cgmath::{Deg, Point3, Vector3};
 use cgmath::num_traits::Float;
 
 use log::{info, warn};
 use nalgebra::Point4;
 use parking_lot::RwLock;
-use wgpu::{Device};
+use wgpu::{Device, Queue};
 use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::{DeviceId, ElementState, KeyEvent, MouseButton, MouseScrollDelta, TouchPhase};
 use winit::keyboard::{KeyCode, PhysicalKey};
 use crate::device::window_state::WindowState;
 use crate::gui::camera_base::{CameraMode, FlyActions};
-use crate::remote::common_state::{COMMANDS, DIMENSIONING, REMOTE_HULL_MESH, SLICER};
-use crate::remote::{hull_state, RemoteCommand};
+use crate::gui::clipping::{ClipPlane, SectionBox};
+use crate::remote::common_state::{CABLE_NETWORK, COMMANDS, DIMENSIONING, REMOTE_HULL_MESH, REMOTE_PACK_STREAM, SLICER};
+#[cfg(target_arch = "wasm32")]
+use crate::remote::common_state::{on_pack_stream_done, on_section_done};
+use crate::remote::{hull_state, PackStreamEvent, RemoteCommand};
+use crate::scene::cable_network::{CableTrace, CABLE_PACK_ID};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scene::cable_network::{CableNetwork, BUNDLED_CABLE_DIR};
 use crate::scene::mesh_loader::read_hull_unpacked_new_format;
+use crate::scene::pack_format::PartInfo;
 use crate::scene::scene_state::SceneState;
+use crate::scene::section::Section;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scene::section::SectionFormat;
 use crate::shared::dimension::{Dimension, DimensionMode};
 use crate::shared::materials_lib::{EQ_TY_MAX, EQ_TY_MIN, Material, PIPE_TY_MAX, PIPE_TY_MIN, TY_HULL_OTHERS, TY_HULL_OUTERPLATES, TY_HULL_PLATES, TY_HULL_PROFILES};
 use crate::shared::mesh_common::MeshVertex;
@@ -27,7 +37,7 @@ use crate::shared::shared_buffers::SharedBuffers;
 use crate::shared::text_layout::TextLayout;
 use crate::shared::Triangle;
 #[cfg(target_arch = "wasm32")]
-use crate::remote::hull_state::{get_bbx_array, get_index_array, get_types_array, get_vertex_array, on_render_wasm, on_load_to_gpu};
+use crate::remote::hull_state::{get_bbx_array, get_index_array, get_types_array, get_vertex_array, on_render_wasm, on_load_to_gpu, on_load_error};
 
 use crate::remote::hull_state::{HIDDEN_HULL, SELECTED_HULL};
 
@@ -66,6 +76,9 @@ pub struct MessageController {
     pub active_id: u32,
     active_pack_id: u32,
     pub active_point: Point3<f32>,
+    //part info under the cursor, looked up again only when the picked vertex or pack changes
+    active_part: Option<PartInfo>,
+    active_part_key: (u32, u32),
     pub active_triangle: Triangle,
     pub is_capture_screen_requested: bool,
     pub is_off_screen_ready: bool,
@@ -73,13 +86,16 @@ pub struct MessageController {
     pub text_layout: Rc<RwLock<TextLayout>>,
     pub dimension: Dimension,
     pub test_load: i32,
+    //cable highlighted by F8, index into the cables of the network
+    test_cable: usize,
+    is_cable_load_on: bool,
     pub is_wasm_loaded: bool,
     pub is_mouse_btn_active: bool,
 }
 
 impl MessageController {
-    pub fn new(device: Rc<RwLock<Device>>, window_state: Rc<RwLock<WindowState>>, text_layout: Rc<RwLock<TextLayout>>) -> Self {
-        let scene_state = SceneState::new(device.clone());
+    pub fn new(device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, window_state: Rc<RwLock<WindowState>>, text_layout: Rc<RwLock<TextLayout>>) -> Self {
+        let scene_state = SceneState::new(device.clone(), queue);
         let shared_buffers = SharedBuffers::new(device.clone());
         let active_triangle: Triangle = Triangle::new(
             Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
@@ -102,6 +118,8 @@ impl MessageController {
             active_id: 0,
             active_pack_id: 0,
             active_point: Point3::new(f32::max_value(), f32::max_value(), f32::max_value()),
+            active_part: None,
+            active_part_key: (0, 0),
             active_triangle: active_triangle,
             is_capture_screen_requested: false,
             is_off_screen_ready: false,
@@ -109,6 +127,8 @@ impl MessageController {
             text_layout: text_layout,
             dimension: Dimension::new(),
             test_load: 0,
+            test_cable: 0,
+            is_cable_load_on: false,
             is_wasm_loaded: false,
             is_mouse_btn_active: false,
         }
@@ -128,11 +148,20 @@ impl MessageController {
 
         self.scene_state.on_render();
 
+        if (self.active_part_key != (self.active_id, self.active_pack_id)) {
+            self.active_part_key = (self.active_id, self.active_pack_id);
+            self.active_part = if (self.active_id != 0) {
+                self.scene_state.part_info_by_vertex_index(self.active_id as i32, self.active_pack_id)
+            } else {
+                None
+            };
+        }
 
         self.text_layout.write().on_render(
             self.snap_mode.clone(),
             self.active_id as i32,
             self.active_point.clone(),
+            self.active_part.clone(),
             self.get_mouse_pos(),
             self.dimension.clone(),
             self.scene_state.camera.vp_matrix,
@@ -166,9 +195,13 @@ impl MessageController {
                 match key.state {
                     ElementState::Pressed => {}
                     ElementState::Released => {
-                        warn!("Start Slicer");
-                        //unsafe { debug_move_to(); }
-                        warn!("FINISH Slicer");
+                        //cuts square to the view at the picked point, the next press removes the cuts
+                        if (self.scene_state.clipping.is_active()) {
+                            self.scene_state.clipping.clear();
+                            self.is_state_dirty = true;
+                        } else {
+                            self.clip_plane_from_camera();
+                        }
                     }
                 }
             }
@@ -230,44 +263,130 @@ impl MessageController {
                     ElementState::Pressed => {}
                     ElementState::Released => {
                         warn!("Start Load Hull by F2{:?}", self.test_load);
-                        match self.test_load {
-                            0 => {
-                                self.scene_state.set_hull_mesh0();
-                                self.test_load = self.test_load + 1;
-                            }
-                            1 => {
-                                self.scene_state.set_hull_mesh1();
-                                self.test_load = self.test_load + 1;
-                            }
-                            2 => {
-                                self.scene_state.set_hull_mesh2();
+                        #[cfg(not(target_arch = "wasm32"))]
+                        let pack_id: Option<u32> = self.scene_state.hull_pack_id_at(self.test_load as usize);
+                        #[cfg(target_arch = "wasm32")]
+                        let pack_id: Option<u32> = Some(self.test_load as u32);
+                        match pack_id {
+                            None => {}
+                            Some(pack_id) => {
+                                self.load_pack(pack_id);
                                 self.test_load = self.test_load + 1;
                             }
-                            3 => {
-                                self.scene_state.set_hull_mesh3();
-                                self.test_load = self.test_load + 1;
+                        }
+
+
+                        // info!("FINISH Load Hull by F2{:?}", key);
+                    }
+                }
+            }
+            PhysicalKey::Code(KeyCode::F4) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        //unloads the pack loaded last by F2
+                        if (self.test_load > 0) {
+                            self.test_load = self.test_load - 1;
+                            #[cfg(not(target_arch = "wasm32"))]
+                            let pack_id: Option<u32> = self.scene_state.hull_pack_id_at(self.test_load as usize);
+                            #[cfg(target_arch = "wasm32")]
+                            let pack_id: Option<u32> = Some(self.test_load as u32);
+                            match pack_id {
+                                None => {}
+                                Some(pack_id) => {
+                                    self.unload_pack(pack_id);
+                                }
                             }
-                            4 => {
-                                self.scene_state.set_hull_mesh4();
-                                self.test_load = self.test_load + 1;
+                        }
+                    }
+                }
+            }
+            PhysicalKey::Code(KeyCode::F7) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        //loads the bundled cable network, unloads it on the next press
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            let network = if (self.scene_state.gpu_mems.contains_key(&CABLE_PACK_ID)) {
+                                Ok(None)
+                            } else {
+                                CableNetwork::read_dir(std::path::Path::new(BUNDLED_CABLE_DIR)).map(Some)
+                            };
+                            match network {
+                                Ok(network) => {
+                                    match CABLE_NETWORK.lock() {
+                                        Ok(mut m) => { m.set(network); }
+                                        Err(_e) => { warn!("CANT LOCK CABLE_NETWORK MEM") }
+                                    }
+                                }
+                                Err(e) => { warn!("CANT LOAD CABLE NETWORK: {}", e); }
                             }
-                            5 => {
-                                self.scene_state.set_hull_mesh5();
-                                self.test_load = self.test_load + 1;
+                        }
+                    }
+                }
+            }
+            PhysicalKey::Code(KeyCode::F8) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        //highlights the cables of the loaded network one by one
+                        let cables: Vec<i32> = match CABLE_NETWORK.lock() {
+                            Ok(m) => m.network.as_ref().map_or(vec![], |n| n.cables()),
+                            Err(_e) => {
+                                warn!("CANT LOCK CABLE_NETWORK MEM");
+                                vec![]
                             }
-                            6 => {
-                                self.scene_state.set_hull_mesh6();
-                                self.test_load = self.test_load + 1;
+                        };
+                        match cables.get(self.test_cable % cables.len().max(1)) {
+                            None => { warn!("CABLE NETWORK IS NOT LOADED"); }
+                            Some(cable) => {
+                                self.highlight_cable(*cable);
+                                self.test_cable = self.test_cable + 1;
                             }
-                            7 => {
-                                self.scene_state.set_hull_mesh7();
-                                self.test_load = self.test_load + 1;
+                        }
+                    }
+                }
+            }
+            PhysicalKey::Code(KeyCode::F10) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        //logs the network report and toggles the segment load colours
+                        match CABLE_NETWORK.lock() {
+                            Ok(m) => {
+                                match &m.network {
+                                    None => { warn!("CABLE NETWORK IS NOT LOADED"); }
+                                    Some(network) => {
+                                        let report = network.report();
+                                        warn!("CABLE NETWORK: {} NODES {} LINKS {} DANGLING {} SUB-NETWORKS {} MAX LOAD",
+                                            report.nodes, report.links, report.dangling.len(), report.components.len(),
+                                            report.loads.values().max().copied().unwrap_or(0));
+                                    }
+                                }
                             }
-                            _ => {}
+                            Err(_e) => { warn!("CANT LOCK CABLE_NETWORK MEM") }
                         }
-
-
-                        // info!("FINISH Load Hull by F2{:?}", key);
+                        self.set_cable_load_colors(!self.is_cable_load_on);
+                    }
+                }
+            }
+            PhysicalKey::Code(KeyCode::F11) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        let is_on = !self.scene_state.section_caps.is_on;
+                        self.scene_state.section_caps.set_on(is_on);
+                        self.is_state_dirty = true;
+                    }
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            PhysicalKey::Code(KeyCode::F12) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        self.save_section(None);
                     }
                 }
             }
@@ -390,6 +509,46 @@ impl MessageController {
                     ElementState::Released => {}
                 }
             }
+            PhysicalKey::Code(KeyCode::KeyL) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        let is_on = !self.scene_state.outline.is_on;
+                        self.scene_state.outline.set_on(is_on);
+                        self.is_state_dirty = true;
+                    }
+                }
+            }
+            PhysicalKey::Code(KeyCode::KeyK) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        let is_on = !self.scene_state.ssao.is_on;
+                        self.scene_state.ssao.set_on(is_on);
+                        self.is_state_dirty = true;
+                    }
+                }
+            }
+            PhysicalKey::Code(KeyCode::KeyJ) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        let is_on = !self.scene_state.sun.is_on;
+                        self.scene_state.sun.set_on(is_on);
+                        self.is_state_dirty = true;
+                    }
+                }
+            }
+            PhysicalKey::Code(KeyCode::KeyT) => {
+                match key.state {
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        self.scene_state.light_temperature.next_preset();
+                        info!("LIGHT: {}", self.scene_state.light_temperature.name());
+                        self.is_state_dirty = true;
+                    }
+                }
+            }
 
             PhysicalKey::Unidentified(_) => {}
             _ => (),
@@ -477,6 +636,9 @@ impl MessageController {
     }
 
     fn check_remote_state(&mut self) {
+        self.scene_state.check_assembly();
+        self.check_cable_network();
+
         match SELECTED_HULL.try_lock() {
             Ok(mut s) => {
                 if s.is_dirty {
@@ -544,49 +706,14 @@ impl MessageController {
                                 self.scene_state.zoom_to(oid);
                             }
                             RemoteCommand::LoadAllToGPU(pack_id) => {
-                                match pack_id {
-                                    0 => {
-                                        self.scene_state.set_hull_mesh0();
-                                        #[cfg(target_arch = "wasm32")]
-                                        on_load_to_gpu(0);
-                                    }
-                                    1 => {
-                                        self.scene_state.set_hull_mesh1();
-                                        #[cfg(target_arch = "wasm32")]
-                                        on_load_to_gpu(1);
-                                    }
-                                    2 => {
-                                        self.scene_state.set_hull_mesh2();
-                                        #[cfg(target_arch = "wasm32")]
-                                        on_load_to_gpu(2);
-                                    }
-                                    3 => {
-                                        self.scene_state.set_hull_mesh3();
-                                        #[cfg(target_arch = "wasm32")]
-                                        on_load_to_gpu(3);
-                                    }
-                                    4 => {
-                                        self.scene_state.set_hull_mesh4();
-                                        #[cfg(target_arch = "wasm32")]
-                                        on_load_to_gpu(4);
-                                    }
-                                    5 => {
-                                        self.scene_state.set_hull_mesh5();
-                                        #[cfg(target_arch = "wasm32")]
-                                        on_load_to_gpu(5);
-                                    }
-                                    6 => {
-                                        self.scene_state.set_hull_mesh6();
-                                        #[cfg(target_arch = "wasm32")]
-                                        on_load_to_gpu(6);
-                                    }
-                                    7 => {
-                                        self.scene_state.set_hull_mesh7();
-                                        #[cfg(target_arch = "wasm32")]
-                                        on_load_to_gpu(7);
-                                    }
-                                    _ => {}
-                                }
+                                self.load_pack(pack_id as u32);
+                            }
+                            RemoteCommand::UnloadPack(pack_id) => {
+                                self.unload_pack(pack_id as u32);
+                            }
+                            RemoteCommand::ReplacePack(pack_id) => {
+                                self.load_pack(pack_id as u32);
+                                self.is_state_dirty = true;
                             }
                             RemoteCommand::OnMouseMove((id, pos)) => {
                                 self.on_mouse_move(id, pos);
@@ -622,6 +749,88 @@ impl MessageController {
                             RemoteCommand::OnSetTransparentMat((alfa, mode)) => {
                                 self.set_transparent(alfa, mode);
                             }
+                            RemoteCommand::OnAssembly((action, node)) => {
+                                if (self.scene_state.assembly_action(action, &node)) {
+                                    self.is_state_dirty = true;
+                                }
+                            }
+                            RemoteCommand::HighlightCable(trace) => {
+                                self.show_cable_trace(trace);
+                            }
+                            RemoteCommand::ClearCableHighlight() => {
+                                self.scene_state.set_cable_trace(None);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::HighlightCablePath(trace) => {
+                                self.show_cable_trace(trace);
+                            }
+                            RemoteCommand::CableLoadColors(is_on) => {
+                                self.set_cable_load_colors(is_on);
+                            }
+                            RemoteCommand::SetClipPlanes(planes) => {
+                                self.scene_state.clipping.set_planes(planes);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::SetSectionBox(section_box) => {
+                                self.scene_state.clipping.set_section_box(section_box);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::RotateSectionBox((axis, degrees)) => {
+                                self.rotate_section_box(axis, degrees);
+                            }
+                            RemoteCommand::ClipPlaneFromCamera() => {
+                                self.clip_plane_from_camera();
+                            }
+                            RemoteCommand::SectionCaps(is_on) => {
+                                self.scene_state.section_caps.set_on(is_on);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::SectionCapStyle((ty, style)) => {
+                                self.scene_state.section_caps.set_style(Material::type_to_color(ty), style);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::Outline(is_on) => {
+                                self.scene_state.outline.set_on(is_on);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::OutlineStyle(style) => {
+                                self.scene_state.outline.set_style(style);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::Ssao(is_on) => {
+                                self.scene_state.ssao.set_on(is_on);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::SsaoStyle(style) => {
+                                self.scene_state.ssao.set_style(style);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::Sun(is_on) => {
+                                self.scene_state.sun.set_on(is_on);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::SunPosition((azimuth, elevation, intensity)) => {
+                                self.scene_state.sun.set(azimuth, elevation, intensity);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::LightTemperature(kelvin) => {
+                                self.scene_state.light_temperature.set(kelvin);
+                                self.is_state_dirty = true;
+                            }
+                            RemoteCommand::LightPreset(name) => {
+                                if (self.scene_state.light_temperature.set_preset(name.as_str())) {
+                                    self.is_state_dirty = true;
+                                }
+                            }
+                            RemoteCommand::ExportSection((plane, format)) => {
+                                let result = self.section(plane).map(|section| section.export(format));
+                                match &result {
+                                    Ok(_text) => {}
+                                    Err(e) => { warn!("SECTION: {}", e) }
+                                }
+                                #[cfg(target_arch = "wasm32")]
+                                on_section_done(&result);
+                            }
                         }
                     }
                 }
@@ -653,18 +862,36 @@ impl MessageController {
         match REMOTE_HULL_MESH.try_lock() {
             Ok(mut hm) => {
                 if hm.is_dirty {
-                    self.scene_state.set_hull_mesh_remote(
-                        hm.load_level.clone(),
-                        hm.decoded_v.as_slice(),
-                        hm.decoded_i.as_slice(),
-                        hm.decoded_b.as_slice(),
-                        hm.decoded_t.as_slice(),
-                    );
+                    match self.scene_state.set_hull_mesh_remote(hm.load_level.clone(), &hm.streams) {
+                        Ok(()) => {}
+                        Err(e) => { warn!("CANT LOAD REMOTE HULL PACK {}: {}", hm.load_level, e) }
+                    }
                     hm.clean();
                 }
             }
             Err(_) => {}
         }
+
+        //events are taken out, so JS can push chunks while they are decoded
+        let events: VecDeque<PackStreamEvent> = match REMOTE_PACK_STREAM.try_lock() {
+            Ok(mut m) => std::mem::take(&mut m.values),
+            Err(_) => VecDeque::new(),
+        };
+        events.into_iter().for_each(|event| {
+            match event {
+                PackStreamEvent::Begin => { self.scene_state.begin_pack_upload(); }
+                PackStreamEvent::Chunk(chunk) => { self.scene_state.push_pack_upload(chunk.as_slice()); }
+                PackStreamEvent::End => {
+                    let result = self.scene_state.finish_pack_upload();
+                    match &result {
+                        Ok(_pack_id) => { self.is_state_dirty = true; }
+                        Err(e) => { warn!("CANT LOAD STREAMED HULL PACK: {}", e) }
+                    }
+                    #[cfg(target_arch = "wasm32")]
+                    on_pack_stream_done(&result);
+                }
+            }
+        });
     }
     pub fn reset_material_dirty(&mut self) {
         self.is_materials_dirty = false;
@@ -672,12 +899,172 @@ impl MessageController {
     pub fn get_mouse_pos(&self) -> PhysicalPosition<f64> {
         self.scene_state.camera.get_mouse_pos(self.window_state.read().get_scale_factor())
     }
+    //JS hears back from on_load_to_gpu or, with the LoadError, from on_load_error
+    pub fn load_pack(&mut self, pack_id: u32) {
+        match self.scene_state.set_hull_mesh(pack_id) {
+            Ok(()) => {
+                #[cfg(target_arch = "wasm32")]
+                on_load_to_gpu(pack_id as i32);
+            }
+            Err(e) => {
+                warn!("CANT LOAD HULL PACK {}: {}", pack_id, e);
+                #[cfg(target_arch = "wasm32")]
+                on_load_error(pack_id as i32, e.to_js_value());
+            }
+        }
+    }
+    pub fn unload_pack(&mut self, pack_id: u32) {
+        if (self.scene_state.unload_pack(pack_id)) {
+            self.shared_buffers.remove_metadata(pack_id);
+            if (self.active_pack_id == pack_id) {
+                self.active_id = 0;
+            }
+            self.is_state_dirty = true;
+        } else {
+            warn!("HULL PACK {} IS NOT LOADED", pack_id);
+        }
+    }
+    //builds or unloads the cable pack when the network is set from outside
+    fn check_cable_network(&mut self) {
+        match CABLE_NETWORK.try_lock() {
+            Ok(mut m) => {
+                if (m.is_dirty) {
+                    m.is_dirty = false;
+                    //colours of the old segments go with their pack
+                    self.is_cable_load_on = false;
+                    match &m.network {
+                        None => { self.unload_pack(CABLE_PACK_ID); }
+                        Some(network) => {
+                            self.scene_state.set_cable_network(network);
+                            self.is_state_dirty = true;
+                        }
+                    }
+                }
+            }
+            Err(_e) => { warn!("CANT LOCK CABLE_NETWORK MEM") }
+        }
+    }
+    //traces the cable over the loaded network, lights its route and frames the camera on it,
+    //returns the routed length in mm, None if there is no such cable
+    pub fn highlight_cable(&mut self, cable: i32) -> Option<f64> {
+        let trace = match CABLE_NETWORK.lock() {
+            Ok(m) => m.network.as_ref().and_then(|n| n.trace_cable(cable)),
+            Err(_e) => {
+                warn!("CANT LOCK CABLE_NETWORK MEM");
+                None
+            }
+        };
+        match trace {
+            None => {
+                warn!("CABLE {} IS NOT FOUND", cable);
+                None
+            }
+            Some(trace) => {
+                warn!("CABLE {} NODES {} LENGTH {:.0} MM", cable, trace.nodes.len(), trace.length);
+                Some(self.show_cable_trace(trace))
+            }
+        }
+    }
+    //lights a route traced before, returns its length in mm
+    pub fn show_cable_trace(&mut self, trace: CableTrace) -> f64 {
+        let length = trace.length;
+        self.scene_state.zoom_to_bbx(&trace.bbx);
+        self.scene_state.set_cable_trace(Some(trace));
+        self.is_state_dirty = true;
+        length
+    }
+    //colours the segments of the loaded network by the number of cables routed through them
+    pub fn set_cable_load_colors(&mut self, is_on: bool) {
+        let colors: HashMap<i32, i32> = match CABLE_NETWORK.lock() {
+            Ok(m) => m.network.as_ref().map_or(HashMap::new(), |n| n.load_colors()),
+            Err(_e) => {
+                warn!("CANT LOCK CABLE_NETWORK MEM");
+                HashMap::new()
+            }
+        };
+        if (is_on) {
+            self.scene_state.color_by_ids(colors);
+        } else {
+            self.scene_state.clear_color_by_ids(colors.into_keys().collect());
+        }
+        self.is_cable_load_on = is_on;
+        self.is_state_dirty = true;
+    }
+    //adds a plane through the active point, or the scene center if nothing is picked, facing away from the camera
+    pub fn clip_plane_from_camera(&mut self) -> bool {
+        let point = if (self.active_point.x < f32::max_value()) {
+            self.active_point
+        } else if (self.scene_state.tot_bbx.is_empty()) {
+            warn!("CLIPPING: NOTHING IS LOADED");
+            return false;
+        } else {
+            let c = self.scene_state.tot_bbx.center();
+            Point3::new(c.x as f32, c.y as f32, c.z as f32)
+        };
+        let forward = *self.scene_state.camera.head_forward.read();
+        match ClipPlane::new(point, forward) {
+            None => {
+                warn!("CLIPPING: NO VIEW DIRECTION FOR A PLANE");
+                false
+            }
+            Some(plane) => {
+                let is_added = self.scene_state.clipping.add_plane(plane);
+                self.is_state_dirty = true;
+                is_added
+            }
+        }
+    }
+    //cut of the visible parts of all packs, by the plane of the slicer if none is given
+    pub fn section(&self, plane: Option<ClipPlane>) -> Result<Section, String> {
+        let plane = match plane.or_else(|| Section::plane_from_slicer(&self.scene_state.slicer)) {
+            None => return Err("NO PLANE, MOVE A SLICER SENSOR OR GIVE ONE".to_string()),
+            Some(p) => p,
+        };
+        let mut section = Section::new(plane);
+        self.scene_state.gpu_mems.values().for_each(|m| section.add_pack(m));
+        if (section.is_empty()) {
+            warn!("SECTION: NOTHING IS CUT BY {:?}", plane);
+        }
+        Ok(section)
+    }
+    //section.svg and section.dxf in the working directory
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_section(&self, plane: Option<ClipPlane>) {
+        match self.section(plane) {
+            Err(e) => { warn!("SECTION: {}", e) }
+            Ok(section) => {
+                [SectionFormat::Svg, SectionFormat::Dxf].iter().for_each(|format| {
+                    let path = format!("section.{}", format.extension());
+                    match std::fs::write(&path, section.export(*format)) {
+                        Ok(()) => { warn!("SECTION SAVED TO {}", path) }
+                        Err(e) => { warn!("CANT SAVE SECTION TO {}: {}", path, e) }
+                    }
+                });
+            }
+        }
+    }
+    pub fn rotate_section_box(&mut self, axis: Vector3<f32>, degrees: f32) {
+        let section_box = match self.scene_state.clipping.section_box {
+            None if (self.scene_state.tot_bbx.is_empty()) => {
+                warn!("CLIPPING: NOTHING IS LOADED");
+                return;
+            }
+            None => SectionBox::from_bbx(&self.scene_state.tot_bbx),
+            Some(b) => b,
+        };
+        self.scene_state.clipping.set_section_box(Some(section_box.rotated(axis, Deg(degrees))));
+        self.is_state_dirty = true;
+    }
     pub fn set_pack_id(&mut self, active_pack_id: u32) {
         self.active_pack_id = active_pack_id
     }
     pub fn get_pack_id(&self) -> u32 {
         self.active_pack_id
     }
+    //the translucent pass is skipped while every material is opaque, 0 is the hidden one
+    pub fn has_translucent_materials(&self) -> bool {
+        self.materials.iter().skip(1).any(|m| m.color[3] < 1.0)
+    }
     fn set_transparent(&mut self, alfa: i32, mode: i32) {
         match mode {
             0 => {
@@ -16,6 +16,8 @@ use crate::device::window_state::WindowState;
 use crate::gui::camera_base::CameraMode;
 use crate::remote::common_state::COMMANDS;
 use crate::remote::RemoteCommand;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scene::pack_format::HullPackFiles;
 use crate::shared::text_layout::TextLayout;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -29,11 +31,12 @@ pub struct MState {
     message_controller: Option<Rc<RwLock<MessageController>>>,
     device_state: Option<Rc<RwLock<DeviceState>>>,
     counter: i32,
+    hull_pack_files: Vec<HullPackFiles>,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 impl MState {
-    pub fn new() -> Self {
+    pub fn new(hull_pack_files: Vec<HullPackFiles>) -> Self {
         let (instance, adapter, device, queue): (Rc<RwLock<Instance>>, Rc<RwLock<Adapter>>, Rc<RwLock<Device>>, Rc<RwLock<Queue>>) = {
             let mut limits: Limits = Limits::default();
             limits.max_buffer_size = (134217728) * 20;//128*20=2560 MB
@@ -93,6 +96,7 @@ impl MState {
             message_controller: None,
             device_state: None,
             counter: 0,
+            hull_pack_files: hull_pack_files,
         }
     }
 }
@@ -113,7 +117,8 @@ impl ApplicationHandler for MState {
             None)));
 
         let text_layout: Rc<RwLock<TextLayout>> = Rc::new(RwLock::new(TextLayout::new(self.device.clone(), self.queue.clone(), _window_state.read().config.clone())));
-        let message_controller: Rc<RwLock<MessageController>> = Rc::new(RwLock::new(MessageController::new(self.device.clone(), _window_state.clone(), text_layout.clone())));
+        let message_controller: Rc<RwLock<MessageController>> = Rc::new(RwLock::new(MessageController::new(self.device.clone(), self.queue.clone(), _window_state.clone(), text_layout.clone())));
+        message_controller.write().scene_state.hull_pack_files = self.hull_pack_files.clone();
 
         let device_state: Rc<RwLock<DeviceState>> = Rc::new(RwLock::new(
             DeviceState::new(
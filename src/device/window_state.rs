@@ -13,6 +13,9 @@ use winit::window::{CursorGrabMode, Window};
 use crate::device::device_state::DeviceState;
 use crate::shared::highlight_pipeline::HighlightPipeLine;
 use crate::shared::mesh_pipeline::MeshPipeLine;
+use crate::shared::oit_pipeline::OitPipeLine;
+use crate::shared::outline_pipeline::OutlinePipeLine;
+use crate::shared::ssao_pipeline::SsaoPipeLine;
 
 #[derive(PartialEq)]
 pub enum WindowMode {
@@ -27,6 +30,9 @@ pub struct WindowState {
     pub config: SurfaceConfiguration,
     pub mesh_pipeline: RwLock<MeshPipeLine>,
     pub highlight_pipeline: RwLock<HighlightPipeLine>,
+    pub outline_pipeline: RwLock<OutlinePipeLine>,
+    pub ssao_pipeline: RwLock<SsaoPipeLine>,
+    pub oit_pipeline: RwLock<OitPipeLine>,
     pub window_mode: WindowMode,
     queue: Rc<RwLock<Queue>>,
     pub smaa_target: Rc<RwLock<SmaaTarget>> ,
@@ -91,6 +97,9 @@ impl WindowState {
 
         let mesh_pipeline: RwLock<MeshPipeLine> = RwLock::new(MeshPipeLine::new(device.clone(), format.clone()));
         let highlight_pipeline: RwLock<HighlightPipeLine> = RwLock::new(HighlightPipeLine::new(device.clone(), format.clone()));
+        let outline_pipeline: RwLock<OutlinePipeLine> = RwLock::new(OutlinePipeLine::new(device.clone(), format.clone()));
+        let ssao_pipeline: RwLock<SsaoPipeLine> = RwLock::new(SsaoPipeLine::new(device.clone(), format.clone()));
+        let oit_pipeline: RwLock<OitPipeLine> = RwLock::new(OitPipeLine::new(device.clone(), format.clone()));
         Self {
             window: window,
             canvas: canvas,
@@ -98,6 +107,9 @@ impl WindowState {
             config: config,
             mesh_pipeline: mesh_pipeline,
             highlight_pipeline: highlight_pipeline,
+            outline_pipeline: outline_pipeline,
+            ssao_pipeline: ssao_pipeline,
+            oit_pipeline: oit_pipeline,
             window_mode: WindowMode::CursorVisible,
             queue: queue,
             smaa_target:Rc::new(RwLock::new(smaa_target)) ,
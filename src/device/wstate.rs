@@ -157,7 +157,7 @@ impl ApplicationHandler for WState {
             Some(canvas))));
 
         let text_layout: Rc<RwLock<TextLayout>> = Rc::new(RwLock::new(TextLayout::new(self.device.clone(), self.queue.clone(), _window_state.read().config.clone())));
-        let message_controller: Rc<RwLock<MessageController>> = Rc::new(RwLock::new(MessageController::new(self.device.clone(), _window_state.clone(), text_layout.clone())));
+        let message_controller: Rc<RwLock<MessageController>> = Rc::new(RwLock::new(MessageController::new(self.device.clone(), self.queue.clone(), _window_state.clone(), text_layout.clone())));
 
         let device_state: Rc<RwLock<DeviceState>> = Rc::new(RwLock::new(
             DeviceState::new(
@@ -0,0 +1,193 @@
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Basis3, Deg, EuclideanSpace, InnerSpace, Point3, Rotation, Rotation3, Vector3};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use truck_base::bounding_box::BoundingBox;
+
+//size of clip_planes in the Slice uniform of shader.wgsl, selection.wgsl and snap.wgsl
+pub const MAX_CLIP_PLANES: usize = 8;
+//ClipUniform goes right after the six axis planes of the Slicer and the two spare floats
+pub const CLIP_UNIFORM_OFFSET: u64 = 32;
+
+//cut by a plane through point, the side the normal points to is kept
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClipPlane {
+    pub point: Point3<f32>,
+    pub normal: Vector3<f32>,
+}
+
+impl ClipPlane {
+    //None if the normal has no direction
+    pub fn new(point: Point3<f32>, normal: Vector3<f32>) -> Option<Self> {
+        let l = normal.magnitude();
+        if (!(l > f32::EPSILON) || !l.is_finite() || !(point.x.is_finite() && point.y.is_finite() && point.z.is_finite())) {
+            return None;
+        }
+        Some(Self {
+            point: point,
+            normal: normal / l,
+        })
+    }
+    pub fn is_clipped(&self, p: Point3<f32>) -> bool {
+        self.normal.dot(p - self.point) < 0.0
+    }
+    //px, py, pz, nx, ny, nz per plane, planes with no normal are skipped
+    pub fn from_floats(values: &[f32]) -> Vec<Self> {
+        if (values.len() % 6 != 0) {
+            warn!("CLIPPING: {} VALUES ARE NOT (POINT, NORMAL) SIXES", values.len());
+        }
+        values.chunks_exact(6).filter_map(|c| {
+            let plane = ClipPlane::new(Point3::new(c[0], c[1], c[2]), Vector3::new(c[3], c[4], c[5]));
+            if (plane.is_none()) {
+                warn!("CLIPPING: PLANE {:?} HAS NO NORMAL", c);
+            }
+            plane
+        }).collect()
+    }
+    //normal and the offset of the plane equation, a fragment is kept while dot(normal, p) + w >= 0
+    fn to_uniform(&self) -> [f32; 4] {
+        [self.normal.x, self.normal.y, self.normal.z, -self.normal.dot(self.point.to_vec())]
+    }
+}
+
+//box with its own orthonormal axes, everything outside is cut
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectionBox {
+    pub center: Point3<f32>,
+    pub axes: [Vector3<f32>; 3],
+    pub half_sizes: [f32; 3],
+}
+
+impl SectionBox {
+    //y_dir is turned to be square to x_dir, None if they are parallel or a size is not positive
+    pub fn new(center: Point3<f32>, x_dir: Vector3<f32>, y_dir: Vector3<f32>, half_sizes: [f32; 3]) -> Option<Self> {
+        if (!half_sizes.iter().all(|h| *h > 0.0 && h.is_finite()) || !(x_dir.magnitude() > f32::EPSILON)) {
+            return None;
+        }
+        let x = x_dir.normalize();
+        let z = x.cross(y_dir);
+        if (!(z.magnitude() > f32::EPSILON)) {
+            return None;
+        }
+        let z = z.normalize();
+        Some(Self {
+            center: center,
+            axes: [x, z.cross(x), z],
+            half_sizes: half_sizes,
+        })
+    }
+    //center, x axis, y axis and half sizes, 12 values
+    pub fn from_floats(values: &[f32]) -> Option<Self> {
+        if (values.len() != 12) {
+            warn!("CLIPPING: SECTION BOX NEEDS 12 VALUES, GOT {}", values.len());
+            return None;
+        }
+        let v = |k: usize| Vector3::new(values[k], values[k + 1], values[k + 2]);
+        SectionBox::new(Point3::from_vec(v(0)), v(3), v(6), [values[9], values[10], values[11]])
+    }
+    pub fn from_bbx(bbx: &BoundingBox<Point3<f64>>) -> Self {
+        let min = bbx.min();
+        let max = bbx.max();
+        Self {
+            center: Point3::new(((min.x + max.x) / 2.0) as f32, ((min.y + max.y) / 2.0) as f32, ((min.z + max.z) / 2.0) as f32),
+            axes: [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()],
+            half_sizes: [((max.x - min.x) / 2.0) as f32, ((max.y - min.y) / 2.0) as f32, ((max.z - min.z) / 2.0) as f32],
+        }
+    }
+    //turned around its center, counter clockwise seen from the axis end
+    pub fn rotated(&self, axis: Vector3<f32>, angle: Deg<f32>) -> Self {
+        if (!(axis.magnitude() > f32::EPSILON)) {
+            return *self;
+        }
+        let r: Basis3<f32> = Basis3::from_axis_angle(axis.normalize(), angle);
+        Self {
+            center: self.center,
+            axes: self.axes.map(|a| r.rotate_vector(a)),
+            half_sizes: self.half_sizes,
+        }
+    }
+    pub fn is_clipped(&self, p: Point3<f32>) -> bool {
+        let d = p - self.center;
+        (0..3).any(|k| self.axes[k].dot(d).abs() > self.half_sizes[k])
+    }
+}
+
+//tail of the Slice uniform, see clip_planes in shader.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct ClipUniform {
+    //x is the number of planes, y is 1 if the section box is on
+    pub count: [i32; 4],
+    pub planes: [[f32; 4]; MAX_CLIP_PLANES],
+    pub box_center: [f32; 4],
+    //unit axes with the half size in w
+    pub box_axes: [[f32; 4]; 3],
+}
+
+pub struct Clipping {
+    pub planes: Vec<ClipPlane>,
+    pub section_box: Option<SectionBox>,
+    pub is_dirty: bool,
+}
+
+impl Clipping {
+    pub fn new() -> Self {
+        Self {
+            planes: vec![],
+            section_box: None,
+            is_dirty: true,
+        }
+    }
+    //planes over MAX_CLIP_PLANES are dropped
+    pub fn set_planes(&mut self, mut planes: Vec<ClipPlane>) {
+        if (planes.len() > MAX_CLIP_PLANES) {
+            warn!("CLIPPING: {} PLANES, ONLY {} ARE USED", planes.len(), MAX_CLIP_PLANES);
+            planes.truncate(MAX_CLIP_PLANES);
+        }
+        self.planes = planes;
+        self.is_dirty = true;
+    }
+    //false if there is no room for it
+    pub fn add_plane(&mut self, plane: ClipPlane) -> bool {
+        if (self.planes.len() >= MAX_CLIP_PLANES) {
+            warn!("CLIPPING: NO ROOM FOR MORE THAN {} PLANES", MAX_CLIP_PLANES);
+            return false;
+        }
+        self.planes.push(plane);
+        self.is_dirty = true;
+        true
+    }
+    pub fn set_section_box(&mut self, section_box: Option<SectionBox>) {
+        self.section_box = section_box;
+        self.is_dirty = true;
+    }
+    pub fn clear(&mut self) {
+        self.planes.clear();
+        self.section_box = None;
+        self.is_dirty = true;
+    }
+    pub fn is_active(&self) -> bool {
+        !self.planes.is_empty() || self.section_box.is_some()
+    }
+    //same test as clipped() of the shaders, without the axis planes of the Slicer
+    pub fn is_clipped(&self, p: Point3<f32>) -> bool {
+        self.planes.iter().any(|pl| pl.is_clipped(p)) || self.section_box.map_or(false, |b| b.is_clipped(p))
+    }
+    pub fn to_uniform(&self) -> ClipUniform {
+        let mut u = ClipUniform::zeroed();
+        u.count = [self.planes.len() as i32, 0, 0, 0];
+        self.planes.iter().enumerate().for_each(|(k, pl)| u.planes[k] = pl.to_uniform());
+        match &self.section_box {
+            None => {}
+            Some(b) => {
+                u.count[1] = 1;
+                u.box_center = [b.center.x, b.center.y, b.center.z, 1.0];
+                u.box_axes = [0, 1, 2].map(|k| [b.axes[k].x, b.axes[k].y, b.axes[k].z, b.half_sizes[k]]);
+            }
+        }
+        u
+    }
+    pub fn reset_dirty(&mut self) {
+        self.is_dirty = false;
+    }
+}
@@ -0,0 +1,114 @@
+use log::warn;
+use once_cell::sync::Lazy;
+
+use crate::gui::section_caps::srgb_to_linear;
+
+//kelvin -> sRGB of a black body, 1000 K to 12000 K in steps of 100 K
+static KELVIN_TABLE: Lazy<Vec<(f32, [f32; 3])>> = Lazy::new(|| parse_kelvin_table(include_str!("../../assets/kelvin_table.txt")));
+
+//named light sources, kelvin
+pub const LIGHT_PRESETS: [(&str, f32); 6] = [
+    ("sodium", 2100.0),
+    ("incandescent", 2700.0),
+    ("halogen", 3200.0),
+    ("fluorescent", 4100.0),
+    ("daylight", 6500.0),
+    ("overcast", 7500.0),
+];
+
+//lines of "1000: (255, 56, 0)," between brackets, sorted by kelvin
+fn parse_kelvin_table(text: &str) -> Vec<(f32, [f32; 3])> {
+    let mut table: Vec<(f32, [f32; 3])> = vec![];
+    text.lines().map(|l| l.trim().trim_end_matches(',')).filter(|l| !l.is_empty() && *l != "[" && *l != "]").for_each(|l| {
+        let entry = l.split_once(':').and_then(|(k, rgb)| {
+            let kelvin = k.trim().parse::<f32>().ok()?;
+            let c: Vec<f32> = rgb.trim().trim_start_matches('(').trim_end_matches(')').split(',').filter_map(|v| v.trim().parse::<u8>().ok()).map(|v| v as f32 / 255.0).collect();
+            match c.as_slice() {
+                [r, g, b] => Some((kelvin, [*r, *g, *b])),
+                _ => None
+            }
+        });
+        match entry {
+            None => { warn!("KELVIN TABLE: CANT READ {}", l) }
+            Some(e) => { table.push(e) }
+        }
+    });
+    table.sort_by(|a, b| a.0.total_cmp(&b.0));
+    table
+}
+
+//colour temperature of the eye light, white while it is not set
+pub struct LightTemperature {
+    pub kelvin: Option<f32>,
+}
+
+impl LightTemperature {
+    pub fn new() -> Self {
+        Self {
+            kelvin: None,
+        }
+    }
+    //None is white, kelvin out of the table is clamped to it
+    pub fn set(&mut self, kelvin: Option<f32>) {
+        self.kelvin = kelvin.filter(|k| k.is_finite() && *k > 0.0);
+    }
+    pub fn set_preset(&mut self, name: &str) -> bool {
+        match LIGHT_PRESETS.iter().find(|(n, _k)| n.eq_ignore_ascii_case(name)) {
+            None => {
+                warn!("LIGHT: NO PRESET {}", name);
+                false
+            }
+            Some((_n, k)) => {
+                self.kelvin = Some(*k);
+                true
+            }
+        }
+    }
+    //next preset after the current one, then white
+    pub fn next_preset(&mut self) {
+        self.kelvin = match self.kelvin {
+            None => Some(LIGHT_PRESETS[0].1),
+            Some(k) => LIGHT_PRESETS.iter().map(|(_n, pk)| *pk).find(|pk| *pk > k)
+        };
+    }
+    pub fn name(&self) -> String {
+        match self.kelvin {
+            None => "white".to_string(),
+            Some(k) => match LIGHT_PRESETS.iter().find(|(_n, pk)| *pk == k) {
+                None => format!("{} K", k),
+                Some((n, _pk)) => n.to_string(),
+            }
+        }
+    }
+    //linear rgb for the light uniform
+    pub fn color(&self) -> [f32; 4] {
+        match self.kelvin {
+            None => [1.0, 1.0, 1.0, 1.0],
+            Some(k) => {
+                let c = kelvin_to_srgb(k);
+                [srgb_to_linear(c[0]), srgb_to_linear(c[1]), srgb_to_linear(c[2]), 1.0]
+            }
+        }
+    }
+}
+
+//interpolated between the two nearest rows of the table
+pub fn kelvin_to_srgb(kelvin: f32) -> [f32; 3] {
+    let table = &*KELVIN_TABLE;
+    match (table.first(), table.last()) {
+        (Some(first), Some(last)) => {
+            if (kelvin <= first.0) {
+                return first.1;
+            }
+            if (kelvin >= last.0) {
+                return last.1;
+            }
+            let i = table.partition_point(|e| e.0 <= kelvin);
+            let (k0, c0) = table[i - 1];
+            let (k1, c1) = table[i];
+            let t = (kelvin - k0) / (k1 - k0);
+            [c0[0] + (c1[0] - c0[0]) * t, c0[1] + (c1[1] - c0[1]) * t, c0[2] + (c1[2] - c0[2]) * t]
+        }
+        _ => [1.0, 1.0, 1.0]
+    }
+}
@@ -4,5 +4,11 @@ pub mod camera_fly;
 pub mod camera_orbit;
 pub mod camera_touch;
 pub mod slicer;
+pub mod clipping;
+pub mod section_caps;
+pub mod outline;
+pub mod ssao;
+pub mod sun;
+pub mod light_temperature;
 
 
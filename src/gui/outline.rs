@@ -0,0 +1,54 @@
+use bytemuck::{Pod, Zeroable};
+
+use crate::gui::section_caps::srgb_to_linear;
+
+//see OutlineStyle in outline.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct OutlineStyle {
+    pub color: [f32; 4],
+    //line width in px, cosine of the crease angle, depth jump as a part of the distance
+    pub params: [f32; 4],
+}
+
+impl OutlineStyle {
+    //edges between faces turned by more than crease_deg are drawn, width is clamped to 1..8 px
+    pub fn new(color: [f32; 4], width: f32, crease_deg: f32) -> Self {
+        Self {
+            color: color,
+            params: [width.clamp(1.0, 8.0), crease_deg.clamp(1.0, 179.0).to_radians().cos(), 0.02, 0.0],
+        }
+    }
+    //0xRRGGBB in sRGB
+    pub fn from_rgb(rgb: u32, width: f32, crease_deg: f32) -> Self {
+        let c = |shift: u32| srgb_to_linear(((rgb >> shift) & 0xff) as f32 / 255.0);
+        OutlineStyle::new([c(16), c(8), c(0), 1.0], width, crease_deg)
+    }
+}
+
+//silhouettes, part borders and creases drawn over the hull so parts of one colour stay apart
+pub struct Outline {
+    pub is_on: bool,
+    pub style: OutlineStyle,
+    pub is_dirty: bool,
+}
+
+impl Outline {
+    pub fn new() -> Self {
+        Self {
+            is_on: false,
+            style: OutlineStyle::new([0.02, 0.02, 0.02, 1.0], 1.0, 30.0),
+            is_dirty: true,
+        }
+    }
+    pub fn set_on(&mut self, is_on: bool) {
+        self.is_on = is_on;
+    }
+    pub fn set_style(&mut self, style: OutlineStyle) {
+        self.style = style;
+        self.is_dirty = true;
+    }
+    pub fn reset_dirty(&mut self) {
+        self.is_dirty = false;
+    }
+}
@@ -0,0 +1,99 @@
+use bytemuck::{Pod, Zeroable};
+use log::warn;
+
+use crate::shared::materials_lib::{Material, MATERIALS_COUNT, TY_HULL_OTHERS, TY_HULL_OUTERPLATES, TY_HULL_PLATES, TY_HULL_PROFILES};
+
+//caps are the back faces seen through a cut, how they are filled, see fs_cap in shader.wgsl
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CapHatch {
+    Solid = 0,
+    Lines = 1,
+    Cross = 2,
+}
+
+impl CapHatch {
+    pub fn from_code(code: i32) -> Option<CapHatch> {
+        match code {
+            0 => Some(CapHatch::Solid),
+            1 => Some(CapHatch::Lines),
+            2 => Some(CapHatch::Cross),
+            _ => None,
+        }
+    }
+}
+
+//one per material, so a selected or coloured part shows it on its caps too
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct CapStyle {
+    pub color: [f32; 4],
+    //hatch code, spacing and line width in px, angle in radians
+    pub hatch: [f32; 4],
+}
+
+impl CapStyle {
+    pub fn new(color: [f32; 4], hatch: CapHatch, spacing: f32, angle_deg: f32) -> Self {
+        Self {
+            color: color,
+            hatch: [hatch as i32 as f32, spacing.max(2.0), angle_deg.to_radians(), 1.0],
+        }
+    }
+    //0xRRGGBB in sRGB like the palette of materials_lib
+    pub fn from_rgb(rgb: u32, hatch: CapHatch, spacing: f32, angle_deg: f32) -> Self {
+        let c = |shift: u32| srgb_to_linear(((rgb >> shift) & 0xff) as f32 / 255.0);
+        CapStyle::new([c(16), c(8), c(0), 1.0], hatch, spacing, angle_deg)
+    }
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if (c <= 0.04045) { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+pub struct SectionCaps {
+    pub is_on: bool,
+    pub styles: Vec<CapStyle>,
+    pub is_dirty: bool,
+}
+
+impl SectionCaps {
+    //a darker solid of the material colour, hull plates and profiles are hatched like a drawing
+    pub fn new() -> Self {
+        let mut styles: Vec<CapStyle> = Material::generate_materials().iter().map(|m| {
+            CapStyle::new([m.color[0] * 0.6, m.color[1] * 0.6, m.color[2] * 0.6, 1.0], CapHatch::Solid, 8.0, 45.0)
+        }).collect();
+        styles.resize(MATERIALS_COUNT, CapStyle::zeroed());
+        let mut hatched = |material: i32, hatch: CapHatch, angle_deg: f32| {
+            let color = styles[material as usize].color;
+            styles[material as usize] = CapStyle::new(color, hatch, 8.0, angle_deg);
+        };
+        hatched(TY_HULL_PLATES, CapHatch::Lines, 45.0);
+        hatched(TY_HULL_OUTERPLATES, CapHatch::Cross, 45.0);
+        hatched(TY_HULL_PROFILES, CapHatch::Lines, -45.0);
+        hatched(TY_HULL_OTHERS, CapHatch::Lines, 0.0);
+        Self {
+            is_on: false,
+            styles: styles,
+            is_dirty: true,
+        }
+    }
+    pub fn set_on(&mut self, is_on: bool) {
+        self.is_on = is_on;
+    }
+    //false if there is no such material
+    pub fn set_style(&mut self, material_index: i32, style: CapStyle) -> bool {
+        match self.styles.get_mut(material_index as usize).filter(|_s| material_index >= 0) {
+            None => {
+                warn!("SECTION CAPS: NO MATERIAL {}", material_index);
+                false
+            }
+            Some(s) => {
+                *s = style;
+                self.is_dirty = true;
+                true
+            }
+        }
+    }
+    pub fn reset_dirty(&mut self) {
+        self.is_dirty = false;
+    }
+}
@@ -66,8 +66,8 @@ impl Slicer {
     }
     pub fn slice_positions(&self) -> (f32, f32, f32, f32, f32, f32) {
         let dx = (self.x_max_plane - self.x_min_plane).abs();
-        let dy = (self.x_max_plane - self.x_min_plane).abs();
-        let dz = (self.x_max_plane - self.x_min_plane).abs();
+        let dy = (self.y_max_plane - self.y_min_plane).abs();
+        let dz = (self.z_max_plane - self.z_min_plane).abs();
 
         let pos_x_max = self.x_max_plane - (dx * (1.0 - self.x_max_sensor));
         let pos_x_min = self.x_min_plane + (dx * self.x_min_sensor);
@@ -95,6 +95,12 @@ impl Slicer {
         self.z_min_plane = min.z as f32;
         self.is_dirty = true
     }
+    //true if a sensor is moved in from the scene bbox
+    pub fn is_cutting(&self) -> bool {
+        self.x_max_sensor < 1.0 || self.x_min_sensor > 0.0
+            || self.y_max_sensor < 1.0 || self.y_min_sensor > 0.0
+            || self.z_max_sensor < 1.0 || self.z_min_sensor > 0.0
+    }
     pub fn reset_dirty(&mut self) {
         self.is_dirty = false;
     }
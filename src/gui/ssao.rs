@@ -0,0 +1,38 @@
+use bytemuck::{Pod, Zeroable};
+
+//see params of SsaoUniforms in ssao.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct SsaoStyle {
+    //radius in model units (mm), strength 0..4, blur radius in px 0..4, depth bias as a part of the radius
+    pub params: [f32; 4],
+}
+
+impl SsaoStyle {
+    pub fn new(radius: f32, strength: f32, blur: f32) -> Self {
+        Self {
+            params: [radius.max(1.0), strength.clamp(0.0, 4.0), blur.clamp(0.0, 4.0).round(), 0.05],
+        }
+    }
+}
+
+//ambient occlusion darkens corners and narrow spaces between plates, double bottom and engine room get depth cues
+pub struct Ssao {
+    pub is_on: bool,
+    pub style: SsaoStyle,
+}
+
+impl Ssao {
+    pub fn new() -> Self {
+        Self {
+            is_on: false,
+            style: SsaoStyle::new(600.0, 1.0, 2.0),
+        }
+    }
+    pub fn set_on(&mut self, is_on: bool) {
+        self.is_on = is_on;
+    }
+    pub fn set_style(&mut self, style: SsaoStyle) {
+        self.style = style;
+    }
+}
@@ -0,0 +1,88 @@
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Angle, Deg, InnerSpace, Matrix4, ortho, Point3, SquareMatrix, Vector3};
+use truck_base::bounding_box::BoundingBox;
+
+//side of the square shadow map in texels
+pub const SHADOW_MAP_SIZE: u32 = 2048;
+
+//cgmath clip depth is -1..1, wgpu keeps 0..1
+const OPENGL_TO_WGPU: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+//see Sun in shader.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct SunUniform {
+    pub light_vp: [[f32; 4]; 4],
+    //xyz toward the sun, w 1 if it is on
+    pub direction: [f32; 4],
+    //rgb times intensity
+    pub color: [f32; 4],
+    //texel size of the shadow map, depth bias
+    pub params: [f32; 4],
+}
+
+//directional light over the whole scene, lights and shadows deck structures besides the eye light
+pub struct Sun {
+    pub is_on: bool,
+    //degrees from the bow (x) turning to port (y)
+    pub azimuth: f32,
+    //degrees above the deck plane
+    pub elevation: f32,
+    pub intensity: f32,
+}
+
+impl Sun {
+    pub fn new() -> Self {
+        Self {
+            is_on: false,
+            azimuth: 135.0,
+            elevation: 45.0,
+            intensity: 0.7,
+        }
+    }
+    pub fn set_on(&mut self, is_on: bool) {
+        self.is_on = is_on;
+    }
+    //elevation is kept above the horizon, intensity 0..2
+    pub fn set(&mut self, azimuth: f32, elevation: f32, intensity: f32) {
+        self.azimuth = azimuth.rem_euclid(360.0);
+        self.elevation = elevation.clamp(1.0, 90.0);
+        self.intensity = intensity.clamp(0.0, 2.0);
+    }
+    //unit vector toward the sun
+    pub fn direction(&self) -> Vector3<f32> {
+        let (az, el) = (Deg(self.azimuth), Deg(self.elevation));
+        Vector3::new(el.cos() * az.cos(), el.cos() * az.sin(), el.sin())
+    }
+    //orthographic view along the sun that holds the bounding sphere of the scene
+    pub fn light_vp(&self, tot_bbx: &BoundingBox<Point3<f64>>) -> Option<Matrix4<f32>> {
+        let radius = (tot_bbx.diagonal().magnitude() / 2.0) as f32;
+        if (!radius.is_normal()) {
+            return None;
+        }
+        let c = tot_bbx.center();
+        let center = Point3::new(c.x as f32, c.y as f32, c.z as f32);
+        let dir = self.direction();
+        let up = if (dir.z.abs() > 0.99) { Vector3::unit_x() } else { Vector3::unit_z() };
+        let view = Matrix4::look_at_rh(center + dir * radius * 2.0, center, up);
+        let proj = ortho(-radius, radius, -radius, radius, radius * 0.5, radius * 3.5);
+        Some(OPENGL_TO_WGPU * proj * view)
+    }
+    //off while the scene is empty
+    pub fn uniform(&self, tot_bbx: &BoundingBox<Point3<f64>>) -> SunUniform {
+        let light_vp = self.light_vp(tot_bbx);
+        let dir = self.direction();
+        let is_on = self.is_on && light_vp.is_some();
+        SunUniform {
+            light_vp: light_vp.unwrap_or(Matrix4::identity()).into(),
+            direction: [dir.x, dir.y, dir.z, if (is_on) { 1.0 } else { 0.0 }],
+            color: [self.intensity, self.intensity, self.intensity, 1.0],
+            params: [1.0 / SHADOW_MAP_SIZE as f32, 0.0002, 0.0, 0.0],
+        }
+    }
+}
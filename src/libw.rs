@@ -21,9 +21,10 @@ use crate::device::wstate::WState;
 use crate::shared::text_layout::TextLayout;
 
 mod device;
-mod shared;
+//putout-pack reads and writes packs with the same code as the viewer
+pub mod shared;
 mod gui;
-mod scene;
+pub mod scene;
 mod remote;
 
 #[wasm_bindgen]
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::rc::Rc;
 use cgmath::num_traits::Float;
 use env_logger::{Builder, Target};
@@ -22,6 +23,8 @@ use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
 use winit::window::{Window, WindowId};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::device::state::MState;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scene::pack_format::{find_hull_packs, HullPackFiles};
 use crate::shared::text_layout::TextLayout;
 
 mod device;
@@ -35,15 +38,51 @@ mod remote;
 
 
 
+const USAGE: &str = "usage: putout [MODEL_DIR | PACK_FILE ...]
+       putout section [--plane PX,PY,PZ,NX,NY,NZ | --slicer XMAX,XMIN,YMAX,YMIN,ZMAX,ZMIN] [-o FILE ...] MODEL_DIR | PACK_FILE ...
+  MODEL_DIR  directory with hull packs, *.putpack files or old <N>data_mesh|ind|bbx|hash sets
+  PACK_FILE  a single *.putpack file, or any stream file of an old pack, e.g. model/3data_mesh
+Press F2 in the viewer to load the next pack.
+section cuts the visible parts by a plane and writes polylines per part, a layer per part type
+  --plane           point and normal, the cut is drawn as seen from the side of the normal
+  --slicer          slicer sensors 0..1 over the model box, the first moved one gives the plane (as F12 in the viewer)
+  -o, --out FILE    .svg or .dxf, can be repeated (default section.svg and section.dxf)";
+
 fn main() {
     let mut builder = Builder::from_default_env();
     builder.target(Target::Stdout);
     builder.filter(None, LevelFilter::Warn).init();
 
+    let args: Vec<PathBuf> = std::env::args_os().skip(1).map(PathBuf::from).collect();
+    if (args.iter().any(|a| a.as_os_str() == "-h" || a.as_os_str() == "--help")) {
+        println!("{}", USAGE);
+        return;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if (args.first().map_or(false, |a| a.as_os_str() == "section")) {
+        let section_args: Vec<String> = args[1..].iter().map(|a| a.to_string_lossy().to_string()).collect();
+        match scene::section::run(section_args) {
+            Ok(()) => {}
+            Err(e) => {
+                eprintln!("putout: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let hull_pack_files: Vec<HullPackFiles> = match find_hull_packs(&args) {
+        Ok(packs) => packs,
+        Err(e) => {
+            eprintln!("putout: {}", e);
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    };
 
     let event_loop = EventLoop::new().unwrap();
     #[cfg(not(target_arch = "wasm32"))]
-    let mut state = MState::new();
+    let mut state = MState::new(hull_pack_files);
     #[cfg(not(target_arch = "wasm32"))]
     let _ = event_loop.run_app(&mut state);
     println!("out")
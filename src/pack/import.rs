@@ -0,0 +1,189 @@
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::path::Path;
+use cgmath::{InnerSpace, Matrix, Matrix3, Matrix4, SquareMatrix, Vector3, Vector4};
+
+//One ship part as a triangle soup, 3 positions per triangle
+pub struct ImportedPart {
+    pub id: Option<i32>,
+    pub ty: Option<i32>,
+    pub name: String,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+}
+
+impl ImportedPart {
+    fn new(name: &str) -> Self {
+        let (id, ty) = parse_part_name(name);
+        Self {
+            id: id,
+            ty: ty,
+            name: name.to_string(),
+            positions: vec![],
+            normals: vec![],
+        }
+    }
+    fn push_triangle(&mut self, p: [[f32; 3]; 3], n: Option<[[f32; 3]; 3]>) {
+        let n = match n {
+            Some(n) => n,
+            None => {
+                let fnorm = face_normal(&p);
+                [fnorm, fnorm, fnorm]
+            }
+        };
+        self.positions.extend_from_slice(&p);
+        self.normals.extend_from_slice(&n);
+    }
+}
+
+//Part id and type come from the object/node/file name: "<id>" or "<id>_<type>", e.g. "1904245_8"
+pub fn parse_part_name(name: &str) -> (Option<i32>, Option<i32>) {
+    let mut it = name.trim().splitn(2, '_');
+    let id = it.next().and_then(|s| s.parse::<i32>().ok());
+    match id {
+        None => (None, None),
+        Some(id) => (Some(id), it.next().and_then(|s| s.parse::<i32>().ok()))
+    }
+}
+
+pub fn import_model(path: &Path) -> io::Result<Vec<ImportedPart>> {
+    let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).unwrap_or_default();
+    let parts = match ext.as_str() {
+        "obj" => import_obj(path),
+        "stl" => import_stl(path),
+        "gltf" | "glb" => import_gltf(path),
+        _ => Err(io::Error::new(ErrorKind::InvalidInput, "unknown model format, expected .obj, .stl, .gltf or .glb".to_string()))
+    };
+    parts.map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+}
+
+//every "o"/"g" object is a part
+fn import_obj(path: &Path) -> io::Result<Vec<ImportedPart>> {
+    let options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+        ..Default::default()
+    };
+    let (models, _materials) = tobj::load_obj(path, &options).map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+    let mut parts: Vec<ImportedPart> = vec![];
+    models.iter().for_each(|model| {
+        let mesh = &model.mesh;
+        let mut part = ImportedPart::new(model.name.as_str());
+        let has_normals = mesh.normals.len() == mesh.positions.len();
+        mesh.indices.chunks_exact(3).for_each(|tri| {
+            let p = [0, 1, 2].map(|k| {
+                let i = tri[k] as usize * 3;
+                [mesh.positions[i], mesh.positions[i + 1], mesh.positions[i + 2]]
+            });
+            let n = if (has_normals) {
+                Some([0, 1, 2].map(|k| {
+                    let i = tri[k] as usize * 3;
+                    [mesh.normals[i], mesh.normals[i + 1], mesh.normals[i + 2]]
+                }))
+            } else {
+                None
+            };
+            part.push_triangle(p, n);
+        });
+        parts.push(part);
+    });
+    Ok(parts)
+}
+
+//STL has no objects, the whole file is one part named by the file stem
+fn import_stl(path: &Path) -> io::Result<Vec<ImportedPart>> {
+    let mut file = fs::File::open(path)?;
+    let mesh = stl_io::read_stl(&mut file)?;
+    let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let mut part = ImportedPart::new(name);
+    mesh.faces.iter().for_each(|face| {
+        let p = face.vertices.map(|i| mesh.vertices[i].0);
+        let n = face.normal.0;
+        if (n[0] == 0.0 && n[1] == 0.0 && n[2] == 0.0) {
+            part.push_triangle(p, None);
+        } else {
+            part.push_triangle(p, Some([n, n, n]));
+        }
+    });
+    Ok(vec![part])
+}
+
+//every node with a mesh is a part, node transforms are applied
+fn import_gltf(path: &Path) -> io::Result<Vec<ImportedPart>> {
+    let gltf = gltf::Gltf::open(path).map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+    let buffers = gltf::import_buffers(&gltf.document, path.parent(), gltf.blob.clone()).map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+    let mut parts: Vec<ImportedPart> = vec![];
+    let scenes: Vec<gltf::Scene> = match gltf.document.default_scene() {
+        Some(scene) => vec![scene],
+        None => gltf.document.scenes().collect()
+    };
+    scenes.iter().for_each(|scene| {
+        scene.nodes().for_each(|node| {
+            import_gltf_node(&node, Matrix4::identity(), &buffers, &mut parts);
+        });
+    });
+    Ok(parts)
+}
+
+fn import_gltf_node(node: &gltf::Node, parent: Matrix4<f32>, buffers: &Vec<gltf::buffer::Data>, parts: &mut Vec<ImportedPart>) {
+    let transform = parent * Matrix4::from(node.transform().matrix());
+    let normal_transform: Matrix3<f32> = {
+        let m = Matrix3::new(
+            transform.x.x, transform.x.y, transform.x.z,
+            transform.y.x, transform.y.y, transform.y.z,
+            transform.z.x, transform.z.y, transform.z.z,
+        );
+        m.invert().map(|inv| inv.transpose()).unwrap_or(m)
+    };
+    match node.mesh() {
+        None => {}
+        Some(mesh) => {
+            let name = node.name().or(mesh.name()).map(|s| s.to_string()).unwrap_or(format!("node{}", node.index()));
+            let mut part = ImportedPart::new(name.as_str());
+            mesh.primitives().filter(|p| p.mode() == gltf::mesh::Mode::Triangles).for_each(|primitive| {
+                let reader = primitive.reader(|b| buffers.get(b.index()).map(|d| d.0.as_slice()));
+                let positions: Vec<[f32; 3]> = match reader.read_positions() {
+                    None => vec![],
+                    Some(it) => it.map(|p| {
+                        let v = transform * Vector4::new(p[0], p[1], p[2], 1.0);
+                        [v.x, v.y, v.z]
+                    }).collect()
+                };
+                let normals: Vec<[f32; 3]> = match reader.read_normals() {
+                    None => vec![],
+                    Some(it) => it.map(|n| {
+                        let v = (normal_transform * Vector3::new(n[0], n[1], n[2])).normalize();
+                        [v.x, v.y, v.z]
+                    }).collect()
+                };
+                let indices: Vec<u32> = match reader.read_indices() {
+                    None => (0..positions.len() as u32).collect(),
+                    Some(it) => it.into_u32().collect()
+                };
+                let has_normals = normals.len() == positions.len();
+                indices.chunks_exact(3).filter(|tri| tri.iter().all(|i| (*i as usize) < positions.len())).for_each(|tri| {
+                    let p = [0, 1, 2].map(|k| positions[tri[k] as usize]);
+                    let n = if (has_normals) { Some([0, 1, 2].map(|k| normals[tri[k] as usize])) } else { None };
+                    part.push_triangle(p, n);
+                });
+            });
+            parts.push(part);
+        }
+    }
+    node.children().for_each(|child| import_gltf_node(&child, transform, buffers, parts));
+}
+
+fn face_normal(p: &[[f32; 3]; 3]) -> [f32; 3] {
+    let a = Vector3::new(p[1][0] - p[0][0], p[1][1] - p[0][1], p[1][2] - p[0][2]);
+    let b = Vector3::new(p[2][0] - p[0][0], p[2][1] - p[0][1], p[2][2] - p[0][2]);
+    let n = a.cross(b);
+    if (n.magnitude2() > 0.0) {
+        let n = n.normalize();
+        [n.x, n.y, n.z]
+    } else {
+        [1.0, 0.0, 0.0]
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use main::shared::materials_lib::{Material, TY_HULL_OTHERS, TY_HULL_OUTERPLATES, TY_HULL_PLATES, TY_HULL_PROFILES};
+use main::shared::mesh_common::{CompactVertex, MeshVertex, PartEntry, Quantization};
+use main::scene::pack_format::{find_hull_packs, unpack_id, unpack_packid, HullPackFiles, PackContainer, PackLayout, PackStreams, PartInfo, StreamCodec, StreamKind};
+use std::mem::size_of;
+
+//first errors of a kind are printed, the rest are only counted
+const MAX_REPORTED: usize = 5;
+
+struct Report {
+    errors: Vec<String>,
+    counts: BTreeMap<&'static str, usize>,
+}
+
+impl Report {
+    fn new() -> Self {
+        Self {
+            errors: vec![],
+            counts: BTreeMap::new(),
+        }
+    }
+    fn error(&mut self, kind: &'static str, msg: String) {
+        let count = self.counts.entry(kind).or_insert(0);
+        *count = *count + 1;
+        if (*count <= MAX_REPORTED) {
+            self.errors.push(msg);
+        }
+    }
+    fn error_count(&self) -> usize {
+        self.counts.values().sum()
+    }
+}
+
+//returns false if any pack is broken
+pub fn run(args: Vec<String>) -> Result<bool, String> {
+    let mut list_parts = false;
+    let mut bench_codecs = false;
+    let mut paths: Vec<PathBuf> = vec![];
+    args.into_iter().for_each(|arg| {
+        if (arg == "--parts") {
+            list_parts = true;
+        } else if (arg == "--codecs") {
+            bench_codecs = true;
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    });
+    if (paths.is_empty()) {
+        return Err("inspect needs a model directory or pack files".to_string());
+    }
+    let packs = find_hull_packs(&paths).map_err(|e| e.to_string())?;
+    let mut is_ok = true;
+    let mut part_packs: HashMap<u32, u32> = HashMap::new();
+    let mut tot_bbx: [f32; 6] = [f32::MAX, f32::MAX, f32::MAX, f32::MIN, f32::MIN, f32::MIN];
+    let mut tot_parts = 0;
+    let mut tot_triangles = 0;
+    for pack in &packs {
+        match pack.read_streams() {
+            Ok(streams) => {
+                let mut report = Report::new();
+                let (parts, triangles) = inspect_pack(pack, &streams, list_parts, bench_codecs, &mut report, &mut tot_bbx);
+                streams.t.chunks_exact(12).for_each(|h| {
+                    let id = u32::from_le_bytes([h[0], h[1], h[2], h[3]]);
+                    match part_packs.insert(id, pack.pack_id) {
+                        Some(other) if other != pack.pack_id => {
+                            report.error("duplicate id", format!("part {} is also in pack {}", id, other));
+                        }
+                        _ => {}
+                    }
+                });
+                tot_parts = tot_parts + parts;
+                tot_triangles = tot_triangles + triangles;
+                report.errors.iter().for_each(|e| println!("  ERROR {}", e));
+                report.counts.iter().filter(|(_k, c)| **c > MAX_REPORTED).for_each(|(k, c)| println!("  ERROR {} more {} errors", c - MAX_REPORTED, k));
+                if (report.error_count() == 0) {
+                    println!("  OK");
+                } else {
+                    is_ok = false;
+                }
+            }
+            Err(e) => {
+                println!("pack {} ({})", pack.pack_id, pack.path().display());
+                println!("  ERROR {}", e);
+                is_ok = false;
+            }
+        }
+    }
+    println!("total: {} packs, {} parts, {} triangles, bbox {}", packs.len(), tot_parts, tot_triangles, bbx_to_string(&tot_bbx));
+    Ok(is_ok)
+}
+
+fn inspect_pack(pack: &HullPackFiles, streams: &PackStreams, list_parts: bool, bench_codecs: bool, report: &mut Report, tot_bbx: &mut [f32; 6]) -> (usize, usize) {
+    let layout = match (&pack.layout, streams.is_compact()) {
+        (PackLayout::Container(_), false) => "container",
+        (PackLayout::Container(_), true) => "container, compact",
+        (PackLayout::Legacy { .. }, _) => "old four-file layout",
+    };
+    println!("pack {} ({}, {})", pack.pack_id, pack.path().display(), layout);
+    match &streams.meta {
+        None => {}
+        Some(meta) => {
+            println!("  meta: name {}, units {}, bbox {}",
+                     meta.name.clone().unwrap_or("-".to_string()),
+                     meta.units.clone().unwrap_or("-".to_string()),
+                     meta.bbx.map(|b| bbx_to_string(&b)).unwrap_or("-".to_string()));
+        }
+    }
+    match &pack.layout {
+        PackLayout::Container(path) => { print_streams(path, report); }
+        PackLayout::Legacy { .. } => {}
+    }
+    if (bench_codecs) {
+        print_codecs(streams);
+    }
+    let i: Vec<i32> = bytemuck::pod_collect_to_vec(streams.i.as_slice());
+    let b: Vec<f32> = bytemuck::pod_collect_to_vec(streams.b.as_slice());
+    let t: Vec<u32> = bytemuck::pod_collect_to_vec(streams.t.as_slice());
+    let hashes: Vec<&[u32]> = t.chunks_exact(3).collect();
+    let bbxs: Vec<&[f32]> = b.chunks_exact(6).collect();
+    //id and material_index of every vertex, from the vertex itself or from its part in the compact layout
+    let v: Vec<MeshVertex> = if (streams.is_compact()) {
+        compact_vertices(streams, &hashes, report)
+    } else {
+        bytemuck::pod_collect_to_vec(streams.v.as_slice())
+    };
+    println!("  vertices {}, indices {}, parts {}, bboxes {}", v.len(), i.len(), hashes.len(), bbxs.len());
+
+    //get_triangle_by_vertex_index takes vertices by 3, compact packs are picked by index position
+    if (!streams.is_compact() && v.len() % 3 != 0) {
+        report.error("vertex count", format!("vertex count {} is not a multiple of 3", v.len()));
+    }
+    if (i.len() % 3 != 0) {
+        report.error("index count", format!("index count {} is not a multiple of 3", i.len()));
+    }
+    i.iter().enumerate().filter(|(_n, ind)| **ind < 0 || **ind as usize >= v.len()).for_each(|(n, ind)| {
+        report.error("index range", format!("index {} at {} is out of vertex range 0..{}", ind, n, v.len()));
+    });
+    v.iter().enumerate().filter(|(_n, m)| unpack_packid(m.material_index as u32) != pack.pack_id).for_each(|(n, m)| {
+        report.error("pack id", format!("vertex {} material_index {} unpacks to pack {}, pack slot is {}", n, m.material_index, unpack_packid(m.material_index as u32), pack.pack_id));
+    });
+    if (bbxs.len() != hashes.len()) {
+        report.error("bbox count", format!("{} bboxes for {} parts", bbxs.len(), hashes.len()));
+    }
+
+    //mesh_hash is id -> (start, end) with end inclusive
+    let mut ids: HashMap<u32, usize> = HashMap::new();
+    let mut ranges: Vec<(u32, u32, u32)> = vec![];
+    hashes.iter().enumerate().for_each(|(n, h)| {
+        let (id, start, end) = (h[0], h[1], h[2]);
+        match ids.insert(id, n) {
+            None => {}
+            Some(other) => {
+                report.error("duplicate id", format!("part {} is listed twice, entries {} and {}", id, other, n));
+            }
+        }
+        if (start > end || end as usize >= v.len()) {
+            report.error("hash range", format!("part {} range {}..={} is out of vertex range 0..{}", id, start, end, v.len()));
+        } else {
+            v[start as usize..=end as usize].iter().enumerate().filter(|(_k, m)| m.id as u32 != id).for_each(|(k, m)| {
+                report.error("vertex id", format!("vertex {} of part {} has id {}", start as usize + k, id, m.id));
+            });
+            ranges.push((start, end, id));
+        }
+    });
+    let info = part_info(pack, streams, &ids, report);
+    ranges.sort();
+    ranges.windows(2).filter(|w| w[1].0 <= w[0].1).for_each(|w| {
+        report.error("hash overlap", format!("part {} range {}..={} overlaps part {} range {}..={}", w[0].2, w[0].0, w[0].1, w[1].2, w[1].0, w[1].1));
+    });
+
+    let mut pack_bbx: [f32; 6] = [f32::MAX, f32::MAX, f32::MAX, f32::MIN, f32::MIN, f32::MIN];
+    bbxs.iter().enumerate().for_each(|(n, bb)| {
+        if (bb[0] > bb[3] || bb[1] > bb[4] || bb[2] > bb[5]) {
+            report.error("bbox", format!("bbox {} {} has min above max", n, bbx_to_string(&[bb[0], bb[1], bb[2], bb[3], bb[4], bb[5]])));
+        }
+        for k in 0..3 {
+            pack_bbx[k] = pack_bbx[k].min(bb[k]);
+            pack_bbx[k + 3] = pack_bbx[k + 3].max(bb[k + 3]);
+        }
+    });
+    for k in 0..3 {
+        tot_bbx[k] = tot_bbx[k].min(pack_bbx[k]);
+        tot_bbx[k + 3] = tot_bbx[k + 3].max(pack_bbx[k + 3]);
+    }
+
+    //stats over parts with valid ranges, a triangle belongs to the part of its first corner
+    let mut part_triangles: HashMap<i32, usize> = HashMap::new();
+    i.chunks_exact(3).filter_map(|tri| v.get(tri[0] as usize)).for_each(|m| {
+        *part_triangles.entry(m.id).or_insert(0) += 1;
+    });
+    let mut triangles: Vec<usize> = vec![];
+    let mut groups: BTreeMap<i32, usize> = BTreeMap::new();
+    let mut types: BTreeMap<u32, usize> = BTreeMap::new();
+    hashes.iter().filter(|h| h[1] <= h[2] && (h[2] as usize) < v.len()).for_each(|h| {
+        let count = part_triangles.get(&(h[0] as i32)).copied().unwrap_or(0);
+        let ty = unpack_id(v[h[1] as usize].material_index as u32);
+        triangles.push(count);
+        *types.entry(ty).or_insert(0) += 1;
+        *groups.entry(Material::type_to_color(ty as i32)).or_insert(0) += 1;
+        if (list_parts) {
+            match info.get(&(h[0] as i32)).and_then(|p| p.name.clone()) {
+                None => println!("    part {} type {} triangles {}", h[0], ty, count),
+                Some(name) => println!("    part {} type {} triangles {} {}", h[0], ty, count, name),
+            }
+        }
+    });
+    let tri_count: usize = triangles.iter().sum();
+    match (triangles.iter().min(), triangles.iter().max()) {
+        (Some(min), Some(max)) => {
+            println!("  triangles {}, per part min {} avg {:.1} max {}", tri_count, min, tri_count as f64 / triangles.len() as f64, max);
+        }
+        _ => {
+            println!("  triangles 0");
+        }
+    }
+    println!("  colors: {}", groups.iter().map(|(c, n)| format!("{} {}", color_group_name(*c), n)).collect::<Vec<String>>().join(", "));
+    println!("  types: {}", types.iter().map(|(t, n)| format!("{}:{}", t, n)).collect::<Vec<String>>().join(" "));
+    println!("  bbox {}", bbx_to_string(&pack_bbx));
+    (hashes.len(), i.len() / 3)
+}
+
+//info stream or sidecar file, every entry must be a part of the pack
+fn part_info(pack: &HullPackFiles, streams: &PackStreams, ids: &HashMap<u32, usize>, report: &mut Report) -> HashMap<i32, PartInfo> {
+    let (info, source) = match &streams.info {
+        Some(info) => (info.clone(), "stream".to_string()),
+        None => {
+            match pack.read_part_info() {
+                Ok(Some(info)) => (info, pack.part_info_path().display().to_string()),
+                Ok(None) => { return HashMap::new(); }
+                Err(e) => {
+                    report.error("info", e.to_string());
+                    return HashMap::new();
+                }
+            }
+        }
+    };
+    let mut by_id: HashMap<i32, PartInfo> = HashMap::new();
+    info.into_iter().for_each(|p| {
+        if (!ids.contains_key(&(p.id as u32))) {
+            report.error("info id", format!("info of part {} is not a part of the pack", p.id));
+        }
+        match by_id.insert(p.id, p) {
+            None => {}
+            Some(other) => { report.error("info id", format!("info of part {} is listed twice", other.id)); }
+        }
+    });
+    println!("  info: {} of {} parts ({})", by_id.len(), ids.len(), source);
+    by_id
+}
+
+//codec, sizes and decode time of every stream in the file
+fn print_streams(path: &PathBuf, report: &mut Report) {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            report.error("io", format!("{}: {}", path.display(), e));
+            return;
+        }
+    };
+    let container = match PackContainer::parse(bytes.as_slice()) {
+        Ok(container) => container,
+        Err(e) => {
+            report.error("format", e.to_string());
+            return;
+        }
+    };
+    container.header.streams.iter().for_each(|entry| {
+        let start = Instant::now();
+        let decoded = container.read_stream(entry.kind);
+        let ms = start.elapsed().as_secs_f64() * 1000.0;
+        match decoded {
+            Ok(_) => {
+                println!("  stream {:<5} {:<7} {:>12} -> {:>12} bytes {:>6.1}%, decode {:.2} ms", entry.kind.name(), entry.codec.name(),
+                         entry.raw_len, entry.packed_len, percent(entry.packed_len as usize, entry.raw_len as usize), ms);
+            }
+            Err(e) => { report.error("stream", e.to_string()); }
+        }
+    });
+}
+
+//packs all streams of the pack with every codec, to choose one per project
+fn print_codecs(streams: &PackStreams) {
+    let raw: Vec<(StreamKind, &Vec<u8>)> = [
+        (StreamKind::Vertices, &streams.v),
+        (StreamKind::Indices, &streams.i),
+        (StreamKind::Bbxs, &streams.b),
+        (StreamKind::Hashes, &streams.t),
+        (StreamKind::CompactVertices, &streams.c),
+        (StreamKind::Parts, &streams.p),
+        (StreamKind::Quantization, &streams.q),
+    ].into_iter().filter(|(_kind, data)| !data.is_empty()).collect();
+    let raw_len: usize = raw.iter().map(|(_kind, data)| data.len()).sum();
+    StreamCodec::ALL.iter().for_each(|codec| {
+        let mut packed_len = 0;
+        let mut encode_ms = 0.0;
+        let mut decode_ms = 0.0;
+        let mut is_ok = true;
+        raw.iter().for_each(|(kind, data)| {
+            let start = Instant::now();
+            let packed = match codec.encode(*kind, data.as_slice()) {
+                Ok(packed) => packed,
+                Err(e) => {
+                    println!("  ERROR codec {}: {}", codec.name(), e);
+                    is_ok = false;
+                    return;
+                }
+            };
+            encode_ms = encode_ms + start.elapsed().as_secs_f64() * 1000.0;
+            let start = Instant::now();
+            let decoded = codec.decode(*kind, packed.as_slice(), data.len() as u64);
+            decode_ms = decode_ms + start.elapsed().as_secs_f64() * 1000.0;
+            packed_len = packed_len + packed.len();
+            is_ok = is_ok && decoded.map_or(false, |d| d == **data);
+        });
+        println!("  codec {:<7} {:>12} -> {:>12} bytes {:>6.1}%, encode {:.1} ms, decode {:.1} ms{}", codec.name(), raw_len, packed_len,
+                 percent(packed_len, raw_len), encode_ms, decode_ms, if (is_ok) { "" } else { ", ROUND TRIP FAILED" });
+    });
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if (whole == 0) { 100.0 } else { part as f64 * 100.0 / whole as f64 }
+}
+
+//expands the part table to MeshVertex, positions and normals are decoded for the bbox checks
+fn compact_vertices(streams: &PackStreams, hashes: &Vec<&[u32]>, report: &mut Report) -> Vec<MeshVertex> {
+    let cv: Vec<CompactVertex> = bytemuck::pod_collect_to_vec(streams.c.as_slice());
+    let parts: Vec<PartEntry> = bytemuck::pod_collect_to_vec(streams.p.as_slice());
+    if (streams.q.len() != size_of::<Quantization>()) {
+        report.error("quantization", format!("quantization stream has {} bytes, expected {}", streams.q.len(), size_of::<Quantization>()));
+        return vec![];
+    }
+    let q: Quantization = bytemuck::pod_read_unaligned(streams.q.as_slice());
+    println!("  quantization origin [{} {} {}] extent [{} {} {}], step {:.4}", q.origin[0], q.origin[1], q.origin[2], q.extent[0], q.extent[1], q.extent[2],
+             q.extent[0].max(q.extent[1]).max(q.extent[2]) / 65535.0);
+    if (parts.len() != hashes.len()) {
+        report.error("part count", format!("part table has {} entries for {} parts", parts.len(), hashes.len()));
+    }
+    parts.iter().zip(hashes.iter()).enumerate().filter(|(_n, (pt, h))| pt.id as u32 != h[0]).for_each(|(n, (pt, h))| {
+        report.error("part id", format!("part table entry {} has id {}, hash entry has {}", n, pt.id, h[0]));
+    });
+    cv.iter().enumerate().map(|(n, c)| {
+        match parts.get(c.part as usize) {
+            None => {
+                report.error("part range", format!("vertex {} part index {} is out of part table 0..{}", n, c.part, parts.len()));
+                MeshVertex::default()
+            }
+            Some(pt) => {
+                let p = c.position(&q);
+                let nr = c.normal();
+                MeshVertex::new(p[0], p[1], p[2], nr[0], nr[1], nr[2], pt.material_index, pt.id)
+            }
+        }
+    }).collect()
+}
+
+fn color_group_name(color: i32) -> String {
+    match color {
+        TY_HULL_PROFILES => "profiles".to_string(),
+        TY_HULL_PLATES => "plates".to_string(),
+        TY_HULL_OUTERPLATES => "outer plates".to_string(),
+        TY_HULL_OTHERS => "others".to_string(),
+        _ => format!("material {}", color),
+    }
+}
+
+fn bbx_to_string(b: &[f32; 6]) -> String {
+    if (b[0] > b[3]) {
+        "empty".to_string()
+    } else {
+        format!("[{} {} {}]..[{} {} {}]", b[0], b[1], b[2], b[3], b[4], b[5])
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use crate::import::{import_model, ImportedPart};
+use main::scene::pack_format::{pack_raw_id, parse_part_info, PackMeta, PackWriter, PartInfo, StreamCodec, StreamKind, CABLE_PACK_ID, MAX_RAW_ID_VALUE, PACK_FILE_EXT};
+use main::shared::mesh_common::{CompactVertex, MeshVertex, PartEntry, Quantization};
+
+mod import;
+mod inspect;
+
+const USAGE: &str = "usage: putout-pack [OPTIONS] -o OUT_DIR MODEL...
+       putout-pack inspect [--parts] [--codecs] MODEL_DIR | PACK_FILE ...
+  MODEL             .obj, .stl, .gltf or .glb file
+                    obj objects, gltf nodes and stl files are parts, named <id> or <id>_<type>, e.g. 1904245_8
+Options:
+  -o, --out DIR     output directory, created if missing
+  --name NAME       pack file prefix, packs are written as <NAME><pack_id>.putpack (default hull)
+  --budget MB       max vertex+index data per pack in MB (default 64)
+  --first-pack N    id of the first pack (default 0)
+  --first-id N      first id for parts without an id in the name (default 1)
+  --type N          type for parts without a type in the name (default 0)
+  --scale F         multiply all coordinates by F (default 1)
+  --units UNITS     units written to the pack meta, e.g. cm
+  --parts FILE      json array of part info (id, name, type, block, section, grade, thickness mm, weight kg),
+                    written to an info stream of every pack, parts without an entry get their model name
+  --codec CODEC     stream codec: stored, deflate, zstd or mesh (zstd with a vertex/index filter), default deflate
+  --legacy          also write the old <N>data_mesh|ind|bbx|hash files, always deflate
+  --compact         shared vertices with 16-bit positions over the pack box and octahedral normals,
+                    part id and material in a part table, half to a fifth of the GPU memory
+inspect checks packs and prints statistics, exit code is 1 if any pack is broken
+  --parts           print every part with its type and triangle count
+  --codecs          pack every stream with every codec and print sizes and encode/decode times";
+
+//bytes one vertex takes in GPU buffers, MeshVertex + i32 index
+const VERTEX_COST: usize = 44;
+//compact upper bound, nothing shared: CompactVertex + i32 index
+const COMPACT_VERTEX_COST: usize = 20;
+
+struct Options {
+    out: PathBuf,
+    name: String,
+    budget: usize,
+    first_pack: u32,
+    first_id: i32,
+    ty: i32,
+    scale: f32,
+    units: Option<String>,
+    parts: Option<PathBuf>,
+    codec: StreamCodec,
+    legacy: bool,
+    compact: bool,
+    inputs: Vec<PathBuf>,
+}
+
+struct Part {
+    id: i32,
+    name: String,
+    ty: i32,
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if (args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help")) {
+        println!("{}", USAGE);
+        return;
+    }
+    if (args[0] == "inspect") {
+        match inspect::run(args[1..].to_vec()) {
+            Ok(true) => {}
+            Ok(false) => { exit(1); }
+            Err(e) => {
+                eprintln!("putout-pack: {}", e);
+                exit(1);
+            }
+        }
+        return;
+    }
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("putout-pack: {}", e);
+            eprintln!("{}", USAGE);
+            exit(1);
+        }
+    };
+    match run(&options) {
+        Ok(()) => {}
+        Err(e) => {
+            eprintln!("putout-pack: {}", e);
+            exit(1);
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut options = Options {
+        out: PathBuf::new(),
+        name: "hull".to_string(),
+        budget: 64 * 1024 * 1024,
+        first_pack: 0,
+        first_id: 1,
+        ty: 0,
+        scale: 1.0,
+        units: None,
+        parts: None,
+        codec: StreamCodec::Deflate,
+        legacy: false,
+        compact: false,
+        inputs: vec![],
+    };
+    let mut is_out_set = false;
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--legacy" => { options.legacy = true; }
+            "--compact" => { options.compact = true; }
+            "-o" | "--out" | "--name" | "--budget" | "--first-pack" | "--first-id" | "--type" | "--scale" | "--units" | "--parts" | "--codec" => {
+                let value = it.next().ok_or(format!("{} needs a value", arg))?;
+                let bad = |_e| format!("bad value {} for {}", value, arg);
+                match arg.as_str() {
+                    "-o" | "--out" => {
+                        options.out = PathBuf::from(&value);
+                        is_out_set = true;
+                    }
+                    "--name" => { options.name = value.clone(); }
+                    "--budget" => {
+                        let mb: f64 = value.parse().map_err(|e: std::num::ParseFloatError| bad(e.to_string()))?;
+                        if (mb <= 0.0) {
+                            return Err(format!("--budget must be positive, got {}", value));
+                        }
+                        options.budget = (mb * 1024.0 * 1024.0) as usize;
+                    }
+                    "--first-pack" => { options.first_pack = value.parse().map_err(|e: std::num::ParseIntError| bad(e.to_string()))?; }
+                    "--first-id" => { options.first_id = value.parse().map_err(|e: std::num::ParseIntError| bad(e.to_string()))?; }
+                    "--type" => { options.ty = value.parse().map_err(|e: std::num::ParseIntError| bad(e.to_string()))?; }
+                    "--scale" => { options.scale = value.parse().map_err(|e: std::num::ParseFloatError| bad(e.to_string()))?; }
+                    "--units" => { options.units = Some(value.clone()); }
+                    "--parts" => { options.parts = Some(PathBuf::from(&value)); }
+                    "--codec" => {
+                        options.codec = StreamCodec::from_name(value.as_str()).ok_or(format!("unknown codec {}, expected stored, deflate, zstd or mesh", value))?;
+                    }
+                    _ => {}
+                }
+            }
+            _ => {
+                if (arg.starts_with('-')) {
+                    return Err(format!("unknown option {}", arg));
+                }
+                options.inputs.push(PathBuf::from(arg));
+            }
+        }
+    }
+    if (!is_out_set) {
+        return Err("output directory is not set, use -o DIR".to_string());
+    }
+    if (options.inputs.is_empty()) {
+        return Err("no input models".to_string());
+    }
+    if (options.first_pack >= CABLE_PACK_ID) {
+        return Err(format!("--first-pack must be below {}, pack {} is the cable network", CABLE_PACK_ID, CABLE_PACK_ID));
+    }
+    if (options.ty < 0) {
+        return Err("--type must not be negative".to_string());
+    }
+    if (options.first_id < 0) {
+        return Err("--first-id must not be negative".to_string());
+    }
+    if (options.legacy && options.compact) {
+        return Err("--legacy files can't hold the compact layout, use one of --legacy and --compact".to_string());
+    }
+    Ok(options)
+}
+
+fn run(options: &Options) -> io::Result<()> {
+    let mut imported: Vec<ImportedPart> = vec![];
+    for path in &options.inputs {
+        let parts = import_model(path)?;
+        println!("{}: {} parts", path.display(), parts.len());
+        imported.extend(parts);
+    }
+    let parts = assign_ids(imported, options)?;
+    if (parts.is_empty()) {
+        return Err(io::Error::new(ErrorKind::InvalidData, "input models have no triangles"));
+    }
+    let info = match &options.parts {
+        None => None,
+        Some(path) => Some(read_part_info(path, &parts)?),
+    };
+    let vertex_cost = if (options.compact) { COMPACT_VERTEX_COST } else { VERTEX_COST };
+    let packs = split_by_budget(&parts, options.budget, vertex_cost);
+    let last_pack = options.first_pack as usize + packs.len() - 1;
+    if (last_pack >= CABLE_PACK_ID as usize) {
+        return Err(io::Error::new(ErrorKind::InvalidInput, format!("model needs {} packs, pack ids {}..={} do not fit below {}, raise --budget", packs.len(), options.first_pack, last_pack, CABLE_PACK_ID)));
+    }
+    fs::create_dir_all(&options.out).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", options.out.display(), e)))?;
+    for (n, pack_parts) in packs.iter().enumerate() {
+        write_pack(options.first_pack + n as u32, pack_parts, info.as_ref(), options)?;
+    }
+    Ok(())
+}
+
+fn assign_ids(imported: Vec<ImportedPart>, options: &Options) -> io::Result<Vec<Part>> {
+    let mut used: HashSet<i32> = HashSet::new();
+    for part in &imported {
+        match part.id {
+            None => {}
+            Some(id) => {
+                if (!used.insert(id)) {
+                    return Err(io::Error::new(ErrorKind::InvalidData, format!("part id {} is used twice (part {})", id, part.name)));
+                }
+            }
+        }
+    }
+    let mut next_id = options.first_id;
+    let mut parts: Vec<Part> = vec![];
+    for part in imported {
+        if (part.positions.is_empty()) {
+            eprintln!("putout-pack: part {} has no triangles, skipped", part.name);
+            continue;
+        }
+        let id = match part.id {
+            Some(id) => id,
+            None => {
+                while (used.contains(&next_id)) {
+                    next_id = next_id + 1;
+                }
+                used.insert(next_id);
+                next_id
+            }
+        };
+        //the viewer keeps negative ids for the cable network
+        if (id < 0) {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("part {} has negative id {}", part.name, id)));
+        }
+        let ty = part.ty.unwrap_or(options.ty);
+        if (ty < 0) {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("part {} has negative type {}", part.name, ty)));
+        }
+        //the type is packed with the pack id into the material index
+        if (ty as u32 > MAX_RAW_ID_VALUE) {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("part {} has type {}, more than {}", part.name, ty, MAX_RAW_ID_VALUE)));
+        }
+        let scale = options.scale;
+        parts.push(Part {
+            id: id,
+            name: part.name,
+            ty: ty,
+            positions: part.positions.iter().map(|p| [p[0] * scale, p[1] * scale, p[2] * scale]).collect(),
+            normals: part.normals,
+        });
+    }
+    Ok(parts)
+}
+
+//parts are never split, a part bigger than the budget gets a pack of its own
+fn split_by_budget(parts: &Vec<Part>, budget: usize, vertex_cost: usize) -> Vec<Vec<&Part>> {
+    let mut packs: Vec<Vec<&Part>> = vec![];
+    let mut current: Vec<&Part> = vec![];
+    let mut current_size = 0;
+    parts.iter().for_each(|part| {
+        let size = part.positions.len() * vertex_cost;
+        if (!current.is_empty() && current_size + size > budget) {
+            packs.push(current.clone());
+            current = vec![];
+            current_size = 0;
+        }
+        if (size > budget) {
+            eprintln!("putout-pack: part {} is {} bytes, over the pack budget", part.id, size);
+        }
+        current.push(part);
+        current_size = current_size + size;
+    });
+    if (!current.is_empty()) {
+        packs.push(current);
+    }
+    packs
+}
+
+fn part_bbx(part: &Part) -> [f32; 6] {
+    let mut bbx: [f32; 6] = [f32::MAX, f32::MAX, f32::MAX, f32::MIN, f32::MIN, f32::MIN];
+    part.positions.iter().for_each(|p| {
+        for k in 0..3 {
+            bbx[k] = bbx[k].min(p[k]);
+            bbx[k + 3] = bbx[k + 3].max(p[k]);
+        }
+    });
+    bbx
+}
+
+fn write_pack(pack_id: u32, parts: &Vec<&Part>, info: Option<&HashMap<i32, PartInfo>>, options: &Options) -> io::Result<()> {
+    let mut bbxs: Vec<f32> = vec![];
+    let mut tot_bbx: [f32; 6] = [f32::MAX, f32::MAX, f32::MAX, f32::MIN, f32::MIN, f32::MIN];
+    parts.iter().for_each(|part| {
+        let bbx = part_bbx(part);
+        for k in 0..3 {
+            tot_bbx[k] = tot_bbx[k].min(bbx[k]);
+            tot_bbx[k + 3] = tot_bbx[k + 3].max(bbx[k + 3]);
+        }
+        bbxs.extend_from_slice(&bbx);
+    });
+
+    let mut writer = PackWriter::new(pack_id, options.codec);
+    let (i, hashes, vertex_count) = if (options.compact) {
+        let (cv, pt, q, i, hashes) = build_compact(pack_id, parts, &tot_bbx);
+        writer.add_stream(StreamKind::CompactVertices, bytemuck::cast_slice(cv.as_slice()));
+        writer.add_stream(StreamKind::Parts, bytemuck::cast_slice(pt.as_slice()));
+        writer.add_stream(StreamKind::Quantization, bytemuck::cast_slice(&[q]));
+        (i, hashes, cv.len())
+    } else {
+        let (v, i, hashes) = build_full(pack_id, parts);
+        writer.add_stream(StreamKind::Vertices, bytemuck::cast_slice(v.as_slice()));
+        if (options.legacy) {
+            let legacy_path = options.out.join(format!("{}data_{}", pack_id, StreamKind::Vertices.name()));
+            write_file(&legacy_path, StreamCodec::Deflate.encode(StreamKind::Vertices, bytemuck::cast_slice(v.as_slice()))?.as_slice())?;
+        }
+        (i, hashes, v.len())
+    };
+    let i_bytes: &[u8] = bytemuck::cast_slice(i.as_slice());
+    let b_bytes: &[u8] = bytemuck::cast_slice(bbxs.as_slice());
+    let h_bytes: &[u8] = bytemuck::cast_slice(hashes.as_slice());
+    writer.add_stream(StreamKind::Indices, i_bytes);
+    writer.add_stream(StreamKind::Bbxs, b_bytes);
+    writer.add_stream(StreamKind::Hashes, h_bytes);
+    writer.set_meta(&PackMeta {
+        name: Some(options.name.clone()),
+        bbx: Some(tot_bbx),
+        units: options.units.clone(),
+    })?;
+    let pack_info: Option<Vec<PartInfo>> = info.map(|info| {
+        parts.iter().map(|part| {
+            let mut p = info.get(&part.id).cloned().unwrap_or_default();
+            p.id = part.id;
+            if (p.name.is_none()) {
+                p.name = Some(part.name.clone());
+            }
+            p
+        }).collect()
+    });
+    match &pack_info {
+        None => {}
+        Some(pack_info) => { writer.set_part_info(pack_info.as_slice())?; }
+    }
+    let bytes = writer.to_bytes()?;
+    let path = options.out.join(format!("{}{}.{}", options.name, pack_id, PACK_FILE_EXT));
+    write_file(&path, bytes.as_slice())?;
+    println!("pack {}: {} parts, {} triangles, {} vertices, {} bytes ({} unpacked, {}) -> {}", pack_id, parts.len(), i.len() / 3, vertex_count,
+             bytes.len(), writer.raw_len(), options.codec.name(), path.display());
+
+    if (options.legacy) {
+        for (kind, raw) in [(StreamKind::Indices, i_bytes), (StreamKind::Bbxs, b_bytes), (StreamKind::Hashes, h_bytes)] {
+            let legacy_path = options.out.join(format!("{}data_{}", pack_id, kind.name()));
+            write_file(&legacy_path, StreamCodec::Deflate.encode(kind, raw)?.as_slice())?;
+        }
+        match &pack_info {
+            None => {}
+            Some(pack_info) => {
+                let legacy_path = options.out.join(format!("{}data_parts.json", pack_id));
+                let raw = serde_json::to_vec(pack_info).map_err(|e| io::Error::new(ErrorKind::InvalidData, format!("{}: {}", legacy_path.display(), e)))?;
+                write_file(&legacy_path, raw.as_slice())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+//entries for ids that are not in the model are dropped with a warning
+fn read_part_info(path: &Path, parts: &Vec<Part>) -> io::Result<HashMap<i32, PartInfo>> {
+    let raw = fs::read(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let info = parse_part_info(raw.as_slice()).map_err(|e| io::Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))?;
+    let ids: HashSet<i32> = parts.iter().map(|p| p.id).collect();
+    let mut by_id: HashMap<i32, PartInfo> = HashMap::new();
+    let mut unknown = 0;
+    for p in info {
+        if (!ids.contains(&p.id)) {
+            unknown = unknown + 1;
+            continue;
+        }
+        if (by_id.contains_key(&p.id)) {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("{}: part {} is listed twice", path.display(), p.id)));
+        }
+        by_id.insert(p.id, p);
+    }
+    if (unknown > 0) {
+        eprintln!("putout-pack: {}: {} parts are not in the model, skipped", path.display(), unknown);
+    }
+    println!("{}: info for {} of {} parts", path.display(), by_id.len(), parts.len());
+    Ok(by_id)
+}
+
+//triangle soup, one MeshVertex per corner
+fn build_full(pack_id: u32, parts: &Vec<&Part>) -> (Vec<MeshVertex>, Vec<i32>, Vec<u32>) {
+    let mut v: Vec<MeshVertex> = vec![];
+    let mut i: Vec<i32> = vec![];
+    let mut hashes: Vec<u32> = vec![];
+    parts.iter().for_each(|part| {
+        let start = v.len();
+        let material_index = pack_raw_id(part.ty as u32, pack_id) as i32;
+        part.positions.iter().zip(part.normals.iter()).for_each(|(p, n)| {
+            i.push(v.len() as i32);
+            v.push(MeshVertex::new(p[0], p[1], p[2], n[0], n[1], n[2], material_index, part.id));
+        });
+        //end is inclusive, see GpuMem::change_material
+        hashes.extend_from_slice(&[part.id as u32, start as u32, (v.len() - 1) as u32]);
+    });
+    (v, i, hashes)
+}
+
+//corners with the same quantized position and normal are shared inside a part, never across parts,
+//so a part stays a contiguous vertex range for the hash stream
+fn build_compact(pack_id: u32, parts: &Vec<&Part>, tot_bbx: &[f32; 6]) -> (Vec<CompactVertex>, Vec<PartEntry>, Quantization, Vec<i32>, Vec<u32>) {
+    let q = Quantization::from_bbx([tot_bbx[0], tot_bbx[1], tot_bbx[2]], [tot_bbx[3], tot_bbx[4], tot_bbx[5]]);
+    let mut cv: Vec<CompactVertex> = vec![];
+    let mut pt: Vec<PartEntry> = vec![];
+    let mut i: Vec<i32> = vec![];
+    let mut hashes: Vec<u32> = vec![];
+    parts.iter().enumerate().for_each(|(part_index, part)| {
+        let start = cv.len();
+        let mut shared: HashMap<([u16; 4], [i16; 2]), i32> = HashMap::new();
+        part.positions.iter().zip(part.normals.iter()).for_each(|(p, n)| {
+            let vertex = CompactVertex::new(&q, *p, *n, part_index as u32);
+            let index = *shared.entry((vertex.position, vertex.normal)).or_insert_with(|| {
+                cv.push(vertex);
+                (cv.len() - 1) as i32
+            });
+            i.push(index);
+        });
+        pt.push(PartEntry {
+            id: part.id,
+            material_index: pack_raw_id(part.ty as u32, pack_id) as i32,
+        });
+        hashes.extend_from_slice(&[part.id as u32, start as u32, (cv.len() - 1) as u32]);
+    });
+    (cv, pt, q, i, hashes)
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    fs::write(path, bytes).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+}
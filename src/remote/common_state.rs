@@ -1,6 +1,5 @@
 use std::sync::Mutex;
 use log::{Level, warn};
-use miniz_oxide::inflate::decompress_to_vec;
 use once_cell::sync::Lazy;
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::wasm_bindgen;
@@ -8,17 +7,60 @@ use wasm_bindgen::prelude::wasm_bindgen;
 use wasm_bindgen_futures::js_sys::{Float32Array, Uint8Array};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen_futures::js_sys::ArrayBuffer;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_futures::js_sys::{Function, Promise};
+#[cfg(target_arch = "wasm32")]
+use std::cell::RefCell;
+#[cfg(target_arch = "wasm32")]
+use std::collections::VecDeque;
 use web_sys::js_sys::{Int32Array, Uint32Array};
 
 use crate::device::message_controller::SnapMode;
-use crate::remote::{ArrayF32State, CommandState, RemoteCommand, RemoteMeshData};
+use crate::remote::{ArrayF32State, CableNetworkState, CommandState, PackStreamState, PartInfoState, RemoteCommand, RemoteMeshData};
+#[cfg(target_arch = "wasm32")]
+use crate::remote::PackStreamEvent;
 use crate::remote::RemoteCommand::SwitchToGameMode;
+#[cfg(target_arch = "wasm32")]
+use crate::scene::gpu_mem::ID_MEM_OFFSET;
+#[cfg(target_arch = "wasm32")]
+use crate::scene::pack_format::{parse_part_info, read_pack_streams, StreamCodec, StreamKind, MAX_STREAM_LEN};
+#[cfg(target_arch = "wasm32")]
+use crate::scene::load_error::LoadError;
+#[cfg(target_arch = "wasm32")]
+use crate::scene::mesh_loader::{check_hull_streams, check_pack_streams};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsValue;
+#[cfg(target_arch = "wasm32")]
+use crate::scene::assembly::{AssemblyAction, AssemblyNode, AssemblyTree};
+#[cfg(target_arch = "wasm32")]
+use crate::shared::materials_lib::Material;
+#[cfg(target_arch = "wasm32")]
+use crate::scene::cable_network::{CableNetwork, CABLE_PACK_ID};
+#[cfg(target_arch = "wasm32")]
+use crate::gui::clipping::{ClipPlane, SectionBox, MAX_CLIP_PLANES};
+#[cfg(target_arch = "wasm32")]
+use crate::gui::section_caps::{CapHatch, CapStyle};
+#[cfg(target_arch = "wasm32")]
+use crate::gui::outline::OutlineStyle;
+#[cfg(target_arch = "wasm32")]
+use crate::gui::ssao::SsaoStyle;
+#[cfg(target_arch = "wasm32")]
+use crate::gui::light_temperature::LIGHT_PRESETS;
+#[cfg(target_arch = "wasm32")]
+use cgmath::{Point3, Vector3};
+#[cfg(target_arch = "wasm32")]
+use crate::scene::section::SectionFormat;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_futures::js_sys::Error;
 
 
 pub static REMOTE_HULL_MESH: Lazy<Mutex<RemoteMeshData>> = Lazy::new(|| Mutex::new(RemoteMeshData::new()));
+pub static REMOTE_PACK_STREAM: Lazy<Mutex<PackStreamState>> = Lazy::new(|| Mutex::new(PackStreamState::new()));
 pub static DIMENSIONING: Lazy<Mutex<SnapMode>> = Lazy::new(|| Mutex::new(SnapMode::Disabled));
 pub static SLICER: Lazy<Mutex<ArrayF32State>> = Lazy::new(|| Mutex::new(ArrayF32State::new()));
 pub static COMMANDS: Lazy<Mutex<CommandState>> = Lazy::new(|| Mutex::new(CommandState::new()));
+pub static PART_INFO: Lazy<Mutex<PartInfoState>> = Lazy::new(|| Mutex::new(PartInfoState::new()));
+pub static CABLE_NETWORK: Lazy<Mutex<CableNetworkState>> = Lazy::new(|| Mutex::new(CableNetworkState::new()));
 
 pub unsafe fn debug_move_to() {
     match COMMANDS.lock() {
@@ -99,24 +141,17 @@ pub async unsafe fn enable_dimensioning(mode: i32) {
     }
 }
 
+//Errors reject the Promise with a JS Error named LoadError, see LoadError::to_js_value
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub async unsafe fn wasm_unpack_hull(arr_v: Uint8Array, arr_i: Uint8Array, arr_b: Uint8Array, arr_t: Uint8Array) -> bool {
+pub async unsafe fn wasm_unpack_hull(arr_v: Uint8Array, arr_i: Uint8Array, arr_b: Uint8Array, arr_t: Uint8Array) -> Result<bool, JsValue> {
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
     let _ = console_log::init_with_level(Level::Warn);
-    warn!("TRY UNPACK V {}", arr_v.length());
-    let handler_v: Vec<u8> = arr_v.to_vec();
-    let decoded_v: Vec<u8> = decompress_to_vec(&handler_v).unwrap();
-    warn!("TRY UNPACK I {}", arr_i.length());
-    let handler_i: Vec<u8> = arr_i.to_vec();
-    let decoded_i: Vec<u8> = decompress_to_vec(&handler_i).unwrap();
-    warn!("TRY UNPACK B {}", arr_b.length());
-    let handler_b: Vec<u8> = arr_b.to_vec();
-    let decoded_b: Vec<u8> = decompress_to_vec(&handler_b).unwrap();
-
-    warn!("TRY UNPACK T {}", arr_t.length());
-    let handler_t: Vec<u8> = arr_t.to_vec();
-    let decoded_t: Vec<u8> = decompress_to_vec(&handler_t).unwrap();
+    let decoded_v: Vec<u8> = inflate_stream(StreamKind::Vertices, &arr_v)?;
+    let decoded_i: Vec<u8> = inflate_stream(StreamKind::Indices, &arr_i)?;
+    let decoded_b: Vec<u8> = inflate_stream(StreamKind::Bbxs, &arr_b)?;
+    let decoded_t: Vec<u8> = inflate_stream(StreamKind::Hashes, &arr_t)?;
+    check_hull_streams(decoded_v.as_slice(), decoded_i.as_slice(), decoded_b.as_slice(), decoded_t.as_slice()).map_err(|e| e.to_js_value())?;
 
 
     //mesh_loader::read_unpacked_wasm(decoded_v, decoded_i, decoded_b, decoded_t);
@@ -130,39 +165,111 @@ pub async unsafe fn wasm_unpack_hull(arr_v: Uint8Array, arr_i: Uint8Array, arr_b
         Ok(mut m) => {
             m.clean();
             m.is_dirty = true;
-            m.decoded_v.extend_from_slice(decoded_v.as_slice());
-            m.decoded_i.extend_from_slice(decoded_i.as_slice());
-            m.decoded_b.extend_from_slice(decoded_b.as_slice());
-            m.decoded_t.extend_from_slice(decoded_t.as_slice());
+            m.streams.v = decoded_v;
+            m.streams.i = decoded_i;
+            m.streams.b = decoded_b;
+            m.streams.t = decoded_t;
         }
         Err(_e) => { warn!("CANT LOCK REMOTE_HULL_MESH MEM") }
     }
-    true
+    Ok(true)
+}
+
+//Single file pack (see scene::pack_format), pack id is taken from the header
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub async unsafe fn wasm_unpack_pack(arr: Uint8Array) -> Result<i32, JsValue> {
+    std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+    let _ = console_log::init_with_level(Level::Warn);
+    warn!("TRY UNPACK PACK {}", arr.length());
+    let handler: Vec<u8> = arr.to_vec();
+    let streams = read_pack_streams(handler.as_slice()).map_err(|e| e.to_js_value())?;
+    let pack_id = streams.pack_id;
+    if (pack_id >= CABLE_PACK_ID) {
+        return Err(LoadError::PackId { pack_id: pack_id }.to_js_value());
+    }
+    check_pack_streams(&streams).map_err(|e| e.to_js_value())?;
+    match REMOTE_HULL_MESH.lock() {
+        Ok(mut m) => {
+            m.clean();
+            m.is_dirty = true;
+            m.load_level = pack_id as i32;
+            m.streams = streams;
+        }
+        Err(_e) => { warn!("CANT LOCK REMOTE_HULL_MESH MEM") }
+    }
+    Ok(pack_id as i32)
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    //resolve and reject of pack_stream_end Promises, in the order of their End events
+    static PACK_STREAM_PROMISES: RefCell<VecDeque<(Function, Function)>> = RefCell::new(VecDeque::new());
+}
+
+//Streamed load of a single file pack: pack_stream_begin, pack_stream_push for every chunk as it arrives
+//(e.g. from a fetch body reader), then pack_stream_end. Only packed chunks wait here, the render loop
+//decodes them and uploads vertices to the GPU in slices. The Promise of pack_stream_end resolves with
+//the pack id or rejects with a LoadError, a pack with the same id is replaced only on success
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn pack_stream_begin() {
+    push_pack_stream_event(PackStreamEvent::Begin);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn pack_stream_push(chunk: Uint8Array) {
+    push_pack_stream_event(PackStreamEvent::Chunk(chunk.to_vec()));
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn pack_stream_end() -> Promise {
+    Promise::new(&mut |resolve, reject| {
+        PACK_STREAM_PROMISES.with(|p| p.borrow_mut().push_back((resolve, reject)));
+        push_pack_stream_event(PackStreamEvent::End);
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+fn push_pack_stream_event(event: PackStreamEvent) {
+    match REMOTE_PACK_STREAM.lock() {
+        Ok(mut m) => { m.values.push_back(event); }
+        Err(_e) => { warn!("CANT LOCK REMOTE_PACK_STREAM MEM") }
+    }
+}
+
+//called by the render loop when an End event is handled
+#[cfg(target_arch = "wasm32")]
+pub fn on_pack_stream_done(result: &Result<u32, LoadError>) {
+    PACK_STREAM_PROMISES.with(|p| {
+        match p.borrow_mut().pop_front() {
+            None => {}
+            Some((resolve, reject)) => {
+                let _ = match result {
+                    Ok(pack_id) => resolve.call1(&JsValue::NULL, &JsValue::from(*pack_id)),
+                    Err(e) => reject.call1(&JsValue::NULL, &e.to_js_value()),
+                };
+            }
+        }
+    });
 }
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub async unsafe fn wasm_unpack_hull_with_packs(load_state: i32, arr_v: Uint8Array, arr_i: Uint8Array, arr_b: Uint8Array, arr_t: Uint8Array) -> Uint8Array {
+pub async unsafe fn wasm_unpack_hull_with_packs(load_state: i32, arr_v: Uint8Array, arr_i: Uint8Array, arr_b: Uint8Array, arr_t: Uint8Array) -> Result<Uint8Array, JsValue> {
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
     let _ = console_log::init_with_level(Level::Warn);
-    warn!("TRY UNPACK V {}", arr_v.length());
-    let mut handler_v: Vec<u8> = arr_v.to_vec();
-    let decoded_v: Vec<u8> = decompress_to_vec(&handler_v).unwrap();
-    handler_v = vec![];
-    warn!("TRY UNPACK I {}", arr_i.length());
-    let handler_i: Vec<u8> = arr_i.to_vec();
-    let decoded_i: Vec<u8> = decompress_to_vec(&handler_i).unwrap();
-    warn!("TRY UNPACK B {}", arr_b.length());
-    let handler_b: Vec<u8> = arr_b.to_vec();
-    let decoded_b: Vec<u8> = decompress_to_vec(&handler_b).unwrap();
-
-    warn!("TRY UNPACK T {}", arr_t.length());
-    let handler_t: Vec<u8> = arr_t.to_vec();
-    let decoded_t: Vec<u8> = decompress_to_vec(&handler_t).unwrap();
+    let decoded_v: Vec<u8> = inflate_stream(StreamKind::Vertices, &arr_v)?;
+    let decoded_i: Vec<u8> = inflate_stream(StreamKind::Indices, &arr_i)?;
+    let decoded_b: Vec<u8> = inflate_stream(StreamKind::Bbxs, &arr_b)?;
+    let decoded_t: Vec<u8> = inflate_stream(StreamKind::Hashes, &arr_t)?;
+    check_hull_streams(decoded_v.as_slice(), decoded_i.as_slice(), decoded_b.as_slice(), decoded_t.as_slice()).map_err(|e| e.to_js_value())?;
 
 
     //(
-    Uint8Array::from(decoded_v.as_slice())
+    Ok(Uint8Array::from(decoded_v.as_slice()))
     //Uint8Array::from(decoded_i.as_slice()),
     //Uint8Array::from(decoded_b.as_slice()),
     //Uint8Array::from(decoded_t.as_slice()),
@@ -173,29 +280,142 @@ pub async unsafe fn wasm_unpack_hull_with_packs(load_state: i32, arr_v: Uint8Arr
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub async unsafe fn wasm_unpack(arr: Uint8Array) -> Uint8Array {
+pub async unsafe fn wasm_unpack(arr: Uint8Array) -> Result<Uint8Array, JsValue> {
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
     let _ = console_log::init_with_level(Level::Warn);
     warn!("TRY UNPACK {}", arr.length());
-    let mut handler_v: Vec<u8> = arr.to_vec();
-    let decoded_v: Vec<u8> = decompress_to_vec(&handler_v).unwrap();
-    Uint8Array::from(decoded_v.as_slice())
+    let handler_v: Vec<u8> = arr.to_vec();
+    let decoded_v: Vec<u8> = StreamCodec::sniff(handler_v.as_slice()).decode(StreamKind::Meta, handler_v.as_slice(), MAX_STREAM_LEN).map_err(|e| {
+        match e {
+            LoadError::Decompress { message, .. } => LoadError::Decompress { stream: "data", message: message },
+            e => e
+        }.to_js_value()
+    })?;
+    Ok(Uint8Array::from(decoded_v.as_slice()))
+}
+
+//old layout files are deflate or zstd, told apart by the zstd magic
+#[cfg(target_arch = "wasm32")]
+fn inflate_stream(kind: StreamKind, arr: &Uint8Array) -> Result<Vec<u8>, JsValue> {
+    warn!("TRY UNPACK {} {}", kind.name(), arr.length());
+    let handler: Vec<u8> = arr.to_vec();
+    StreamCodec::sniff(handler.as_slice()).decode(kind, handler.as_slice(), MAX_STREAM_LEN).map_err(|e| e.to_js_value())
+}
+
+//json of the part, undefined if no loaded pack has info for it
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn get_part_info(id: i32) -> Option<String> {
+    match PART_INFO.lock() {
+        Ok(m) => m.find(id).and_then(|p| serde_json::to_string(p).ok()),
+        Err(_e) => {
+            warn!("CANT LOCK PART_INFO MEM");
+            None
+        }
+    }
+}
+
+//sidecar json for a pack without an info stream, a json array of parts, see PartInfo.
+//It is kept until the pack is unloaded or replaced
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_part_info(pack_id: i32, json: String) -> Result<(), JsValue> {
+    if (pack_id < 0 || pack_id >= CABLE_PACK_ID as i32) {
+        return Err(LoadError::PackId { pack_id: pack_id as u32 }.to_js_value());
+    }
+    let info = parse_part_info(json.as_bytes()).map_err(|e| e.to_js_value())?;
+    match PART_INFO.lock() {
+        Ok(mut m) => {
+            m.set_pack(pack_id as u32, info);
+        }
+        Err(_e) => { warn!("CANT LOCK PART_INFO MEM") }
+    }
+    Ok(())
+}
+
+//tree of the part info as json, see AssemblyTree::to_json
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn get_assembly_tree() -> String {
+    match PART_INFO.lock() {
+        Ok(m) => AssemblyTree::build(m.packs.values().flat_map(|p| p.values())).to_json(),
+        Err(_e) => {
+            warn!("CANT LOCK PART_INFO MEM");
+            "".to_string()
+        }
+    }
 }
 
+//path is "" for the ship, "B12" for a block, "B12/S3" for a section or "#1904245" for a part
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub fn load_all_packs_to_gpu() {
+pub fn assembly_select(path: String) {
+    push_assembly_command(AssemblyAction::Select, path);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn assembly_hide(path: String) {
+    push_assembly_command(AssemblyAction::Hide, path);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn assembly_show(path: String) {
+    push_assembly_command(AssemblyAction::Show, path);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn assembly_isolate(path: String) {
+    push_assembly_command(AssemblyAction::Isolate, path);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn assembly_zoom(path: String) {
+    push_assembly_command(AssemblyAction::Zoom, path);
+}
+
+//group is an open color name (gray, red, blue...), shade is 0..9
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn assembly_color(path: String, group: String, shade: i32) {
+    match usize::try_from(shade).ok().and_then(|shade| Material::palette_index(group.as_str(), shade)) {
+        None => { warn!("COLOR {} {} IS NOT IN PALETTE", group, shade); }
+        Some(mat) => { push_assembly_command(AssemblyAction::Color(mat), path); }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn assembly_clear_color(path: String) {
+    push_assembly_command(AssemblyAction::ClearColor, path);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn push_assembly_command(action: AssemblyAction, path: String) {
+    match AssemblyNode::parse(path.as_str()) {
+        None => { warn!("ASSEMBLY PATH {} IS NOT VALID", path); }
+        Some(node) => {
+            match COMMANDS.lock() {
+                Ok(mut m) => {
+                    m.values.push_back(RemoteCommand::OnAssembly((action, node)));
+                }
+                Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn load_all_packs_to_gpu(pack_count: i32) {
     match COMMANDS.lock() {
         Ok(mut m) => {
-            //1904245 17193573
-            m.values.push_back(RemoteCommand::LoadAllToGPU(0));
-            m.values.push_back(RemoteCommand::LoadAllToGPU(1));
-            m.values.push_back(RemoteCommand::LoadAllToGPU(2));
-            m.values.push_back(RemoteCommand::LoadAllToGPU(3));
-            m.values.push_back(RemoteCommand::LoadAllToGPU(4));
-            m.values.push_back(RemoteCommand::LoadAllToGPU(5));
-            m.values.push_back(RemoteCommand::LoadAllToGPU(6));
-            m.values.push_back(RemoteCommand::LoadAllToGPU(7));
+            for pack_id in 0..pack_count.min(CABLE_PACK_ID as i32) {
+                m.values.push_back(RemoteCommand::LoadAllToGPU(pack_id));
+            }
         }
         Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
     }
@@ -204,21 +424,45 @@ pub fn load_all_packs_to_gpu() {
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub fn load_pack_to_gpu(pack_id: i32) {
+    if (pack_id < 0 || pack_id >= CABLE_PACK_ID as i32) {
+        warn!("PACK ID {} IS OUT OF RANGE 0..{}", pack_id, CABLE_PACK_ID);
+        return;
+    }
     match COMMANDS.lock() {
         Ok(mut m) => {
-            match pack_id {
-                0 => { m.values.push_back(RemoteCommand::LoadAllToGPU(0)); }
-                1 => { m.values.push_back(RemoteCommand::LoadAllToGPU(1)); }
-                2 => { m.values.push_back(RemoteCommand::LoadAllToGPU(2)); }
-                3 => { m.values.push_back(RemoteCommand::LoadAllToGPU(3)); }
-                4 => { m.values.push_back(RemoteCommand::LoadAllToGPU(4)); }
-                5 => { m.values.push_back(RemoteCommand::LoadAllToGPU(5)); }
-                6 => { m.values.push_back(RemoteCommand::LoadAllToGPU(6)); }
-                7 => { m.values.push_back(RemoteCommand::LoadAllToGPU(7)); }
-                _ => {}
-            }
+            m.values.push_back(RemoteCommand::LoadAllToGPU(pack_id));
+        }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
 
-            //1904245 17193573
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn unload_pack(pack_id: i32) {
+    if (pack_id < 0 || pack_id >= ID_MEM_OFFSET as i32) {
+        warn!("PACK ID {} IS OUT OF RANGE 0..{}", pack_id, ID_MEM_OFFSET);
+        return;
+    }
+    match COMMANDS.lock() {
+        Ok(mut m) => {
+            m.values.push_back(RemoteCommand::UnloadPack(pack_id));
+        }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//pack is read again from the arrays of wvservice, wasm_unpack_pack with a loaded pack id replaces it as well.
+//Reported like load_pack_to_gpu, on_load_error keeps the old pack loaded since the arrays are read before it is dropped
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn replace_pack(pack_id: i32) {
+    if (pack_id < 0 || pack_id >= CABLE_PACK_ID as i32) {
+        warn!("PACK ID {} IS OUT OF RANGE 0..{}", pack_id, CABLE_PACK_ID);
+        return;
+    }
+    match COMMANDS.lock() {
+        Ok(mut m) => {
+            m.values.push_back(RemoteCommand::ReplacePack(pack_id));
         }
         Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
     }
@@ -251,4 +495,360 @@ pub fn set_transparent(arr: Int32Array) {
     }
 }
 
+//cable network from the contents of nodes.json, cab_nodes.json, routes.json and segments.json,
+//it is drawn as its own pack CABLE_PACK_ID and replaces the loaded network
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_cable_network(nodes: String, cab_nodes: String, routes: String, segments: String) -> Result<(), JsValue> {
+    let network = CableNetwork::from_json(nodes.as_bytes(), cab_nodes.as_bytes(), routes.as_bytes(), segments.as_bytes()).map_err(|e| e.to_js_value())?;
+    match CABLE_NETWORK.lock() {
+        Ok(mut m) => { m.set(Some(network)); }
+        Err(_e) => { warn!("CANT LOCK CABLE_NETWORK MEM") }
+    }
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn clear_cable_network() {
+    match CABLE_NETWORK.lock() {
+        Ok(mut m) => { m.set(None); }
+        Err(_e) => { warn!("CANT LOCK CABLE_NETWORK MEM") }
+    }
+}
+
+//lights the route of the cable and frames the camera on it, returns the routed length in mm,
+//undefined if the loaded network has no such cable
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn highlight_cable(cable_id: i32) -> Option<f64> {
+    let trace = match CABLE_NETWORK.lock() {
+        Ok(m) => m.network.as_ref().and_then(|n| n.trace_cable(cable_id)),
+        Err(_e) => {
+            warn!("CANT LOCK CABLE_NETWORK MEM");
+            None
+        }
+    };
+    match trace {
+        None => None,
+        Some(trace) => {
+            let length = trace.length;
+            match COMMANDS.lock() {
+                Ok(mut m) => { m.values.push_back(RemoteCommand::HighlightCable(trace)); }
+                Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+            }
+            Some(length)
+        }
+    }
+}
 
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn clear_cable_highlight() {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::ClearCableHighlight()); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//lights the shortest route between two nodes over the segments of the loaded network and frames the camera on it,
+//returns JSON {"nodes": [...], "length": mm}, undefined if the nodes are not connected
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn cable_shortest_path(from: i32, to: i32) -> Option<String> {
+    let path = match CABLE_NETWORK.lock() {
+        Ok(m) => m.network.as_ref().and_then(|n| n.trace_shortest_path(from, to)),
+        Err(_e) => {
+            warn!("CANT LOCK CABLE_NETWORK MEM");
+            None
+        }
+    };
+    match path {
+        None => None,
+        Some(trace) => {
+            let json = serde_json::to_string(&serde_json::json!({"nodes": trace.nodes, "length": trace.length})).ok();
+            match COMMANDS.lock() {
+                Ok(mut m) => { m.values.push_back(RemoteCommand::HighlightCablePath(trace)); }
+                Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+            }
+            json
+        }
+    }
+}
+
+//dangling nodes, connected sub-networks and the number of cables of every segment as JSON,
+//undefined if no network is loaded
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn get_cable_network_report() -> Option<String> {
+    match CABLE_NETWORK.lock() {
+        Ok(m) => m.network.as_ref().and_then(|n| serde_json::to_string(&n.report()).ok()),
+        Err(_e) => {
+            warn!("CANT LOCK CABLE_NETWORK MEM");
+            None
+        }
+    }
+}
+
+//colours the segments by their load, gray for empty ones up to red for the fullest
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn cable_load_colors(is_on: bool) {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::CableLoadColors(is_on)); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//px, py, pz, nx, ny, nz per plane, the side the normal points to is kept; replaces the planes set before,
+//an empty array removes them. Returns the number of planes used, at most 8
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_clip_planes(planes: Float32Array) -> u32 {
+    let planes: Vec<ClipPlane> = ClipPlane::from_floats(planes.to_vec().as_slice());
+    let count = planes.len().min(MAX_CLIP_PLANES) as u32;
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::SetClipPlanes(planes)); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+    count
+}
+
+//center, x axis, y axis and half sizes, everything outside the box is cut. False if the values give no box
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_section_box(values: Float32Array) -> bool {
+    match SectionBox::from_floats(values.to_vec().as_slice()) {
+        None => false,
+        Some(section_box) => {
+            match COMMANDS.lock() {
+                Ok(mut m) => { m.values.push_back(RemoteCommand::SetSectionBox(Some(section_box))); }
+                Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+            }
+            true
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn clear_section_box() {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::SetSectionBox(None)); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//turns the section box around its center, a box around the scene is made first if there is none
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn rotate_section_box(axis_x: f32, axis_y: f32, axis_z: f32, degrees: f32) {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::RotateSectionBox((Vector3::new(axis_x, axis_y, axis_z), degrees))); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//adds a plane through the picked point square to the view, the far side is kept
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn clip_plane_from_camera() {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::ClipPlaneFromCamera()); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//fills the faces cut by the slicer, the clip planes and the section box
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn section_caps(is_on: bool) {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::SectionCaps(is_on)); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//cap of the parts of a type (a HullPartTypes code), rgb is 0xRRGGBB, hatch 0 solid, 1 lines, 2 cross,
+//spacing of the lines in px and their angle in degrees. False if the hatch is unknown
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_section_cap_style(ty: i32, rgb: u32, hatch: i32, spacing: f32, angle: f32) -> bool {
+    match CapHatch::from_code(hatch) {
+        None => false,
+        Some(hatch) => {
+            match COMMANDS.lock() {
+                Ok(mut m) => { m.values.push_back(RemoteCommand::SectionCapStyle((ty, CapStyle::from_rgb(rgb, hatch, spacing, angle)))); }
+                Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+            }
+            true
+        }
+    }
+}
+
+//edges of parts drawn over the hull: silhouettes, borders between parts and creases
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn outline(is_on: bool) {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::Outline(is_on)); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//rgb is 0xRRGGBB, width in px (1 to 8), faces turned by more than crease_angle degrees get a line between them
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_outline_style(rgb: u32, width: f32, crease_angle: f32) {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::OutlineStyle(OutlineStyle::from_rgb(rgb, width, crease_angle))); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//ambient occlusion, darkens corners and narrow spaces
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn ssao(is_on: bool) {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::Ssao(is_on)); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//radius in mm, strength 0 to 4 (1 by default), blur radius in px 0 to 4
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_ssao_style(radius: f32, strength: f32, blur: f32) {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::SsaoStyle(SsaoStyle::new(radius, strength, blur))); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//directional light with shadows, the eye light stays
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn sun(is_on: bool) {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::Sun(is_on)); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//azimuth in degrees from the bow turning to port, elevation in degrees above the deck, intensity 0 to 2
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_sun(azimuth: f32, elevation: f32, intensity: f32) {
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::SunPosition((azimuth, elevation, intensity))); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//colour temperature of the eye light in kelvin, 0 or less is white
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_light_temperature(kelvin: f32) {
+    let kelvin = if (kelvin > 0.0) { Some(kelvin) } else { None };
+    match COMMANDS.lock() {
+        Ok(mut m) => { m.values.push_back(RemoteCommand::LightTemperature(kelvin)); }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+//sodium, incandescent, halogen, fluorescent, daylight or overcast, false if there is no such preset
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_light_preset(name: String) -> bool {
+    if (!LIGHT_PRESETS.iter().any(|(n, _k)| n.eq_ignore_ascii_case(name.as_str()))) {
+        warn!("LIGHT: NO PRESET {}", name);
+        return false;
+    }
+    match COMMANDS.lock() {
+        Ok(mut m) => {
+            m.values.push_back(RemoteCommand::LightPreset(name));
+            true
+        }
+        Err(_e) => {
+            warn!("CANT LOCK COMMANDS MEM");
+            false
+        }
+    }
+}
+
+//removes the clip planes and the section box
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn clear_clipping() {
+    match COMMANDS.lock() {
+        Ok(mut m) => {
+            m.values.push_back(RemoteCommand::SetClipPlanes(vec![]));
+            m.values.push_back(RemoteCommand::SetSectionBox(None));
+        }
+        Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    //resolve and reject of export_section Promises, in the order of their commands
+    static SECTION_PROMISES: RefCell<VecDeque<(Function, Function)>> = RefCell::new(VecDeque::new());
+}
+
+//Cut of the visible parts by a plane as a drawing with a layer per part type, format is "svg" or "dxf".
+//plane is px, py, pz, nx, ny, nz and the cut is seen from the side of the normal, an empty array takes
+//the plane of the first moved slicer sensor. The Promise resolves with the text of the file
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn export_section(format: String, plane: Float32Array) -> Promise {
+    let values = plane.to_vec();
+    Promise::new(&mut |resolve, reject| {
+        let format = match SectionFormat::from_name(format.as_str()) {
+            None => {
+                let _ = reject.call1(&JsValue::NULL, &Error::new(&format!("unknown section format {}", format)));
+                return;
+            }
+            Some(f) => f,
+        };
+        let plane = match values.len() {
+            0 => None,
+            6 => {
+                match ClipPlane::new(Point3::new(values[0], values[1], values[2]), Vector3::new(values[3], values[4], values[5])) {
+                    None => {
+                        let _ = reject.call1(&JsValue::NULL, &Error::new("section plane has no normal"));
+                        return;
+                    }
+                    Some(p) => Some(p),
+                }
+            }
+            n => {
+                let _ = reject.call1(&JsValue::NULL, &Error::new(&format!("section plane needs 6 values, got {}", n)));
+                return;
+            }
+        };
+        match COMMANDS.lock() {
+            Ok(mut m) => {
+                SECTION_PROMISES.with(|p| p.borrow_mut().push_back((resolve, reject)));
+                m.values.push_back(RemoteCommand::ExportSection((plane, format)));
+            }
+            Err(_e) => { warn!("CANT LOCK COMMANDS MEM") }
+        }
+    })
+}
+
+//called by the render loop when an ExportSection command is handled
+#[cfg(target_arch = "wasm32")]
+pub fn on_section_done(result: &Result<String, String>) {
+    SECTION_PROMISES.with(|p| {
+        match p.borrow_mut().pop_front() {
+            None => {}
+            Some((resolve, reject)) => {
+                let _ = match result {
+                    Ok(text) => resolve.call1(&JsValue::NULL, &JsValue::from_str(text)),
+                    Err(e) => reject.call1(&JsValue::NULL, &Error::new(e)),
+                };
+            }
+        }
+    });
+}
@@ -6,7 +6,7 @@ use once_cell::sync::Lazy;
 use crate::remote::HashI32State;
 
 #[cfg(target_arch = "wasm32")]
-use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen_futures::js_sys::{Float32Array, Int32Array};
 #[cfg(target_arch = "wasm32")]
@@ -117,9 +117,15 @@ extern "C" {
 
     #[wasm_bindgen(js_namespace = wvservice)]
     pub fn get_mesh_vertex_by_id(pack_id:i32,index:i32)->Uint8Array;
+    //vertices first..=last of a pack in one call
+    #[wasm_bindgen(js_namespace = wvservice)]
+    pub fn get_mesh_vertices_by_range(pack_id:i32,first:i32,last:i32)->Uint8Array;
 
     #[wasm_bindgen(js_namespace = wvservice)]
     pub fn on_load_to_gpu(pack_id:i32);
+    //error is a LoadError, see LoadError::to_js_value
+    #[wasm_bindgen(js_namespace = wvservice)]
+    pub fn on_load_error(pack_id:i32, error:JsValue);
 
 
     #[wasm_bindgen(js_namespace = wvservice)]
@@ -1,7 +1,16 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use wgpu::BufferSlice;
 use winit::dpi::PhysicalPosition;
 use winit::event::{DeviceId, ElementState, KeyEvent, MouseButton, MouseScrollDelta, TouchPhase};
+use cgmath::Vector3;
+use crate::gui::clipping::{ClipPlane, SectionBox};
+use crate::gui::section_caps::CapStyle;
+use crate::gui::outline::OutlineStyle;
+use crate::gui::ssao::SsaoStyle;
+use crate::scene::assembly::{AssemblyAction, AssemblyNode};
+use crate::scene::cable_network::{CableNetwork, CableTrace};
+use crate::scene::pack_format::{PackStreams, PartInfo};
+use crate::scene::section::SectionFormat;
 
 pub mod hull_state;
 pub mod common_state;
@@ -11,6 +20,9 @@ pub enum RemoteCommand {
     MoveCameraToStartPos,
     MoveCameraToOID(i32),
     LoadAllToGPU(i32),
+    UnloadPack(i32),
+    //reloads the pack from its source (files given in command line, JS arrays on web) in place of the loaded one
+    ReplacePack(i32),
     SwitchToGameMode(),
 
     OnMouseMove((DeviceId, PhysicalPosition<f64>)),
@@ -20,6 +32,37 @@ pub enum RemoteCommand {
     OnMouseButton((DeviceId, ElementState, MouseButton)),
     OnOffScreenReady(),
     OnSetTransparentMat((i32, i32)),
+    OnAssembly((AssemblyAction, AssemblyNode)),
+    //traced once by highlight_cable, so the length it returns is the one drawn
+    HighlightCable(CableTrace),
+    ClearCableHighlight(),
+    HighlightCablePath(CableTrace),
+    CableLoadColors(bool),
+    //replaces all clip planes, an empty list removes them
+    SetClipPlanes(Vec<ClipPlane>),
+    SetSectionBox(Option<SectionBox>),
+    //axis and degrees, a box around the scene is made if there is none
+    RotateSectionBox((Vector3<f32>, f32)),
+    //plane through the picked point square to the view, the far side is kept
+    ClipPlaneFromCamera(),
+    //fills the cut faces of the slicer, planes and section box
+    SectionCaps(bool),
+    //cap of the parts of a type, see Material::type_to_color
+    SectionCapStyle((i32, CapStyle)),
+    //cut of the visible parts as a drawing, the plane of the slicer if None, see Section::plane_from_slicer
+    ExportSection((Option<ClipPlane>, SectionFormat)),
+    //silhouettes, part borders and creases over the hull
+    Outline(bool),
+    OutlineStyle(OutlineStyle),
+    //ambient occlusion of the hull, radius, strength and blur
+    Ssao(bool),
+    SsaoStyle(SsaoStyle),
+    //directional light with shadows besides the eye light, azimuth and elevation in degrees and intensity
+    Sun(bool),
+    SunPosition((f32, f32, f32)),
+    //colour temperature of the eye light in kelvin, white if None, or a named preset, see LIGHT_PRESETS
+    LightTemperature(Option<f32>),
+    LightPreset(String),
 
 }
 
@@ -39,6 +82,77 @@ impl CommandState {
     }
 }
 
+//streamed pack load, events are handled in order by the render loop
+pub enum PackStreamEvent {
+    Begin,
+    Chunk(Vec<u8>),
+    End,
+}
+
+pub struct PackStreamState {
+    pub values: VecDeque<PackStreamEvent>,
+}
+
+impl PackStreamState {
+    pub fn new() -> Self {
+        Self {
+            values: VecDeque::new(),
+        }
+    }
+}
+
+//part info of the loaded packs by pack id and part id, from info streams or sidecar json
+pub struct PartInfoState {
+    pub packs: HashMap<u32, HashMap<i32, PartInfo>>,
+    //the assembly tree is built again when set
+    pub is_dirty: bool,
+}
+
+impl PartInfoState {
+    pub fn new() -> Self {
+        Self {
+            packs: HashMap::new(),
+            is_dirty: false,
+        }
+    }
+    pub fn set_pack(&mut self, pack_id: u32, info: Vec<PartInfo>) {
+        self.packs.insert(pack_id, info.into_iter().map(|p| (p.id, p)).collect());
+        self.is_dirty = true;
+    }
+    pub fn remove_pack(&mut self, pack_id: u32) {
+        if (self.packs.remove(&pack_id).is_some()) {
+            self.is_dirty = true;
+        }
+    }
+    pub fn get(&self, pack_id: u32, id: i32) -> Option<&PartInfo> {
+        self.packs.get(&pack_id).and_then(|p| p.get(&id))
+    }
+    //part ids are unique in a model, so any pack will do
+    pub fn find(&self, id: i32) -> Option<&PartInfo> {
+        self.packs.values().find_map(|p| p.get(&id))
+    }
+}
+
+//cable network given from outside, the render loop builds its pack when it is set
+pub struct CableNetworkState {
+    pub network: Option<CableNetwork>,
+    pub is_dirty: bool,
+}
+
+impl CableNetworkState {
+    pub fn new() -> Self {
+        Self {
+            network: None,
+            is_dirty: false,
+        }
+    }
+    //None unloads the cable pack
+    pub fn set(&mut self, network: Option<CableNetwork>) {
+        self.network = network;
+        self.is_dirty = true;
+    }
+}
+
 pub struct HashI32State {
     pub values: HashSet<i32>,
     pub is_dirty: bool,
@@ -70,10 +184,7 @@ impl ArrayF32State {
 pub struct RemoteMeshData {
     pub is_dirty: bool,
     pub load_level: i32,
-    pub decoded_v: Vec<u8>,
-    pub decoded_i: Vec<u8>,
-    pub decoded_b: Vec<u8>,
-    pub decoded_t: Vec<u8>,
+    pub streams: PackStreams,
 }
 
 impl RemoteMeshData {
@@ -81,18 +192,12 @@ impl RemoteMeshData {
         Self {
             is_dirty: false,
             load_level: 0,
-            decoded_v: vec![],
-            decoded_i: vec![],
-            decoded_b: vec![],
-            decoded_t: vec![],
+            streams: PackStreams::default(),
         }
     }
     pub fn clean(&mut self) {
         self.is_dirty = false;
         self.load_level = 0;
-        self.decoded_v = vec![];
-        self.decoded_i = vec![];
-        self.decoded_b = vec![];
-        self.decoded_t = vec![];
+        self.streams = PackStreams::default();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, BTreeSet};
+use serde::Serialize;
+
+use crate::scene::pack_format::PartInfo;
+
+//block or section of parts whose info does not set it
+pub const UNASSIGNED: &str = "-";
+
+//Node of the assembly tree ship -> block -> section -> part
+#[derive(Clone, Debug, PartialEq)]
+pub enum AssemblyNode {
+    Ship,
+    Block(String),
+    Section(String, String),
+    Part(i32),
+}
+
+impl AssemblyNode {
+    //"" is the ship, "B12" a block, "B12/S3" a section, "#1904245" a part
+    pub fn parse(path: &str) -> Option<AssemblyNode> {
+        let path = path.trim();
+        if (path.is_empty()) {
+            return Some(AssemblyNode::Ship);
+        }
+        match path.strip_prefix('#') {
+            Some(id) => id.parse::<i32>().ok().map(AssemblyNode::Part),
+            None => {
+                match path.split_once('/') {
+                    None => Some(AssemblyNode::Block(path.to_string())),
+                    Some((block, section)) => {
+                        if (block.is_empty() || section.is_empty() || section.contains('/')) {
+                            None
+                        } else {
+                            Some(AssemblyNode::Section(block.to_string(), section.to_string()))
+                        }
+                    }
+                }
+            }
+        }
+    }
+    pub fn path(&self) -> String {
+        match self {
+            AssemblyNode::Ship => "".to_string(),
+            AssemblyNode::Block(block) => block.clone(),
+            AssemblyNode::Section(block, section) => format!("{}/{}", block, section),
+            AssemblyNode::Part(id) => format!("#{}", id),
+        }
+    }
+}
+
+//what is done with all parts of a node
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AssemblyAction {
+    Select,
+    Hide,
+    Show,
+    //shows the node and hides the rest
+    Isolate,
+    Zoom,
+    //material index, see Material::palette_index
+    Color(i32),
+    ClearColor,
+}
+
+#[derive(Serialize)]
+struct JsonNode {
+    name: String,
+    path: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<JsonNode>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parts: Vec<i32>,
+}
+
+//Blocks and sections come from part info, parts without info are only under the ship
+#[derive(Clone, Debug, Default)]
+pub struct AssemblyTree {
+    blocks: BTreeMap<String, BTreeMap<String, BTreeSet<i32>>>,
+}
+
+impl AssemblyTree {
+    pub fn build<'a>(parts: impl Iterator<Item=&'a PartInfo>) -> Self {
+        let mut blocks: BTreeMap<String, BTreeMap<String, BTreeSet<i32>>> = BTreeMap::new();
+        parts.for_each(|p| {
+            let block = p.block.clone().unwrap_or(UNASSIGNED.to_string());
+            let section = p.section.clone().unwrap_or(UNASSIGNED.to_string());
+            blocks.entry(block).or_default().entry(section).or_default().insert(p.id);
+        });
+        Self {
+            blocks: blocks,
+        }
+    }
+    //ids under the node, the ship gives the ids of the tree only, None if there is no such node
+    pub fn ids(&self, node: &AssemblyNode) -> Option<Vec<i32>> {
+        match node {
+            AssemblyNode::Ship => Some(self.blocks.values().flat_map(|b| b.values().flatten()).copied().collect()),
+            AssemblyNode::Block(block) => self.blocks.get(block).map(|b| b.values().flatten().copied().collect()),
+            AssemblyNode::Section(block, section) => self.blocks.get(block).and_then(|b| b.get(section)).map(|s| s.iter().copied().collect()),
+            AssemblyNode::Part(id) => Some(vec![*id]),
+        }
+    }
+    //{"name":"ship","path":"","children":[{"name":"B12","path":"B12","children":[{"name":"S3","path":"B12/S3","parts":[1904245]}]}]}
+    pub fn to_json(&self) -> String {
+        let children: Vec<JsonNode> = self.blocks.iter().map(|(block, sections)| {
+            JsonNode {
+                name: block.clone(),
+                path: AssemblyNode::Block(block.clone()).path(),
+                children: sections.iter().map(|(section, ids)| {
+                    JsonNode {
+                        name: section.clone(),
+                        path: AssemblyNode::Section(block.clone(), section.clone()).path(),
+                        children: vec![],
+                        parts: ids.iter().copied().collect(),
+                    }
+                }).collect(),
+                parts: vec![],
+            }
+        }).collect();
+        let ship = JsonNode {
+            name: "ship".to_string(),
+            path: AssemblyNode::Ship.path(),
+            children: children,
+            parts: vec![],
+        };
+        serde_json::to_string(&ship).unwrap_or_default()
+    }
+}
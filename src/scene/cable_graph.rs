@@ -0,0 +1,255 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use cgmath::MetricSpace;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::scene::cable_network::{CableNetwork, CableTrace};
+use crate::shared::materials_lib::Material;
+
+//Links of the cable network as an undirected graph, edge weight is the link length in mm
+pub struct CableGraph {
+    adjacency: HashMap<i32, Vec<(i32, f64)>>,
+}
+
+//open set entry of shortest_path, the heap pops the shortest distance first
+#[derive(PartialEq)]
+struct Visit {
+    dist: f64,
+    node: i32,
+}
+
+impl Eq for Visit {}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.dist.total_cmp(&self.dist).then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+//result of get_cable_network_report on web
+#[derive(Serialize)]
+pub struct CableGraphReport {
+    pub nodes: usize,
+    pub links: usize,
+    //nodes with at most one neighbour
+    pub dangling: Vec<i32>,
+    //node ids of every connected sub-network, largest first
+    pub components: Vec<Vec<i32>>,
+    //link part id -> number of cables routed through it, links without cables are left out
+    pub loads: HashMap<i32, usize>,
+}
+
+impl CableGraph {
+    pub fn build(network: &CableNetwork) -> Self {
+        let mut adjacency: HashMap<i32, Vec<(i32, f64)>> = network.nodes.keys().map(|id| (*id, vec![])).collect();
+        network.links.iter().for_each(|l| {
+            let length = network.nodes[&l.node1].p.distance(network.nodes[&l.node2].p) as f64;
+            adjacency.entry(l.node1).or_default().push((l.node2, length));
+            adjacency.entry(l.node2).or_default().push((l.node1, length));
+        });
+        Self {
+            adjacency: adjacency,
+        }
+    }
+
+    //Dijkstra over link lengths, nodes from -> to and the length in mm, None if they are not connected
+    pub fn shortest_path(&self, from: i32, to: i32) -> Option<(Vec<i32>, f64)> {
+        if (!self.adjacency.contains_key(&from) || !self.adjacency.contains_key(&to)) {
+            return None;
+        }
+        let mut dist: HashMap<i32, f64> = HashMap::new();
+        let mut prev: HashMap<i32, i32> = HashMap::new();
+        let mut heap: BinaryHeap<Visit> = BinaryHeap::new();
+        dist.insert(from, 0.0);
+        heap.push(Visit { dist: 0.0, node: from });
+        while let Some(Visit { dist: d, node }) = heap.pop() {
+            if (node == to) {
+                break;
+            }
+            if (d > dist.get(&node).copied().unwrap_or(f64::INFINITY)) {
+                continue;
+            }
+            self.adjacency[&node].iter().for_each(|(next, length)| {
+                let nd = d + length;
+                if (nd < dist.get(next).copied().unwrap_or(f64::INFINITY)) {
+                    dist.insert(*next, nd);
+                    prev.insert(*next, node);
+                    heap.push(Visit { dist: nd, node: *next });
+                }
+            });
+        }
+        let length = dist.get(&to).copied()?;
+        let mut path: Vec<i32> = vec![to];
+        while let Some(p) = prev.get(path.last().unwrap()) {
+            path.push(*p);
+        }
+        path.reverse();
+        Some((path, length))
+    }
+
+    pub fn dangling_nodes(&self) -> Vec<i32> {
+        self.adjacency.iter()
+            .filter(|(_id, links)| links.iter().map(|(next, _l)| next).unique().count() <= 1)
+            .map(|(id, _links)| *id)
+            .sorted()
+            .collect()
+    }
+
+    //connected sub-networks, largest first, a node without links is a sub-network of its own
+    pub fn components(&self) -> Vec<Vec<i32>> {
+        let mut visited: HashSet<i32> = HashSet::new();
+        let mut out: Vec<Vec<i32>> = vec![];
+        self.adjacency.keys().sorted().for_each(|start| {
+            if (!visited.insert(*start)) {
+                return;
+            }
+            let mut component: Vec<i32> = vec![];
+            let mut stack: Vec<i32> = vec![*start];
+            while let Some(node) = stack.pop() {
+                component.push(node);
+                self.adjacency[&node].iter().for_each(|(next, _l)| {
+                    if (visited.insert(*next)) {
+                        stack.push(*next);
+                    }
+                });
+            }
+            component.sort();
+            out.push(component);
+        });
+        out.sort_by(|a, b| b.len().cmp(&a.len()));
+        out
+    }
+}
+
+impl CableNetwork {
+    //number of cables of routes.json routed through every link part, a step is matched to the link by its nodes
+    pub fn link_loads(&self) -> HashMap<i32, usize> {
+        let by_pair: HashMap<(i32, i32), i32> = self.links.iter().map(|l| ((l.node1.min(l.node2), l.node1.max(l.node2)), l.id)).collect();
+        let mut cables: HashMap<i32, HashSet<i32>> = HashMap::new();
+        self.routes.iter().for_each(|r| {
+            match by_pair.get(&(r.node1.min(r.node2), r.node1.max(r.node2))) {
+                None => {}
+                Some(id) => { cables.entry(*id).or_default().insert(r.cable); }
+            }
+        });
+        cables.into_iter().map(|(id, c)| (id, c.len())).collect()
+    }
+
+    //material of every link part by its load, from gray for an empty tray to red for the fullest one
+    pub fn load_colors(&self) -> HashMap<i32, i32> {
+        let loads = self.link_loads();
+        let max = loads.values().copied().max().unwrap_or(0).max(1);
+        self.links.iter().map(|l| {
+            let load = loads.get(&l.id).copied().unwrap_or(0);
+            (l.id, load_color(load, max))
+        }).collect()
+    }
+
+    pub fn report(&self) -> CableGraphReport {
+        let graph = CableGraph::build(self);
+        CableGraphReport {
+            nodes: self.nodes.len(),
+            links: self.links.len(),
+            dangling: graph.dangling_nodes(),
+            components: graph.components(),
+            loads: self.link_loads(),
+        }
+    }
+
+    //shortest route as a trace, so it is lit the same way as a cable
+    pub fn trace_shortest_path(&self, from: i32, to: i32) -> Option<CableTrace> {
+        let (nodes, length) = CableGraph::build(self).shortest_path(from, to)?;
+        Some(self.trace_of_nodes(-1, nodes, length, vec![]))
+    }
+}
+
+fn load_color(load: usize, max: usize) -> i32 {
+    let (group, shade) = if (load == 0) {
+        ("gray", 4)
+    } else {
+        match (load * 4 - 1) / max {
+            0 => ("green", 6),
+            1 => ("yellow", 6),
+            2 => ("orange", 6),
+            _ => ("red", 8),
+        }
+    };
+    Material::palette_index(group, shade).unwrap_or(Material::cable_color(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+    use super::*;
+    use crate::scene::cable_network::{CableLink, CableNode, CableRoute};
+
+    //square 1-2-3-4 with the diagonal 1-3, a tail 3-5 and the pair 6-7 apart, 1000 mm sides
+    fn network() -> CableNetwork {
+        let mut network = CableNetwork::default();
+        [(1, 0.0, 0.0), (2, 1000.0, 0.0), (3, 1000.0, 1000.0), (4, 0.0, 1000.0), (5, 2000.0, 1000.0), (6, 0.0, 9000.0), (7, 1000.0, 9000.0)].iter().for_each(|(id, x, y)| {
+            network.nodes.insert(*id, CableNode { id: *id, userid: "".to_string(), p: Point3::new(*x, *y, 0.0), ty: 0 });
+        });
+        network.links = [(-11, 1, 2), (-12, 2, 3), (-13, 3, 4), (-14, 4, 1), (-15, 1, 3), (-16, 3, 5), (-17, 6, 7)].iter()
+            .map(|(id, node1, node2)| CableLink { id: *id, node1: *node1, node2: *node2 })
+            .collect();
+        network
+    }
+
+    fn route(cable: i32, node1: i32, node2: i32, seq_pos: i32) -> CableRoute {
+        CableRoute { cable: cable, node1: node1, node2: node2, seq_pos: seq_pos, lev_num: 0 }
+    }
+
+    #[test]
+    fn shortest_path() {
+        let graph = CableGraph::build(&network());
+        //the diagonal is shorter than two sides
+        let (nodes, length) = graph.shortest_path(2, 5).unwrap();
+        assert_eq!(nodes, vec![2, 3, 5]);
+        assert!((length - 2000.0).abs() < 1e-6);
+        let (nodes, length) = graph.shortest_path(1, 5).unwrap();
+        assert_eq!(nodes, vec![1, 3, 5]);
+        assert!((length - (1000.0 * 2.0_f64.sqrt() + 1000.0)).abs() < 1e-3);
+        assert_eq!(graph.shortest_path(4, 4), Some((vec![4], 0.0)));
+        assert_eq!(graph.shortest_path(1, 6), None);
+        assert_eq!(graph.shortest_path(1, 8), None);
+    }
+
+    #[test]
+    fn components_and_dangling_nodes() {
+        let mut network = network();
+        //a node without links is a sub-network of its own
+        network.nodes.insert(8, CableNode { id: 8, userid: "".to_string(), p: Point3::new(0.0, 0.0, 5000.0), ty: 0 });
+        let graph = CableGraph::build(&network);
+        assert_eq!(graph.components(), vec![vec![1, 2, 3, 4, 5], vec![6, 7], vec![8]]);
+        assert_eq!(graph.dangling_nodes(), vec![5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn link_loads() {
+        let mut network = network();
+        network.routes = vec![
+            route(100, 1, 2, 0), route(100, 2, 3, 1), route(100, 3, 5, 2),
+            //steps go either way and a cable is counted once per link
+            route(101, 3, 2, 0), route(101, 2, 1, 1), route(101, 1, 2, 2),
+            route(102, 1, 3, 0),
+            //not a link
+            route(103, 2, 4, 0),
+        ];
+        let loads = network.link_loads();
+        assert_eq!(loads.len(), 4);
+        assert_eq!(loads[&-11], 2);
+        assert_eq!(loads[&-12], 2);
+        assert_eq!(loads[&-15], 1);
+        assert_eq!(loads[&-16], 1);
+        let report = network.report();
+        assert_eq!((report.nodes, report.links), (7, 7));
+        assert_eq!(report.loads, loads);
+    }
+}
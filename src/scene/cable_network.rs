@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, MetricSpace, Point3};
+use itertools::Itertools;
+use log::warn;
+use serde::Deserialize;
+
+use truck_base::bounding_box::BoundingBox;
+use crate::scene::load_error::LoadError;
+use crate::scene::instanced_pack::InstancedPackBuilder;
+use crate::scene::mesh_loader::HullData;
+use crate::shared::{CABLE_EDGE_RADIUS, CABLE_NODE_SPHERE_RADIUS};
+use crate::shared::materials_lib::{CABLE_TY_MAX, CABLE_TY_MIN};
+use crate::shared::primitives_mesh::perpendicular;
+use crate::shared::primitives_pipe::PipePrimitive;
+use crate::shared::primitives_sphere::SpherePrimitive;
+
+pub use crate::scene::pack_format::CABLE_PACK_ID;
+//files of the network in a directory, see CableNetwork::read_dir
+pub const CABLE_FILES: [&str; 4] = ["nodes.json", "cab_nodes.json", "routes.json", "segments.json"];
+//the network shipped with the sources, loaded by F7 in the native viewer
+#[cfg(not(target_arch = "wasm32"))]
+pub const BUNDLED_CABLE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/dmp");
+//INTENSIVE_RADIUS of highlight_shader.wgsl, lit spheres around the points overlap
+pub const CABLE_HIGHLIGHT_STEP: f32 = 100.0;
+//every lit fragment loops over all points
+pub const CABLE_HIGHLIGHT_MAX_POINTS: usize = 1024;
+//a node is drawn by thousands, so its sphere is coarse
+const CABLE_NODE_SUBDIVISIONS: u32 = 3;
+//Selected, hidden and coloured parts are kept by part id over all packs, so cable parts get negative ids
+//that no hull part has: a node is -1 - seqid, a segment CABLE_SEGMENT_ID_BASE - 1 - id and the n-th step
+//of routes.json that is not a segment CABLE_STEP_ID_BASE - 1 - n. Seqids and segment ids are 0..CABLE_ID_SPAN
+pub const CABLE_ID_SPAN: i32 = 1_000_000_000;
+pub const CABLE_SEGMENT_ID_BASE: i32 = -CABLE_ID_SPAN;
+pub const CABLE_STEP_ID_BASE: i32 = -2 * CABLE_ID_SPAN;
+
+//part id of the sphere of a node
+pub fn node_part_id(seqid: i32) -> i32 {
+    -1 - seqid
+}
+
+//part id of the pipes of a segment of segments.json
+pub fn segment_part_id(segment_id: i32) -> i32 {
+    CABLE_SEGMENT_ID_BASE - 1 - segment_id
+}
+
+//nodes.json, coordinates in mm
+#[derive(Deserialize)]
+struct NodeRecordMm {
+    seqid: i32,
+    #[serde(default)]
+    userid: String,
+    x: f64,
+    y: f64,
+    z: f64,
+    t_ype: String,
+}
+
+//cab_nodes.json, coordinates in metres
+#[derive(Deserialize)]
+struct NodeRecordM {
+    seqid: i32,
+    #[serde(default)]
+    userid: String,
+    x: f64,
+    y: f64,
+    z: f64,
+    typeid: i32,
+}
+
+//one step of a cable, routes.json
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CableRoute {
+    pub cable: i32,
+    pub node1: i32,
+    pub node2: i32,
+    pub seq_pos: i32,
+    pub lev_num: i32,
+}
+
+//p is in mm like the hull
+#[derive(Clone, Debug, PartialEq)]
+pub struct CableNode {
+    pub id: i32,
+    pub userid: String,
+    pub p: Point3<f32>,
+    pub ty: i32,
+}
+
+//pipe between two nodes, id is the part id (see segment_part_id), a segment of segments.json may have several links
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CableLink {
+    pub id: i32,
+    pub node1: i32,
+    pub node2: i32,
+}
+
+//ordered node path of a cable, see CableNetwork::trace_cable
+#[derive(Clone, Debug, PartialEq)]
+pub struct CableTrace {
+    pub cable: i32,
+    pub nodes: Vec<i32>,
+    pub points: Vec<Point3<f32>>,
+    //routed length in mm, jumps between pieces are not counted
+    pub length: f64,
+    //indices of nodes that start a new piece, the jump to them is not routed
+    pub breaks: Vec<usize>,
+    pub bbx: BoundingBox<Point3<f64>>,
+}
+
+impl CableTrace {
+    //the highlight shader lights geometry around points, so long steps get points every step mm
+    pub fn highlight_points(&self, step: f32, max_points: usize) -> Vec<[f32; 4]> {
+        let step = step.max((self.length as f32) / (max_points.max(2) as f32));
+        let mut out: Vec<[f32; 4]> = vec![];
+        self.points.iter().enumerate().for_each(|(n, p)| {
+            out.push([p.x, p.y, p.z, 1.0]);
+            match self.points.get(n + 1).filter(|_next| !self.breaks.contains(&(n + 1))) {
+                None => {}
+                Some(next) => {
+                    let l = p.distance(*next);
+                    let count = (l / step).floor() as usize;
+                    (1..count).for_each(|k| {
+                        let t = k as f32 / count as f32;
+                        out.push([p.x + (next.x - p.x) * t, p.y + (next.y - p.y) * t, p.z + (next.z - p.z) * t, 1.0]);
+                    });
+                }
+            }
+        });
+        out.truncate(max_points);
+        out
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CableNetwork {
+    pub nodes: HashMap<i32, CableNode>,
+    pub links: Vec<CableLink>,
+    pub routes: Vec<CableRoute>,
+}
+
+fn json_error(file: &str, e: serde_json::Error) -> LoadError {
+    json_error_msg(file, e.to_string())
+}
+
+fn json_error_msg(file: &str, message: String) -> LoadError {
+    LoadError::Format { message: format!("{}: {}", file, message) }
+}
+
+impl CableNetwork {
+    //segments.json is a flat array of (segment id, node1, node2) triples over cab_nodes.json,
+    //routes.json steps go over nodes.json and become links too, with ids below CABLE_STEP_ID_BASE
+    pub fn from_json(nodes: &[u8], cab_nodes: &[u8], routes: &[u8], segments: &[u8]) -> Result<Self, LoadError> {
+        let nodes_mm: Vec<NodeRecordMm> = serde_json::from_slice(nodes).map_err(|e| json_error(CABLE_FILES[0], e))?;
+        let nodes_m: Vec<NodeRecordM> = serde_json::from_slice(cab_nodes).map_err(|e| json_error(CABLE_FILES[1], e))?;
+        let routes: Vec<CableRoute> = serde_json::from_slice(routes).map_err(|e| json_error(CABLE_FILES[2], e))?;
+        let segments: Vec<i32> = serde_json::from_slice(segments).map_err(|e| json_error(CABLE_FILES[3], e))?;
+        if (segments.len() % 3 != 0) {
+            return Err(json_error_msg(CABLE_FILES[3], format!("{} values are not (id, node1, node2) triples", segments.len())));
+        }
+
+        let mut network = CableNetwork::default();
+        let mut twins = 0;
+        let mut out_of_span = 0;
+        nodes_m.into_iter().for_each(|n| {
+            if (!(0..CABLE_ID_SPAN).contains(&n.seqid)) {
+                out_of_span = out_of_span + 1;
+                return;
+            }
+            let p = Point3::new((n.x * 1000.0) as f32, (n.y * 1000.0) as f32, (n.z * 1000.0) as f32);
+            network.add_node(n.seqid, n.userid, p, n.typeid);
+        });
+        for n in nodes_mm {
+            if (!(0..CABLE_ID_SPAN).contains(&n.seqid)) {
+                out_of_span = out_of_span + 1;
+                continue;
+            }
+            let ty = n.t_ype.trim().parse::<i32>().map_err(|_e| json_error_msg(CABLE_FILES[0], format!("node {} t_ype {:?} is not a number", n.seqid, n.t_ype)))?;
+            let p = Point3::new(n.x as f32, n.y as f32, n.z as f32);
+            if (!network.add_node(n.seqid, n.userid, p, ty)) {
+                twins = twins + 1;
+            }
+        }
+        if (twins > 0) {
+            warn!("CABLE NETWORK: {} NODES OF {} ARE ALSO IN {}, KEPT FROM {}", twins, CABLE_FILES[0], CABLE_FILES[1], CABLE_FILES[1]);
+        }
+
+        let mut next_step_id = CABLE_STEP_ID_BASE - 1;
+        let mut missing = 0;
+        let mut pairs: HashSet<(i32, i32)> = HashSet::new();
+        for s in segments.chunks_exact(3) {
+            if (!(0..CABLE_ID_SPAN).contains(&s[0])) {
+                out_of_span = out_of_span + 1;
+                continue;
+            }
+            if (!network.nodes.contains_key(&s[1]) || !network.nodes.contains_key(&s[2])) {
+                missing = missing + 1;
+                continue;
+            }
+            pairs.insert((s[1].min(s[2]), s[1].max(s[2])));
+            network.links.push(CableLink { id: segment_part_id(s[0]), node1: s[1], node2: s[2] });
+        }
+        routes.iter().for_each(|r| {
+            if (!network.nodes.contains_key(&r.node1) || !network.nodes.contains_key(&r.node2)) {
+                missing = missing + 1;
+                return;
+            }
+            if (pairs.insert((r.node1.min(r.node2), r.node1.max(r.node2)))) {
+                match next_step_id.checked_sub(1) {
+                    None => { out_of_span = out_of_span + 1; }
+                    Some(id) => {
+                        network.links.push(CableLink { id: next_step_id, node1: r.node1, node2: r.node2 });
+                        next_step_id = id;
+                    }
+                }
+            }
+        });
+        if (missing > 0) {
+            warn!("CABLE NETWORK: {} LINKS ARE SKIPPED, THEIR NODES ARE MISSING", missing);
+        }
+        if (out_of_span > 0) {
+            warn!("CABLE NETWORK: {} NODES AND LINKS ARE SKIPPED, THEIR IDS ARE OUT OF 0..{}", out_of_span, CABLE_ID_SPAN);
+        }
+        network.routes = routes;
+        Ok(network)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_dir(dir: &std::path::Path) -> Result<Self, LoadError> {
+        let mut files: Vec<Vec<u8>> = vec![];
+        for name in CABLE_FILES {
+            let path = dir.join(name);
+            files.push(std::fs::read(&path).map_err(|e| LoadError::from(e).in_file(path.display().to_string()))?);
+        }
+        CableNetwork::from_json(files[0].as_slice(), files[1].as_slice(), files[2].as_slice(), files[3].as_slice())
+            .map_err(|e| e.in_file(dir.display().to_string()))
+    }
+
+    //Steps of the cable in seq_pos order are chained by their shared nodes, a step that does not touch
+    //the previous one starts a new piece. None if the cable has no steps with known nodes
+    pub fn trace_cable(&self, cable: i32) -> Option<CableTrace> {
+        let steps: Vec<&CableRoute> = self.routes.iter()
+            .filter(|r| r.cable == cable && self.nodes.contains_key(&r.node1) && self.nodes.contains_key(&r.node2))
+            .sorted_by_key(|r| (r.lev_num, r.seq_pos))
+            .collect();
+        if (steps.is_empty()) {
+            return None;
+        }
+        //the first step is turned away from the second one
+        let first = match steps.get(1) {
+            Some(next) if (steps[0].node1 == next.node1 || steps[0].node1 == next.node2) => (steps[0].node2, steps[0].node1),
+            _ => (steps[0].node1, steps[0].node2),
+        };
+        let mut nodes: Vec<i32> = vec![first.0, first.1];
+        let mut length = self.nodes[&first.0].p.distance(self.nodes[&first.1].p) as f64;
+        let mut breaks: Vec<usize> = vec![];
+        steps.iter().skip(1).for_each(|r| {
+            let last = *nodes.last().unwrap();
+            let (from, to) = if (r.node2 == last) { (r.node2, r.node1) } else { (r.node1, r.node2) };
+            //the jump to a new piece is not routed, so it is not counted
+            if (from != last) {
+                breaks.push(nodes.len());
+                nodes.push(from);
+            }
+            length = length + self.nodes[&from].p.distance(self.nodes[&to].p) as f64;
+            nodes.push(to);
+        });
+        Some(self.trace_of_nodes(cable, nodes, length, breaks))
+    }
+
+    pub(crate) fn trace_of_nodes(&self, cable: i32, nodes: Vec<i32>, length: f64, breaks: Vec<usize>) -> CableTrace {
+        let points: Vec<Point3<f32>> = nodes.iter().map(|id| self.nodes[id].p).collect();
+        let mut bbx: BoundingBox<Point3<f64>> = BoundingBox::default();
+        points.iter().for_each(|p| bbx.push(Point3::new(p.x as f64, p.y as f64, p.z as f64)));
+        CableTrace {
+            cable: cable,
+            nodes: nodes,
+            points: points,
+            length: length,
+            breaks: breaks,
+            bbx: bbx,
+        }
+    }
+
+    //cable ids in routes.json, sorted
+    pub fn cables(&self) -> Vec<i32> {
+        self.routes.iter().map(|r| r.cable).unique().sorted().collect()
+    }
+
+    //false if the node is already there
+    fn add_node(&mut self, id: i32, userid: String, p: Point3<f32>, ty: i32) -> bool {
+        if (self.nodes.contains_key(&id)) {
+            return false;
+        }
+        self.nodes.insert(id, CableNode {
+            id: id,
+            userid: userid,
+            p: p,
+            ty: ty,
+        });
+        true
+    }
+
+    //vertex type of a node, see CABLE_TY_MIN
+    fn node_type(&self, id: i32) -> i32 {
+        match self.nodes.get(&id) {
+            None => { CABLE_TY_MIN }
+            Some(n) => { CABLE_TY_MIN + n.ty.clamp(0, CABLE_TY_MAX - CABLE_TY_MIN) }
+        }
+    }
+
+    fn link_type(&self, link: &CableLink) -> i32 {
+        let ty1 = self.node_type(link.node1);
+        let ty2 = self.node_type(link.node2);
+        if (ty1 == ty2) { ty1 } else { CABLE_TY_MIN }
+    }
+
+    //Instanced layout pack, a node is a unit sphere and a link a unit pipe moved into place,
+    //a node and the pipes of a link id are one part each, so parts are picked, selected and hidden the same as hull parts
+    pub fn to_hull_data(&self, pack_id: u32) -> HullData {
+        let mut builder = InstancedPackBuilder::new(pack_id);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let sphere = builder.add_template(&SpherePrimitive::new(0, origin, 1.0, 0).triangulate_with(CABLE_NODE_SUBDIVISIONS));
+        let pipe = builder.add_template(&PipePrimitive::new(0, origin, Point3::new(0.0, 0.0, 1.0), 1.0, 0).triangulate());
+        self.nodes.keys().sorted().for_each(|id| {
+            let n = &self.nodes[id];
+            let model = Matrix4::from_translation(n.p.to_vec()) * Matrix4::from_scale(CABLE_NODE_SPHERE_RADIUS);
+            builder.push_part(node_part_id(n.id), self.node_type(n.id), sphere, vec![model]);
+        });
+        let mut links: BTreeMap<i32, Vec<&CableLink>> = BTreeMap::new();
+        self.links.iter().for_each(|l| links.entry(l.id).or_default().push(l));
+        let mut degenerate = 0;
+        links.iter().for_each(|(id, links)| {
+            let models: Vec<Matrix4<f32>> = links.iter().filter_map(|l| {
+                let p_a = self.nodes[&l.node1].p;
+                let dir = self.nodes[&l.node2].p - p_a;
+                //nodes at the same point give no pipe direction
+                if (!(dir.magnitude() > f32::EPSILON)) {
+                    degenerate = degenerate + 1;
+                    return None;
+                }
+                //the unit pipe goes along z, its ring is scaled to the radius around dir
+                let u = perpendicular(dir);
+                let w = dir.normalize().cross(u);
+                Some(Matrix4::from_cols((u * CABLE_EDGE_RADIUS).extend(0.0), (w * CABLE_EDGE_RADIUS).extend(0.0), dir.extend(0.0), p_a.to_homogeneous()))
+            }).collect();
+            builder.push_part(*id, self.link_type(links[0]), pipe, models);
+        });
+        if (degenerate > 0) {
+            warn!("CABLE NETWORK: {} LINKS HAVE NO LENGTH, NOT DRAWN", degenerate);
+        }
+        builder.finish()
+    }
+}
@@ -6,17 +6,17 @@ use log::warn;
 use parking_lot::RwLock;
 use truck_base::bounding_box::BoundingBox;
 use web_sys::js_sys::Uint8Array;
-use wgpu::{Buffer, Device};
+use wgpu::{BindGroup, Buffer, Device};
 use wgpu::util::DeviceExt;
 use crate::device::message_controller::ActionType;
 
 use crate::scene::scene_state::SceneState;
 use crate::shared::materials_lib::{HIDDEN_HULL_MAT, Material, SELECTION_HULL_MAT};
-use crate::shared::mesh_common::MeshVertex;
+use crate::shared::mesh_common::{CompactMesh, CompactVertex, HullVertices, InstancedMesh, MeshVertex};
 use crate::shared::Triangle;
 #[cfg(target_arch = "wasm32")]
-use crate::remote::hull_state::get_mesh_vertex_by_id;
-pub const ID_MEM_OFFSET: u32 = 100;
+use crate::remote::hull_state::{get_mesh_vertex_by_id, get_mesh_vertices_by_range};
+pub use crate::scene::pack_format::{ID_MEM_OFFSET, unpack_id, unpack_packid};
 
 pub struct GpuMem {
     device: Rc<RwLock<Device>>,
@@ -31,6 +31,22 @@ pub struct GpuMem {
     pub v_buffer: Buffer,
     pub i_buffer: Buffer,
     pub is_renderable: bool,
+    //kept on CPU on both targets, it is small and picking reads it
+    pub compact: Option<CompactMesh>,
+    pub compact_buffers: Option<CompactBuffers>,
+    //kept on CPU on both targets, v_buffer holds the templates and instance_buffer the instances
+    pub instanced: Option<InstancedMesh>,
+    pub instance_buffer: Option<Buffer>,
+    //vertices are built here (e.g. the cable network), on web there is no copy in JS to read them back from
+    pub is_local: bool,
+    //group 1 of every pass, made again after the buffers it binds are replaced, see DeviceState::update_pack_groups
+    pub bind_group: Option<BindGroup>,
+}
+
+//quantization uniform and part table of a compact pack, bound with the metadata as group 1
+pub struct CompactBuffers {
+    pub quantization_buffer: Buffer,
+    pub parts_buffer: Buffer,
 }
 
 impl GpuMem {
@@ -67,9 +83,32 @@ impl GpuMem {
             v_buffer: vertex_buffer,
             i_buffer: index_buffer,
             is_renderable: false,
+            compact: None,
+            compact_buffers: None,
+            instanced: None,
+            instance_buffer: None,
+            is_local: false,
+            bind_group: None,
         }
     }
     pub fn resize_buffers(&mut self) {
+        self.bind_group = None;
+        match self.compact.take() {
+            None => {}
+            Some(compact) => {
+                self.resize_compact_buffers(&compact);
+                self.compact = Some(compact);
+                return;
+            }
+        }
+        match self.instanced.take() {
+            None => {}
+            Some(instanced) => {
+                self.resize_instanced_buffers(&instanced);
+                self.instanced = Some(instanced);
+                return;
+            }
+        }
         self.i_buffer = self.device.read().create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some(format!("Index Mesh Buffer {}", self.id).as_str()),
             contents: bytemuck::cast_slice(&self.i),
@@ -82,23 +121,109 @@ impl GpuMem {
         });
         self.is_renderable = true;
     }
-    pub fn set_data(&mut self, v: Vec<MeshVertex>, i: Vec<i32>, metadata: Vec<i32>, tot_loc_bbx: BoundingBox<Point3<f64>>, mesh_hash: HashMap<i32, (i32, i32, i32)>, loc_bbxs: Vec<BoundingBox<Point3<f64>>>) {
+    //selection pass reads vertices and indices as storage, see vs_compact in selection.wgsl
+    fn resize_compact_buffers(&mut self, compact: &CompactMesh) {
+        let device = self.device.read();
+        self.i_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(format!("Index Compact Buffer {}", self.id).as_str()),
+            contents: bytemuck::cast_slice(&self.i),
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::STORAGE,
+        });
+        self.v_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(format!("Vertex Compact Buffer {}", self.id).as_str()),
+            contents: bytemuck::cast_slice(&compact.v),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+        });
+        let quantization_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(format!("Quantization Buffer {}", self.id).as_str()),
+            contents: bytemuck::cast_slice(&[compact.quantization]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let parts_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(format!("Parts Buffer {}", self.id).as_str()),
+            contents: bytemuck::cast_slice(&compact.parts),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        self.compact_buffers = Some(CompactBuffers {
+            quantization_buffer: quantization_buffer,
+            parts_buffer: parts_buffer,
+        });
+        //empty storage buffers can't be bound
+        self.is_renderable = !self.i.is_empty() && !compact.v.is_empty() && !compact.parts.is_empty();
+    }
+    //templates are drawn without indices, see vs_instanced in shader.wgsl
+    fn resize_instanced_buffers(&mut self, instanced: &InstancedMesh) {
+        let device = self.device.read();
+        self.v_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(format!("Vertex Template Buffer {}", self.id).as_str()),
+            contents: bytemuck::cast_slice(&instanced.v),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        self.instance_buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(format!("Instance Buffer {}", self.id).as_str()),
+            contents: bytemuck::cast_slice(&instanced.instances),
+            usage: wgpu::BufferUsages::VERTEX,
+        }));
+        self.is_renderable = !instanced.v.is_empty() && !instanced.instances.is_empty();
+    }
+    pub fn set_data(&mut self, v: HullVertices, i: Vec<i32>, metadata: Vec<i32>, tot_loc_bbx: BoundingBox<Point3<f64>>, mesh_hash: HashMap<i32, (i32, i32, i32)>, loc_bbxs: Vec<BoundingBox<Point3<f64>>>) {
         //warn!("MEMORY SIZE IS {} {}", size_of::<MeshVertex>()*v.len(),  size_of::<i32>()*i.len() );
-        self.v = v;
+        match v {
+            HullVertices::Full(v) => {
+                self.v = v;
+                self.compact = None;
+                self.compact_buffers = None;
+                self.instanced = None;
+                self.instance_buffer = None;
+            }
+            HullVertices::Compact(compact) => {
+                self.v = vec![];
+                self.compact = Some(compact);
+                self.instanced = None;
+                self.instance_buffer = None;
+            }
+            HullVertices::Instanced(instanced) => {
+                self.v = vec![];
+                self.compact = None;
+                self.compact_buffers = None;
+                self.instanced = Some(instanced);
+            }
+        }
         self.i = i;
         self.metadata = metadata;
-        //self.loc_bbxs = loc_bbxs;
+        self.loc_bbxs = loc_bbxs;
         self.tot_loc_bbx = tot_loc_bbx;
         self.mesh_hash = mesh_hash;
         self.resize_buffers();
         self.is_metadata_dirty = true;
         #[cfg(target_arch = "wasm32")]
         {
-            self.i = vec![];
-            self.v = vec![];
+            if (self.compact.is_none() && self.instanced.is_none() && !self.is_local) {
+                self.i = vec![];
+                self.v = vec![];
+            }
         }
     }
 
+    //full layout pack streamed by PackUpload, vertices and indices are already in the buffers
+    pub fn set_buffers(&mut self, v_buffer: Buffer, i_buffer: Buffer, v: Vec<MeshVertex>, metadata: Vec<i32>, tot_loc_bbx: BoundingBox<Point3<f64>>, mesh_hash: HashMap<i32, (i32, i32, i32)>, loc_bbxs: Vec<BoundingBox<Point3<f64>>>) {
+        self.v_buffer = v_buffer;
+        self.i_buffer = i_buffer;
+        self.bind_group = None;
+        self.v = v;
+        self.i = vec![];
+        self.compact = None;
+        self.compact_buffers = None;
+        self.instanced = None;
+        self.instance_buffer = None;
+        self.metadata = metadata;
+        self.loc_bbxs = loc_bbxs;
+        self.tot_loc_bbx = tot_loc_bbx;
+        self.mesh_hash = mesh_hash;
+        self.is_renderable = true;
+        self.is_metadata_dirty = true;
+    }
+
     pub fn select_by_id(&mut self, oid: i32) -> bool {
         match self.mesh_hash.get(&oid) {
             None => {
@@ -121,6 +246,17 @@ impl GpuMem {
             }
         }
     }
+    pub fn set_material_by_id(&mut self, oid: i32, mat_indx: i32) -> bool {
+        match self.mesh_hash.get(&oid) {
+            None => {
+                false
+            }
+            Some(mesh) => {
+                self.change_material(mat_indx, mesh.0, mesh.1);
+                true
+            }
+        }
+    }
     pub fn set_default_by_id(&mut self, oid: i32) -> bool {
         match self.mesh_hash.get(&oid) {
             None => {
@@ -132,10 +268,62 @@ impl GpuMem {
             }
         }
     }
+    //compact packs are picked by index buffer position, see vs_compact in selection.wgsl
+    fn get_compact_triangle(&self, compact: &CompactMesh, index_pos: usize) -> Option<(i32, Triangle)> {
+        let start = index_pos - index_pos % 3;
+        let corners: Vec<&CompactVertex> = self.i.get(start..start + 3)?.iter().map(|i| compact.v.get(*i as usize)).collect::<Option<Vec<&CompactVertex>>>()?;
+        let part = compact.parts.get(corners[0].part as usize)?;
+        let p: Vec<[f32; 3]> = corners.iter().map(|cv| cv.position(&compact.quantization)).collect();
+        let triangle = Triangle::from_coords(
+            p[0][0], p[0][1], p[0][2],
+            p[1][0], p[1][1], p[1][2],
+            p[2][0], p[2][1], p[2][2],
+        );
+        Some((part.id, triangle))
+    }
+    fn get_instanced_triangle(instanced: &InstancedMesh, vertex_index: usize) -> Option<(i32, Triangle)> {
+        let (id, p) = instanced.triangle_by_vertex_index(vertex_index)?;
+        let triangle = Triangle::from_coords(
+            p[0][0], p[0][1], p[0][2],
+            p[1][0], p[1][1], p[1][2],
+            p[2][0], p[2][1], p[2][2],
+        );
+        Some((id, triangle))
+    }
+    //part id under a picked vertex, vertex_index is the value written by the selection pass
+    pub fn get_id_by_vertex_index(&self, vertex_index: usize) -> Option<i32> {
+        match &self.instanced {
+            None => {}
+            Some(instanced) => { return instanced.part_by_vertex_index(vertex_index).map(|part| part.id); }
+        }
+        match &self.compact {
+            Some(compact) => {
+                let cv = self.i.get(vertex_index).and_then(|i| compact.v.get(*i as usize))?;
+                compact.parts.get(cv.part as usize).map(|part| part.id)
+            }
+            None => {
+                #[cfg(target_arch = "wasm32")]
+                {
+                    self.get_local_or_remote_mesh(vertex_index).map(|m| m.id)
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    self.v.get(vertex_index).map(|m| m.id)
+                }
+            }
+        }
+    }
     #[cfg(target_arch = "wasm32")]
     pub fn get_triangle_by_vertex_index(&self, vertex_index: usize) -> Option<(i32, Triangle)> {
-        let bin = get_mesh_vertex_by_id(self.id as i32, vertex_index as i32).to_vec();
-        let meshes: &[MeshVertex] = bytemuck::cast_slice(bin.as_slice());
+        match &self.compact {
+            None => {}
+            Some(compact) => { return self.get_compact_triangle(compact, vertex_index); }
+        }
+        match &self.instanced {
+            None => {}
+            Some(instanced) => { return GpuMem::get_instanced_triangle(instanced, vertex_index); }
+        }
+        let meshes: Vec<MeshVertex> = self.get_local_or_remote_mesh(vertex_index).into_iter().collect();
 
         if(meshes.len()>0){
            let base_mesh=meshes[0];
@@ -200,6 +388,14 @@ impl GpuMem {
     }
     #[cfg(not(target_arch = "wasm32"))]
     pub fn get_triangle_by_vertex_index(&self, vertex_index: usize) -> Option<(i32, Triangle)> {
+        match &self.compact {
+            None => {}
+            Some(compact) => { return self.get_compact_triangle(compact, vertex_index); }
+        }
+        match &self.instanced {
+            None => {}
+            Some(instanced) => { return GpuMem::get_instanced_triangle(instanced, vertex_index); }
+        }
         match self.v.get(vertex_index) {
             None => { None }
             Some(base_mesh) => {
@@ -259,9 +455,51 @@ impl GpuMem {
     }
     #[cfg(target_arch = "wasm32")]
     fn get_mesh_by_id(&self,vertex_index:usize)->MeshVertex{
+        self.get_local_or_remote_mesh(vertex_index).unwrap_or(MeshVertex::default())
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn get_local_or_remote_mesh(&self, vertex_index: usize) -> Option<MeshVertex> {
+        if (self.is_local) {
+            return self.v.get(vertex_index).copied();
+        }
         let bin = get_mesh_vertex_by_id(self.id as i32, vertex_index as i32).to_vec();
-        let meshes: &[MeshVertex] = bytemuck::cast_slice(bin.as_slice());
-        meshes[0]
+        let meshes: Vec<MeshVertex> = bytemuck::pod_collect_to_vec(bin.as_slice());
+        meshes.first().copied()
+    }
+    //vertices first..=last, None when any of them is missing
+    #[cfg(target_arch = "wasm32")]
+    fn get_local_or_remote_range(&self, first: usize, last: usize) -> Option<Vec<MeshVertex>> {
+        if (self.is_local) {
+            return self.v.get(first..=last).map(|v| v.to_vec());
+        }
+        let bin = get_mesh_vertices_by_range(self.id as i32, first as i32, last as i32).to_vec();
+        let meshes: Vec<MeshVertex> = bytemuck::pod_collect_to_vec(bin.as_slice());
+        if (meshes.len() == last + 1 - first) { Some(meshes) } else { None }
+    }
+    //every triangle of the parts that are not hidden, with the part id and type, see visit_hull_triangles.
+    //On web the vertices of a full pack are read from JS one part at a time
+    pub fn visit_visible_triangles(&self, mut f: impl FnMut(i32, i32, [[f32; 3]; 3])) {
+        #[cfg(target_arch = "wasm32")]
+        {
+            if (self.compact.is_none() && self.instanced.is_none() && !self.is_local) {
+                let is_visible = |k: usize| self.metadata.get(k).map_or(false, |m| *m != HIDDEN_HULL_MAT);
+                self.mesh_hash.iter().filter(|(_id, m)| is_visible(m.0 as usize)).for_each(|(id, m)| {
+                    //a part with a missing vertex would shift every triangle after it, so it is skipped whole
+                    match self.get_local_or_remote_range(m.0 as usize, m.1 as usize) {
+                        None => {
+                            warn!("PART {} OF PACK {} HAS MISSING VERTICES, SKIPPED", id, self.id);
+                        }
+                        Some(corners) => {
+                            corners.chunks_exact(3).for_each(|c| {
+                                f(c[0].id, unpack_id(c[0].material_index as u32) as i32, [0, 1, 2].map(|n| [c[n].position[0], c[n].position[1], c[n].position[2]]));
+                            });
+                        }
+                    }
+                });
+                return;
+            }
+        }
+        visit_hull_triangles(&self.v, &self.i, self.compact.as_ref(), self.instanced.as_ref(), &self.metadata, f);
     }
     pub fn get_bbx_by_oid(&self, oid: i32) -> Option<BoundingBox<Point3<f64>>> {
         match self.mesh_hash.get(&oid) {
@@ -282,14 +520,28 @@ impl GpuMem {
         });
         self.is_metadata_dirty = true;
     }
+    //mesh_hash of an instanced pack spans instances, the first one gives the material
+    fn get_instanced_default_material(instanced: &InstancedMesh, first_instance: i32) -> i32 {
+        match instanced.parts.get(first_instance as usize) {
+            None => { 0 }
+            Some(part) => { Material::type_to_color(unpack_id(part.material_index as u32) as i32) }
+        }
+    }
+    fn get_compact_default_material(compact: &CompactMesh, part_index: i32) -> i32 {
+        match compact.parts.get(part_index as usize) {
+            None => { 0 }
+            Some(part) => { Material::type_to_color(unpack_id(part.material_index as u32) as i32) }
+        }
+    }
     #[cfg(target_arch = "wasm32")]
     fn get_default_material_by_id(&self, id: i32) -> i32 {
-        match self.mesh_hash.get(&id) {
-            None => { 0 }
-            Some(m) => {
+        match (self.mesh_hash.get(&id), &self.compact) {
+            (None, _) => { 0 }
+            (Some(m), Some(compact)) => { GpuMem::get_compact_default_material(compact, m.2) }
+            (Some(m), None) if (self.instanced.is_some()) => { self.instanced.as_ref().map_or(0, |inst| GpuMem::get_instanced_default_material(inst, m.0)) }
+            (Some(m), None) => {
                 let start_index = m.1;
-                let bin = get_mesh_vertex_by_id(self.id as i32, start_index).to_vec();
-                let meshes: &[MeshVertex] = bytemuck::cast_slice(bin.as_slice());
+                let meshes: Vec<MeshVertex> = self.get_local_or_remote_mesh(start_index as usize).into_iter().collect();
                 if (meshes.len() > 0) {
                     let mesh_v = meshes[0];
                     let default_material = Material::type_to_color(unpack_id(mesh_v.material_index as u32) as i32);
@@ -302,9 +554,11 @@ impl GpuMem {
     }
     #[cfg(not(target_arch = "wasm32"))]
     fn get_default_material_by_id(&self, id: i32) -> i32 {
-        match self.mesh_hash.get(&id) {
-            None => { 0 }
-            Some(m) => {
+        match (self.mesh_hash.get(&id), &self.compact) {
+            (None, _) => { 0 }
+            (Some(m), Some(compact)) => { GpuMem::get_compact_default_material(compact, m.2) }
+            (Some(m), None) if (self.instanced.is_some()) => { self.instanced.as_ref().map_or(0, |inst| GpuMem::get_instanced_default_material(inst, m.0)) }
+            (Some(m), None) => {
                 let start_index = m.1;
                 let mesh_v = self.v[start_index as usize];
                 let default_material = Material::type_to_color(unpack_id(mesh_v.material_index as u32) as i32);
@@ -319,14 +573,38 @@ impl GpuMem {
     }
 }
 
-
-pub fn unpack_id(raw_id: u32) -> u32 {
-    let pack_id = raw_id % ID_MEM_OFFSET;
-    let id = (raw_id - pack_id) / ID_MEM_OFFSET;
-    id
+//every triangle of a pack whose metadata is not HIDDEN_HULL_MAT, with the part id and type,
+//instances are moved into place. Works on the streams of HullData as well, so no GPU is needed
+pub fn visit_hull_triangles(v: &[MeshVertex], i: &[i32], compact: Option<&CompactMesh>, instanced: Option<&InstancedMesh>, metadata: &[i32], mut f: impl FnMut(i32, i32, [[f32; 3]; 3])) {
+    let is_visible = |k: usize| metadata.get(k).map_or(false, |m| *m != HIDDEN_HULL_MAT);
+    match (compact, instanced) {
+        (Some(compact), _) => {
+            i.chunks_exact(3).filter(|t| is_visible(t[0] as usize)).for_each(|t| {
+                match t.iter().map(|i| compact.v.get(*i as usize)).collect::<Option<Vec<&CompactVertex>>>() {
+                    None => {}
+                    Some(corners) => {
+                        match compact.parts.get(corners[0].part as usize) {
+                            None => {}
+                            Some(part) => { f(part.id, unpack_id(part.material_index as u32) as i32, [0, 1, 2].map(|c| corners[c].position(&compact.quantization))); }
+                        }
+                    }
+                }
+            });
+        }
+        (None, Some(instanced)) => {
+            instanced.instances.iter().enumerate().filter(|(k, _inst)| is_visible(*k)).for_each(|(k, inst)| {
+                let part = &instanced.parts[k];
+                let ty = unpack_id(part.material_index as u32) as i32;
+                let t = &instanced.templates[inst.template as usize];
+                instanced.v[t.first_vertex as usize..(t.first_vertex + t.vertex_count) as usize].chunks_exact(3).for_each(|c| {
+                    f(part.id, ty, [0, 1, 2].map(|n| inst.transform(c[n].position)));
+                });
+            });
+        }
+        (None, None) => {
+            v.chunks_exact(3).enumerate().filter(|(k, _c)| is_visible(k * 3)).for_each(|(_k, c)| {
+                f(c[0].id, unpack_id(c[0].material_index as u32) as i32, [0, 1, 2].map(|n| [c[n].position[0], c[n].position[1], c[n].position[2]]));
+            });
+        }
+    }
 }
-
-pub fn unpack_packid(raw_id: u32) -> u32 {
-    let pack_id = raw_id % ID_MEM_OFFSET;
-    pack_id
-}
\ No newline at end of file
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use cgmath::{Matrix, Matrix3, Matrix4, Point3, SquareMatrix};
+use log::warn;
+use truck_base::bounding_box::BoundingBox;
+
+use crate::scene::mesh_loader::HullData;
+use crate::scene::pack_format::pack_raw_id;
+use crate::scene::RawMesh;
+use crate::shared::materials_lib::Material;
+use crate::shared::mesh_common::{HullVertices, InstancedMesh, MeshInstance, MeshTemplate, MeshVertex, PartEntry};
+
+//Collects repeated primitives into an instanced layout pack: a template is uploaded once,
+//a part is one or more instances of one template, each with its own transform
+pub struct InstancedPackBuilder {
+    pack_id: u32,
+    templates: Vec<Vec<MeshVertex>>,
+    parts: Vec<(i32, i32, usize, Vec<Matrix4<f32>>)>,
+}
+
+impl InstancedPackBuilder {
+    pub fn new(pack_id: u32) -> Self {
+        Self {
+            pack_id: pack_id,
+            templates: vec![],
+            parts: vec![],
+        }
+    }
+    //index of the template for push_part, vertex_normal of a RawMesh is x, y, z, nx, ny, nz per triangle corner
+    pub fn add_template(&mut self, mesh: &RawMesh) -> usize {
+        //the selection pass takes the pack id from material_index, the material comes from the metadata
+        let material_index = pack_raw_id(0, self.pack_id) as i32;
+        self.templates.push(mesh.vertex_normal.chunks_exact(6).map(|c| MeshVertex::new(c[0], c[1], c[2], c[3], c[4], c[5], material_index, 0)).collect());
+        self.templates.len() - 1
+    }
+    pub fn push_part(&mut self, id: i32, ty: i32, template: usize, models: Vec<Matrix4<f32>>) {
+        if (template >= self.templates.len()) {
+            warn!("PART {} HAS NO TEMPLATE {}", id, template);
+            return;
+        }
+        self.parts.push((id, ty, template, models));
+    }
+    //instances of a template are put in a row, parts keep their order inside it
+    pub fn finish(mut self) -> HullData {
+        self.parts.sort_by_key(|p| p.2);
+        let mut v: Vec<MeshVertex> = vec![];
+        let mut templates: Vec<MeshTemplate> = self.templates.iter().map(|t| {
+            let first_vertex = v.len() as u32;
+            v.extend_from_slice(t);
+            MeshTemplate {
+                first_vertex: first_vertex,
+                vertex_count: t.len() as u32,
+                first_instance: 0,
+                instance_count: 0,
+            }
+        }).collect();
+        let mut instances: Vec<MeshInstance> = vec![];
+        let mut parts: Vec<PartEntry> = vec![];
+        let mut metadata: Vec<i32> = vec![];
+        let mut tot_bbx: BoundingBox<Point3<f64>> = BoundingBox::default();
+        let mut hashes: HashMap<i32, (i32, i32, i32)> = HashMap::new();
+        let mut bbxs: Vec<BoundingBox<Point3<f64>>> = vec![];
+        //first index every instance would have in the Full layout
+        let mut full_count: i64 = 0;
+        let mut singular = 0;
+        self.parts.iter().for_each(|(id, ty, template, models)| {
+            let start = instances.len();
+            let material_index = pack_raw_id(*ty as u32, self.pack_id) as i32;
+            let t = templates[*template];
+            let mut bbx: BoundingBox<Point3<f64>> = BoundingBox::default();
+            models.iter().for_each(|model| {
+                let normal_model = match Matrix3::from_cols(model.x.truncate(), model.y.truncate(), model.z.truncate()).invert() {
+                    None => {
+                        singular = singular + 1;
+                        return;
+                    }
+                    Some(inv) => inv.transpose(),
+                };
+                let instance = MeshInstance {
+                    model: (*model).into(),
+                    normal_model: [normal_model.x.extend(0.0).into(), normal_model.y.extend(0.0).into(), normal_model.z.extend(0.0).into()],
+                    base_vertex: (full_count - t.first_vertex as i64) as i32,
+                    template: *template as u32,
+                    _pad: [0, 0],
+                };
+                self.templates[*template].iter().for_each(|mv| {
+                    let p = instance.transform(mv.position);
+                    bbx.push(Point3::new(p[0] as f64, p[1] as f64, p[2] as f64));
+                });
+                full_count = full_count + t.vertex_count as i64;
+                instances.push(instance);
+                parts.push(PartEntry { id: *id, material_index: material_index });
+                metadata.push(Material::type_to_color(*ty));
+            });
+            if (instances.len() == start) {
+                return;
+            }
+            if (templates[*template].instance_count == 0) {
+                templates[*template].first_instance = start as u32;
+            }
+            templates[*template].instance_count = templates[*template].instance_count + (instances.len() - start) as u32;
+            //end is inclusive and counts instances, see GpuMem::change_material
+            hashes.insert(*id, (start as i32, (instances.len() - 1) as i32, bbxs.len() as i32));
+            tot_bbx += &bbx;
+            bbxs.push(bbx);
+        });
+        if (singular > 0) {
+            warn!("PACK {}: {} INSTANCES HAVE A FLAT TRANSFORM, NOT DRAWN", self.pack_id, singular);
+        }
+        let instanced = InstancedMesh {
+            v: v,
+            templates: templates,
+            instances: instances,
+            parts: parts,
+        };
+        (HullVertices::Instanced(instanced), vec![], metadata, tot_bbx, hashes, bbxs)
+    }
+}
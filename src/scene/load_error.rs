@@ -0,0 +1,133 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsValue;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_futures::js_sys::{Error, Reflect};
+
+//Why a hull pack can't be loaded, stream is a StreamKind name ("mesh", "ind", "bbx", "hash", "meta"...)
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadError {
+    Io { message: String },
+    Format { message: String },
+    Decompress { stream: &'static str, message: String },
+    Checksum { stream: &'static str, expected: u32, actual: u32 },
+    //stream length is not a multiple of its element size, cast to MeshVertex/i32/f32/u32 is impossible
+    Length { stream: &'static str, len: usize, item_size: usize },
+    TriangleCount { vertices: usize },
+    HashRange { id: u32, start: u32, end: u32, vertices: usize },
+    BboxCount { bbxs: usize, parts: usize },
+    //compact layout, an index or a vertex part points past its table
+    IndexRange { index: u32, vertices: usize },
+    PartRange { part: u32, parts: usize },
+    PackId { pack_id: u32 },
+    InFile { path: String, error: Box<LoadError> },
+}
+
+impl LoadError {
+    pub fn in_file(self, path: String) -> LoadError {
+        LoadError::InFile { path: path, error: Box::new(self) }
+    }
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LoadError::Io { .. } => "io",
+            LoadError::Format { .. } => "format",
+            LoadError::Decompress { .. } => "decompress",
+            LoadError::Checksum { .. } => "checksum",
+            LoadError::Length { .. } => "length",
+            LoadError::TriangleCount { .. } => "triangle_count",
+            LoadError::HashRange { .. } => "hash_range",
+            LoadError::BboxCount { .. } => "bbox_count",
+            LoadError::IndexRange { .. } => "index_range",
+            LoadError::PartRange { .. } => "part_range",
+            LoadError::PackId { .. } => "pack_id",
+            LoadError::InFile { error, .. } => error.kind(),
+        }
+    }
+
+    //JS Error with name "LoadError", kind and the fields of the variant, to reject a Promise with
+    #[cfg(target_arch = "wasm32")]
+    pub fn to_js_value(&self) -> JsValue {
+        let err = Error::new(self.to_string().as_str());
+        err.set_name("LoadError");
+        let obj: &JsValue = err.as_ref();
+        let set = |key: &str, value: JsValue| {
+            let _ = Reflect::set(obj, &JsValue::from_str(key), &value);
+        };
+        set("kind", JsValue::from_str(self.kind()));
+        let mut e = self;
+        while let LoadError::InFile { path, error } = e {
+            set("path", JsValue::from_str(path));
+            e = error.as_ref();
+        }
+        match e {
+            LoadError::Decompress { stream, .. } => {
+                set("stream", JsValue::from_str(stream));
+            }
+            LoadError::Checksum { stream, expected, actual } => {
+                set("stream", JsValue::from_str(stream));
+                set("expected", JsValue::from(*expected));
+                set("actual", JsValue::from(*actual));
+            }
+            LoadError::Length { stream, len, item_size } => {
+                set("stream", JsValue::from_str(stream));
+                set("len", JsValue::from(*len as f64));
+                set("itemSize", JsValue::from(*item_size as f64));
+            }
+            LoadError::TriangleCount { vertices } => {
+                set("vertices", JsValue::from(*vertices as f64));
+            }
+            LoadError::HashRange { id, start, end, vertices } => {
+                set("id", JsValue::from(*id));
+                set("start", JsValue::from(*start));
+                set("end", JsValue::from(*end));
+                set("vertices", JsValue::from(*vertices as f64));
+            }
+            LoadError::BboxCount { bbxs, parts } => {
+                set("bbxs", JsValue::from(*bbxs as f64));
+                set("parts", JsValue::from(*parts as f64));
+            }
+            LoadError::IndexRange { index, vertices } => {
+                set("index", JsValue::from(*index));
+                set("vertices", JsValue::from(*vertices as f64));
+            }
+            LoadError::PartRange { part, parts } => {
+                set("part", JsValue::from(*part));
+                set("parts", JsValue::from(*parts as f64));
+            }
+            LoadError::PackId { pack_id } => {
+                set("packId", JsValue::from(*pack_id));
+            }
+            _ => {}
+        }
+        err.into()
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io { message } => write!(f, "{}", message),
+            LoadError::Format { message } => write!(f, "{}", message),
+            LoadError::Decompress { stream, message } => write!(f, "stream {}: {}, data is truncated or corrupted", stream, message),
+            LoadError::Checksum { stream, expected, actual } => write!(f, "stream {} checksum {:08x} does not match {:08x}, data is corrupted", stream, actual, expected),
+            LoadError::Length { stream, len, item_size } => write!(f, "stream {} has {} bytes, not a multiple of {} bytes, data is truncated", stream, len, item_size),
+            LoadError::TriangleCount { vertices } => write!(f, "vertex count {} is not a multiple of 3", vertices),
+            LoadError::HashRange { id, start, end, vertices } => write!(f, "part {} range {}..={} is out of vertex range 0..{}", id, start, end, vertices),
+            LoadError::BboxCount { bbxs, parts } => write!(f, "{} bboxes for {} parts", bbxs, parts),
+            LoadError::IndexRange { index, vertices } => write!(f, "index {} is out of vertex range 0..{}", index, vertices),
+            LoadError::PartRange { part, parts } => write!(f, "part index {} is out of part table 0..{}", part, parts),
+            LoadError::PackId { pack_id } => write!(f, "pack id {} is out of range", pack_id),
+            LoadError::InFile { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io { message: e.to_string() }
+    }
+}
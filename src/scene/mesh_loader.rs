@@ -1,564 +1,116 @@
 use std::collections::HashMap;
-
-
-
-
+use std::mem::size_of;
 
 use cgmath::Point3;
-use itertools::Itertools;
 use log::warn;
-use miniz_oxide::inflate::decompress_to_vec;
 
 use truck_base::bounding_box::BoundingBox;
 use crate::scene::gpu_mem::unpack_id;
-use crate::scene::RawMesh;
-use crate::shared::{CABLE_EDGE_COLOR, CABLE_EDGE_RADIUS, CABLE_NODE_COLOR, CABLE_NODE_SPHERE_RADIUS};
-
-use crate::shared::mesh_common::MeshVertex;
-use crate::shared::primitives_pipe::PipePrimitive;
-use crate::shared::primitives_sphere::{SpherePrimitive};
-
-pub const Z_FIGHTING_FACTOR: f32 = 1.0;
-const TET: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI0: &[u8] = (include_bytes!("../dmp/hull/nf/0data_ind")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV0: &[u8] = (include_bytes!("../dmp/hull/nf/0data_mesh")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB0: &[u8] = (include_bytes!("../dmp/hull/nf/0data_bbx")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID0: &[u8] = (include_bytes!("../dmp/hull/nf/0data_hash")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI1: &[u8] = (include_bytes!("../dmp/hull/nf/1data_ind")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV1: &[u8] = (include_bytes!("../dmp/hull/nf/1data_mesh")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB1: &[u8] = (include_bytes!("../dmp/hull/nf/1data_bbx")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID1: &[u8] = (include_bytes!("../dmp/hull/nf/1data_hash")).as_slice();
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI2: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV2: &[u8] =&[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB2: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID2: &[u8] = &[0; 1];
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI3: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV3: &[u8] =&[0; 1];
+use crate::scene::load_error::LoadError;
+use crate::scene::pack_format::{read_pack_streams, PackMeta, PackStreams, StreamKind};
 #[cfg(not(target_arch = "wasm32"))]
-const HULLDB3: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID3: &[u8] = &[0; 1];
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI4: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV4: &[u8] =&[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB4: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID4: &[u8] = &[0; 1];
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI5: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV5: &[u8] =&[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB5: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID5: &[u8] = &[0; 1];
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI6: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV6: &[u8] =&[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB6: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID6: &[u8] = &[0; 1];
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI7: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV7: &[u8] =&[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB7: &[u8] = &[0; 1];
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID7: &[u8] = &[0; 1];
-
-
-/*#[cfg(not(target_arch = "wasm32"))]
-const HULLDI1: &[u8] = (include_bytes!("../dmp/hull/nf/1data_ind")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV1: &[u8] = (include_bytes!("../dmp/hull/nf/1data_mesh")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB1: &[u8] = (include_bytes!("../dmp/hull/nf/1data_bbx")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID1: &[u8] = (include_bytes!("../dmp/hull/nf/1data_hash")).as_slice();
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI2: &[u8] = (include_bytes!("../dmp/hull/nf/2data_ind")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV2: &[u8] = (include_bytes!("../dmp/hull/nf/2data_mesh")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB2: &[u8] = (include_bytes!("../dmp/hull/nf/2data_bbx")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID2: &[u8] = (include_bytes!("../dmp/hull/nf/2data_hash")).as_slice();
-
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI3: &[u8] = (include_bytes!("../dmp/hull/nf/3data_ind")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV3: &[u8] = (include_bytes!("../dmp/hull/nf/3data_mesh")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB3: &[u8] = (include_bytes!("../dmp/hull/nf/3data_bbx")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID3: &[u8] = (include_bytes!("../dmp/hull/nf/3data_hash")).as_slice();
-
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI4: &[u8] = (include_bytes!("../dmp/hull/nf/4data_ind")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV4: &[u8] = (include_bytes!("../dmp/hull/nf/4data_mesh")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB4: &[u8] = (include_bytes!("../dmp/hull/nf/4data_bbx")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID4: &[u8] = (include_bytes!("../dmp/hull/nf/4data_hash")).as_slice();
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI5: &[u8] = (include_bytes!("../dmp/hull/nf/5data_ind")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV5: &[u8] = (include_bytes!("../dmp/hull/nf/5data_mesh")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB5: &[u8] = (include_bytes!("../dmp/hull/nf/5data_bbx")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID5: &[u8] = (include_bytes!("../dmp/hull/nf/5data_hash")).as_slice();
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI6: &[u8] = (include_bytes!("../dmp/hull/nf/6data_ind")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV6: &[u8] = (include_bytes!("../dmp/hull/nf/6data_mesh")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB6: &[u8] = (include_bytes!("../dmp/hull/nf/6data_bbx")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID6: &[u8] = (include_bytes!("../dmp/hull/nf/6data_hash")).as_slice();
-
-
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDI7: &[u8] = (include_bytes!("../dmp/hull/nf/7data_ind")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDV7: &[u8] = (include_bytes!("../dmp/hull/nf/7data_mesh")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLDB7: &[u8] = (include_bytes!("../dmp/hull/nf/7data_bbx")).as_slice();
-#[cfg(not(target_arch = "wasm32"))]
-const HULLID7: &[u8] = (include_bytes!("../dmp/hull/nf/7data_hash")).as_slice();*/
-
-#[cfg(target_arch = "wasm32")]
-const HULLDI0: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDV0: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDB0: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLID0: &[u8] = &[0; 1];
-
-#[cfg(target_arch = "wasm32")]
-const HULLDI1: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDV1: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDB1: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLID1: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDI2: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDV2: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDB2: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLID2: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDI3: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDV3: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDB3: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLID3: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDI4: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDV4: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDB4: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLID4: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDI5: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDV5: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDB5: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLID5: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDI6: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDV6: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDB6: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLID6: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDI7: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDV7: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLDB7: &[u8] = &[0; 1];
-#[cfg(target_arch = "wasm32")]
-const HULLID7: &[u8] = &[0; 1];
-
-pub fn read_hull_packed_new_format0() -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
-    let decoded_mesh: Vec<u8> = decompress_to_vec(HULLDV0).unwrap();
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_mesh.as_slice());
-
-    let decoded_indxes: Vec<u8> = decompress_to_vec(HULLDI0).unwrap();
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_indxes.as_slice());
-
-    let decoded_bbxes: Vec<u8> = decompress_to_vec(HULLDB0).unwrap();
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_bbxes.as_slice());
-
-    let mut out_bbx = {
-        let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
-        let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
-        let bbx = BoundingBox::from_iter([pmin, pmax]);
-        bbx
-    };
-    let mut bbxes: Vec<BoundingBox<Point3<f64>>> = Vec::with_capacity(bbxes_bytes_back.len()/2);
-    bbxes_bytes_back.chunks(6).for_each(|b| {
-        let bbx: BoundingBox<Point3<f64>> = {
-            let pmin: Point3<f64> = Point3::new(b[0] as f64, b[1] as f64, b[2] as f64);
-            let pmax: Point3<f64> = Point3::new(b[3] as f64, b[4] as f64, b[5] as f64);
-            let bbx = BoundingBox::from_iter([pmin, pmax]);
-            bbx
-        };
-        out_bbx += &bbx;
-        bbxes.push(bbx);
-    });
-
-    let mut meta_data: Vec<i32> =   Vec::with_capacity(meshes_bytes_back.len());
+use crate::scene::pack_format::HullPackFiles;
 
-    meshes_bytes_back.iter().for_each(|m| {
-        meta_data.push(unpack_id(m.material_index as u32) as i32);
-    });
+use crate::shared::mesh_common::{CompactMesh, CompactVertex, HullVertices, MeshVertex, PartEntry, Quantization};
 
-    let decoded_hashes: Vec<u8> = decompress_to_vec(HULLID0).unwrap();
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_hashes.as_slice());
+pub const Z_FIGHTING_FACTOR: f32 = 1.0;
 
-    let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
-    let mut counter = 0;
-    hashes_bytes_back.chunks(3).for_each(|hash| {
-        hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
-        counter = counter + 1;
-    });
+//vertices, indices, per vertex metadata, pack bbx, id -> (start, end, part index), part bbxs
+pub type HullData = (HullVertices, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>);
 
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
+pub fn read_hull_unpacked_new_format(decoded_v: Vec<u8>, decoded_i: Vec<u8>, decoded_b: Vec<u8>, decoded_t: Vec<u8>) -> Result<HullData, LoadError> {
+    read_hull_unpacked_new_format_remote(decoded_v.as_slice(), decoded_i.as_slice(), decoded_b.as_slice(), decoded_t.as_slice())
 }
-pub fn read_hull_packed_new_format1() -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
-    let decoded_mesh: Vec<u8> = decompress_to_vec(HULLDV1).unwrap();
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_mesh.as_slice());
-
-    let decoded_indxes: Vec<u8> = decompress_to_vec(HULLDI1).unwrap();
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_indxes.as_slice());
-
-    let decoded_bbxes: Vec<u8> = decompress_to_vec(HULLDB1).unwrap();
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_bbxes.as_slice());
 
-    let mut out_bbx = {
-        let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
-        let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
-        let bbx = BoundingBox::from_iter([pmin, pmax]);
-        bbx
-    };
-    let mut bbxes: Vec<BoundingBox<Point3<f64>>> = Vec::with_capacity(bbxes_bytes_back.len()/2);
-    bbxes_bytes_back.chunks(6).for_each(|b| {
-        let bbx: BoundingBox<Point3<f64>> = {
-            let pmin: Point3<f64> = Point3::new(b[0] as f64, b[1] as f64, b[2] as f64);
-            let pmax: Point3<f64> = Point3::new(b[3] as f64, b[4] as f64, b[5] as f64);
-            let bbx = BoundingBox::from_iter([pmin, pmax]);
-            bbx
-        };
-        out_bbx += &bbx;
-        bbxes.push(bbx);
-    });
-
-    let mut meta_data: Vec<i32> =   Vec::with_capacity(meshes_bytes_back.len());
-    meshes_bytes_back.iter().for_each(|m| {
-        meta_data.push(unpack_id(m.material_index as u32) as i32);
-    });
-
-    let decoded_hashes: Vec<u8> = decompress_to_vec(HULLID1).unwrap();
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_hashes.as_slice());
-
-    let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
-    let mut counter = 0;
-    hashes_bytes_back.chunks(3).for_each(|hash| {
-        hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
-        counter = counter + 1;
-    });
-
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
+fn check_stream_lengths(streams: &[(StreamKind, usize)]) -> Result<(), LoadError> {
+    for (kind, len) in streams {
+        if (len % kind.item_size() != 0) {
+            return Err(LoadError::Length { stream: kind.name(), len: *len, item_size: kind.item_size() });
+        }
+    }
+    Ok(())
 }
-pub fn read_hull_packed_new_format2() -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
-    let decoded_mesh: Vec<u8> = decompress_to_vec(HULLDV2).unwrap();
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_mesh.as_slice());
 
-    let decoded_indxes: Vec<u8> = decompress_to_vec(HULLDI2).unwrap();
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_indxes.as_slice());
-
-    let decoded_bbxes: Vec<u8> = decompress_to_vec(HULLDB2).unwrap();
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_bbxes.as_slice());
-
-    let mut out_bbx = {
-        let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
-        let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
-        let bbx = BoundingBox::from_iter([pmin, pmax]);
-        bbx
-    };
-    let mut bbxes: Vec<BoundingBox<Point3<f64>>> = Vec::with_capacity(bbxes_bytes_back.len()/2);
-    bbxes_bytes_back.chunks(6).for_each(|b| {
-        let bbx: BoundingBox<Point3<f64>> = {
-            let pmin: Point3<f64> = Point3::new(b[0] as f64, b[1] as f64, b[2] as f64);
-            let pmax: Point3<f64> = Point3::new(b[3] as f64, b[4] as f64, b[5] as f64);
-            let bbx = BoundingBox::from_iter([pmin, pmax]);
-            bbx
-        };
-        out_bbx += &bbx;
-        bbxes.push(bbx);
-    });
-
-    let mut meta_data: Vec<i32> =  Vec::with_capacity(meshes_bytes_back.len());
-    meshes_bytes_back.iter().for_each(|m| {
-        meta_data.push(unpack_id(m.material_index as u32) as i32);
-    });
-
-    let decoded_hashes: Vec<u8> = decompress_to_vec(HULLID2).unwrap();
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_hashes.as_slice());
-
-    let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
-    let mut counter = 0;
-    hashes_bytes_back.chunks(3).for_each(|hash| {
-        hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
-        counter = counter + 1;
-    });
-
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
+fn check_hashes(decoded_b: &[u8], decoded_t: &[u8], vertices: usize) -> Result<(), LoadError> {
+    let parts = decoded_t.len() / StreamKind::Hashes.item_size();
+    let bbxs = decoded_b.len() / StreamKind::Bbxs.item_size();
+    if (bbxs != parts) {
+        return Err(LoadError::BboxCount { bbxs: bbxs, parts: parts });
+    }
+    let hashes: Vec<u32> = bytemuck::pod_collect_to_vec(decoded_t);
+    for hash in hashes.chunks_exact(3) {
+        //negative part ids are kept for the cable network, see node_part_id
+        if (hash[0] > i32::MAX as u32) {
+            return Err(LoadError::Format { message: format!("part id {} is negative, negative ids are kept for the cable network", hash[0] as i32) });
+        }
+        //end is inclusive, see GpuMem::change_material
+        if (hash[1] > hash[2] || hash[2] as usize >= vertices) {
+            return Err(LoadError::HashRange { id: hash[0], start: hash[1], end: hash[2], vertices: vertices });
+        }
+    }
+    Ok(())
 }
-pub fn read_hull_packed_new_format3() -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
-    let decoded_mesh: Vec<u8> = decompress_to_vec(HULLDV3).unwrap();
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_mesh.as_slice());
-    let decoded_indxes: Vec<u8> = decompress_to_vec(HULLDI3).unwrap();
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_indxes.as_slice());
-    let decoded_bbxes: Vec<u8> = decompress_to_vec(HULLDB3).unwrap();
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_bbxes.as_slice());
-    let mut out_bbx = {
-        let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
-        let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
-        let bbx = BoundingBox::from_iter([pmin, pmax]);
-        bbx
-    };
-    let mut bbxes: Vec<BoundingBox<Point3<f64>>> = vec![];
-    bbxes_bytes_back.chunks(6).for_each(|b| {
-        let bbx: BoundingBox<Point3<f64>> = {
-            let pmin: Point3<f64> = Point3::new(b[0] as f64, b[1] as f64, b[2] as f64);
-            let pmax: Point3<f64> = Point3::new(b[3] as f64, b[4] as f64, b[5] as f64);
-            let bbx = BoundingBox::from_iter([pmin, pmax]);
-            bbx
-        };
-        out_bbx += &bbx;
-        bbxes.push(bbx);
-    });
-    let mut meta_data: Vec<i32> = vec![];
-    meshes_bytes_back.iter().for_each(|m| {
-        meta_data.push(unpack_id(m.material_index as u32) as i32);
-    });
-    let decoded_hashes: Vec<u8> = decompress_to_vec(HULLID3).unwrap();
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_hashes.as_slice());
-    let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
-    let mut counter = 0;
-    hashes_bytes_back.chunks(3).for_each(|hash| {
-        hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
-        counter = counter + 1;
-    });
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
-}
-pub fn read_hull_packed_new_format4() -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
-    let decoded_mesh: Vec<u8> = decompress_to_vec(HULLDV4).unwrap();
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_mesh.as_slice());
-    let decoded_indxes: Vec<u8> = decompress_to_vec(HULLDI4).unwrap();
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_indxes.as_slice());
-    let decoded_bbxes: Vec<u8> = decompress_to_vec(HULLDB4).unwrap();
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_bbxes.as_slice());
-    let mut out_bbx = {
-        let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
-        let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
-        let bbx = BoundingBox::from_iter([pmin, pmax]);
-        bbx
-    };
-    let mut bbxes: Vec<BoundingBox<Point3<f64>>> = vec![];
-    bbxes_bytes_back.chunks(6).for_each(|b| {
-        let bbx: BoundingBox<Point3<f64>> = {
-            let pmin: Point3<f64> = Point3::new(b[0] as f64, b[1] as f64, b[2] as f64);
-            let pmax: Point3<f64> = Point3::new(b[3] as f64, b[4] as f64, b[5] as f64);
-            let bbx = BoundingBox::from_iter([pmin, pmax]);
-            bbx
-        };
-        out_bbx += &bbx;
-        bbxes.push(bbx);
-    });
-    let mut meta_data: Vec<i32> = vec![];
-    meshes_bytes_back.iter().for_each(|m| {
-        meta_data.push(unpack_id(m.material_index as u32) as i32);
-    });
-    let decoded_hashes: Vec<u8> = decompress_to_vec(HULLID4).unwrap();
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_hashes.as_slice());
-    let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
-    let mut counter = 0;
-    hashes_bytes_back.chunks(3).for_each(|hash| {
-        hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
-        counter = counter + 1;
-    });
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
-}
-pub fn read_hull_packed_new_format5() -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
-    let decoded_mesh: Vec<u8> = decompress_to_vec(HULLDV5).unwrap();
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_mesh.as_slice());
-    let decoded_indxes: Vec<u8> = decompress_to_vec(HULLDI5).unwrap();
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_indxes.as_slice());
-    let decoded_bbxes: Vec<u8> = decompress_to_vec(HULLDB5).unwrap();
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_bbxes.as_slice());
-    let mut out_bbx = {
-        let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
-        let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
-        let bbx = BoundingBox::from_iter([pmin, pmax]);
-        bbx
-    };
-    let mut bbxes: Vec<BoundingBox<Point3<f64>>> = vec![];
-    bbxes_bytes_back.chunks(6).for_each(|b| {
-        let bbx: BoundingBox<Point3<f64>> = {
-            let pmin: Point3<f64> = Point3::new(b[0] as f64, b[1] as f64, b[2] as f64);
-            let pmax: Point3<f64> = Point3::new(b[3] as f64, b[4] as f64, b[5] as f64);
-            let bbx = BoundingBox::from_iter([pmin, pmax]);
-            bbx
-        };
-        out_bbx += &bbx;
-        bbxes.push(bbx);
-    });
-    let mut meta_data: Vec<i32> = vec![];
-    meshes_bytes_back.iter().for_each(|m| {
-        meta_data.push(unpack_id(m.material_index as u32) as i32);
-    });
-    let decoded_hashes: Vec<u8> = decompress_to_vec(HULLID5).unwrap();
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_hashes.as_slice());
-    let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
-    let mut counter = 0;
-    hashes_bytes_back.chunks(3).for_each(|hash| {
-        hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
-        counter = counter + 1;
-    });
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
-}
-pub fn read_hull_packed_new_format6() -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
-    let decoded_mesh: Vec<u8> = decompress_to_vec(HULLDV6).unwrap();
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_mesh.as_slice());
-    let decoded_indxes: Vec<u8> = decompress_to_vec(HULLDI6).unwrap();
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_indxes.as_slice());
-    let decoded_bbxes: Vec<u8> = decompress_to_vec(HULLDB6).unwrap();
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_bbxes.as_slice());
-    let mut out_bbx = {
-        let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
-        let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
-        let bbx = BoundingBox::from_iter([pmin, pmax]);
-        bbx
-    };
-    let mut bbxes: Vec<BoundingBox<Point3<f64>>> = vec![];
-    bbxes_bytes_back.chunks(6).for_each(|b| {
-        let bbx: BoundingBox<Point3<f64>> = {
-            let pmin: Point3<f64> = Point3::new(b[0] as f64, b[1] as f64, b[2] as f64);
-            let pmax: Point3<f64> = Point3::new(b[3] as f64, b[4] as f64, b[5] as f64);
-            let bbx = BoundingBox::from_iter([pmin, pmax]);
-            bbx
-        };
-        out_bbx += &bbx;
-        bbxes.push(bbx);
-    });
-    let mut meta_data: Vec<i32> = vec![];
-    meshes_bytes_back.iter().for_each(|m| {
-        meta_data.push(unpack_id(m.material_index as u32) as i32);
-    });
-    let decoded_hashes: Vec<u8> = decompress_to_vec(HULLID6).unwrap();
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_hashes.as_slice());
-    let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
-    let mut counter = 0;
-    hashes_bytes_back.chunks(3).for_each(|hash| {
-        hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
-        counter = counter + 1;
-    });
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
+
+//an index past the vertices would reach draw_indexed
+pub fn check_indices(decoded_i: &[u8], vertices: usize) -> Result<(), LoadError> {
+    match decoded_i.chunks_exact(size_of::<u32>()).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).find(|i| *i as usize >= vertices) {
+        None => Ok(()),
+        Some(i) => Err(LoadError::IndexRange { index: i, vertices: vertices }),
+    }
 }
-pub fn read_hull_packed_new_format7() -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
-    let decoded_mesh: Vec<u8> = decompress_to_vec(HULLDV7).unwrap();
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_mesh.as_slice());
-    let decoded_indxes: Vec<u8> = decompress_to_vec(HULLDI7).unwrap();
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_indxes.as_slice());
-    let decoded_bbxes: Vec<u8> = decompress_to_vec(HULLDB7).unwrap();
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_bbxes.as_slice());
-    let mut out_bbx = {
-        let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
-        let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
-        let bbx = BoundingBox::from_iter([pmin, pmax]);
-        bbx
-    };
-    let mut bbxes: Vec<BoundingBox<Point3<f64>>> = vec![];
-    bbxes_bytes_back.chunks(6).for_each(|b| {
-        let bbx: BoundingBox<Point3<f64>> = {
-            let pmin: Point3<f64> = Point3::new(b[0] as f64, b[1] as f64, b[2] as f64);
-            let pmax: Point3<f64> = Point3::new(b[3] as f64, b[4] as f64, b[5] as f64);
-            let bbx = BoundingBox::from_iter([pmin, pmax]);
-            bbx
-        };
-        out_bbx += &bbx;
-        bbxes.push(bbx);
-    });
-    let mut meta_data: Vec<i32> = vec![];
-    meshes_bytes_back.iter().for_each(|m| {
-        meta_data.push(unpack_id(m.material_index as u32) as i32);
-    });
-    let decoded_hashes: Vec<u8> = decompress_to_vec(HULLID7).unwrap();
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_hashes.as_slice());
-    let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
-    let mut counter = 0;
-    hashes_bytes_back.chunks(3).for_each(|hash| {
-        hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
-        counter = counter + 1;
-    });
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
+
+//Checks everything GpuMem relies on before the streams are cast, so a broken download is an error instead of a panic
+pub fn check_hull_streams(decoded_v: &[u8], decoded_i: &[u8], decoded_b: &[u8], decoded_t: &[u8]) -> Result<(), LoadError> {
+    check_hull_lengths(decoded_v.len(), decoded_i.len(), decoded_b, decoded_t)?;
+    check_indices(decoded_i, decoded_v.len() / size_of::<MeshVertex>())
 }
 
+//vertices and indices may be already on the GPU, only their lengths are needed, indices are checked while they are uploaded
+pub fn check_hull_lengths(v_len: usize, i_len: usize, decoded_b: &[u8], decoded_t: &[u8]) -> Result<(), LoadError> {
+    check_stream_lengths(&[(StreamKind::Vertices, v_len), (StreamKind::Indices, i_len), (StreamKind::Bbxs, decoded_b.len()), (StreamKind::Hashes, decoded_t.len())])?;
+    let vertices = v_len / size_of::<MeshVertex>();
+    //get_triangle_by_vertex_index takes vertices by 3
+    if (vertices % 3 != 0) {
+        return Err(LoadError::TriangleCount { vertices: vertices });
+    }
+    check_hashes(decoded_b, decoded_t, vertices)
+}
 
+//Same for the compact layout, hash ranges are in shared vertices, indices and vertex parts must stay inside their tables
+pub fn check_compact_streams(decoded_c: &[u8], decoded_p: &[u8], decoded_q: &[u8], decoded_i: &[u8], decoded_b: &[u8], decoded_t: &[u8]) -> Result<(), LoadError> {
+    check_stream_lengths(&[(StreamKind::CompactVertices, decoded_c.len()), (StreamKind::Parts, decoded_p.len()), (StreamKind::Indices, decoded_i.len()), (StreamKind::Bbxs, decoded_b.len()), (StreamKind::Hashes, decoded_t.len())])?;
+    if (decoded_q.len() != size_of::<Quantization>()) {
+        return Err(LoadError::Length { stream: StreamKind::Quantization.name(), len: decoded_q.len(), item_size: size_of::<Quantization>() });
+    }
+    let vertices = decoded_c.len() / size_of::<CompactVertex>();
+    let indices = decoded_i.len() / size_of::<u32>();
+    if (indices % 3 != 0) {
+        return Err(LoadError::Format { message: format!("index count {} is not a multiple of 3", indices) });
+    }
+    check_indices(decoded_i, vertices)?;
+    let parts = decoded_p.len() / size_of::<PartEntry>();
+    let hashes = decoded_t.len() / StreamKind::Hashes.item_size();
+    if (parts != hashes) {
+        return Err(LoadError::Format { message: format!("part table has {} entries for {} parts", parts, hashes) });
+    }
+    let cv: Vec<CompactVertex> = bytemuck::pod_collect_to_vec(decoded_c);
+    match cv.iter().find(|v| v.part as usize >= parts) {
+        None => {}
+        Some(v) => { return Err(LoadError::PartRange { part: v.part, parts: parts }); }
+    }
+    check_hashes(decoded_b, decoded_t, vertices)
+}
 
-pub fn read_hull_unpacked_new_format(decoded_v: Vec<u8>, decoded_i: Vec<u8>, decoded_b: Vec<u8>, decoded_t: Vec<u8>) -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
-    //warn!("start convert");
+pub fn check_pack_streams(streams: &PackStreams) -> Result<(), LoadError> {
+    if (streams.is_compact()) {
+        check_compact_streams(streams.c.as_slice(), streams.p.as_slice(), streams.q.as_slice(), streams.i.as_slice(), streams.b.as_slice(), streams.t.as_slice())
+    } else {
+        check_hull_streams(streams.v.as_slice(), streams.i.as_slice(), streams.b.as_slice(), streams.t.as_slice())
+    }
+}
 
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_v.as_slice());
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_i.as_slice());
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_b.as_slice());
+pub fn read_bbxs(decoded_b: &[u8]) -> (BoundingBox<Point3<f64>>, Vec<BoundingBox<Point3<f64>>>) {
+    let bbxes_bytes_back: Vec<f32> = bytemuck::pod_collect_to_vec(decoded_b);
     let mut out_bbx = {
         let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
         let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
@@ -576,97 +128,75 @@ pub fn read_hull_unpacked_new_format(decoded_v: Vec<u8>, decoded_i: Vec<u8>, dec
         out_bbx += &bbx;
         bbxes.push(bbx);
     });
+    (out_bbx, bbxes)
+}
 
-    let mut meta_data: Vec<i32> = vec![];
-    meshes_bytes_back.iter().for_each(|m| {
-        meta_data.push(unpack_id(m.material_index as u32) as i32);
-    });
-
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_t.as_slice());
-
+pub fn read_hashes(decoded_t: &[u8]) -> HashMap<i32, (i32, i32, i32)> {
+    let hashes_bytes_back: Vec<u32> = bytemuck::pod_collect_to_vec(decoded_t);
     let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
     let mut counter = 0;
     hashes_bytes_back.chunks(3).for_each(|hash| {
         hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
         counter = counter + 1;
     });
-
-
-    warn!("finish convert");
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
+    hull_mesh
 }
 
-pub fn read_hull_unpacked_new_format_remote(load_level:i32,decoded_v: &[u8], decoded_i: &[u8], decoded_b: &[u8], decoded_t: &[u8]) -> (Vec<MeshVertex>, Vec<i32>, Vec<i32>, BoundingBox<Point3<f64>>, HashMap<i32, (i32, i32, i32)>, Vec<BoundingBox<Point3<f64>>>) {
+pub fn read_hull_unpacked_new_format_remote(decoded_v: &[u8], decoded_i: &[u8], decoded_b: &[u8], decoded_t: &[u8]) -> Result<HullData, LoadError> {
     warn!("start convert remote");
+    check_hull_streams(decoded_v, decoded_i, decoded_b, decoded_t)?;
 
-    let meshes_bytes_back: &[MeshVertex] = bytemuck::cast_slice(decoded_v);
-    let indxes_bytes_back: &[i32] = bytemuck::cast_slice(decoded_i);
-    let bbxes_bytes_back: &[f32] = bytemuck::cast_slice(decoded_b);
-    let mut out_bbx = {
-        let pmin: Point3<f64> = Point3::new(-100.0, -100.0, -100.0);
-        let pmax: Point3<f64> = Point3::new(100.0, 100.0, 100.0);
-        let bbx = BoundingBox::from_iter([pmin, pmax]);
-        bbx
-    };
-    let mut bbxes: Vec<BoundingBox<Point3<f64>>> = vec![];
-    bbxes_bytes_back.chunks(6).for_each(|b| {
-        let bbx: BoundingBox<Point3<f64>> = {
-            let pmin: Point3<f64> = Point3::new(b[0] as f64, b[1] as f64, b[2] as f64);
-            let pmax: Point3<f64> = Point3::new(b[3] as f64, b[4] as f64, b[5] as f64);
-            let bbx = BoundingBox::from_iter([pmin, pmax]);
-            bbx
-        };
-        out_bbx += &bbx;
-        bbxes.push(bbx);
-    });
+    //streams are not guaranteed to be aligned for a cast, copy them out instead
+    let meshes_bytes_back: Vec<MeshVertex> = bytemuck::pod_collect_to_vec(decoded_v);
+    let indxes_bytes_back: Vec<i32> = bytemuck::pod_collect_to_vec(decoded_i);
+    let (out_bbx, bbxes) = read_bbxs(decoded_b);
 
     let mut meta_data: Vec<i32> = vec![];
     meshes_bytes_back.iter().for_each(|m| {
-        //warn!("m.material_index {}",m.material_index);
-        //meta_data.push(m.material_index);
         meta_data.push(unpack_id(m.material_index as u32) as i32);
     });
 
-    let hashes_bytes_back: &[u32] = bytemuck::cast_slice(decoded_t);
-
-    let mut hull_mesh: HashMap<i32, (i32, i32, i32)> = HashMap::new();
-    let mut counter = 0;
-    hashes_bytes_back.chunks(3).for_each(|hash| {
-        hull_mesh.insert(hash[0] as i32, (hash[1] as i32, hash[2] as i32, counter));
-        counter = counter + 1;
-    });
-
+    let hull_mesh = read_hashes(decoded_t);
 
     //warn!("finish convert");
-    (meshes_bytes_back.to_vec(), indxes_bytes_back.to_vec(), meta_data, out_bbx, hull_mesh, bbxes)
+    Ok((HullVertices::Full(meshes_bytes_back), indxes_bytes_back, meta_data, out_bbx, hull_mesh, bbxes))
 }
 
+pub fn read_hull_compact_remote(decoded_c: &[u8], decoded_p: &[u8], decoded_q: &[u8], decoded_i: &[u8], decoded_b: &[u8], decoded_t: &[u8]) -> Result<HullData, LoadError> {
+    check_compact_streams(decoded_c, decoded_p, decoded_q, decoded_i, decoded_b, decoded_t)?;
+    let v: Vec<CompactVertex> = bytemuck::pod_collect_to_vec(decoded_c);
+    let parts: Vec<PartEntry> = bytemuck::pod_collect_to_vec(decoded_p);
+    let quantization: Quantization = bytemuck::pod_read_unaligned(decoded_q);
+    let indxes_bytes_back: Vec<i32> = bytemuck::pod_collect_to_vec(decoded_i);
+    let (out_bbx, bbxes) = read_bbxs(decoded_b);
+    //metadata stays per vertex, so select/hide ranges work the same as in the full layout
+    let meta_data: Vec<i32> = v.iter().map(|cv| unpack_id(parts[cv.part as usize].material_index as u32) as i32).collect();
+    let hull_mesh = read_hashes(decoded_t);
+    let compact = CompactMesh {
+        v: v,
+        parts: parts,
+        quantization: quantization,
+    };
+    Ok((HullVertices::Compact(compact), indxes_bytes_back, meta_data, out_bbx, hull_mesh, bbxes))
+}
 
+pub fn read_hull_streams(streams: &PackStreams) -> Result<HullData, LoadError> {
+    if (streams.is_compact()) {
+        read_hull_compact_remote(streams.c.as_slice(), streams.p.as_slice(), streams.q.as_slice(), streams.i.as_slice(), streams.b.as_slice(), streams.t.as_slice())
+    } else {
+        read_hull_unpacked_new_format_remote(streams.v.as_slice(), streams.i.as_slice(), streams.b.as_slice(), streams.t.as_slice())
+    }
+}
 
-pub fn read_cable_with_test_data() -> Vec<RawMesh> {
-    let mut out: Vec<RawMesh> = vec![];
-    let p1: Point3<f32> = Point3::new(500.0, 0.0, 0.0);
-    let s1: SpherePrimitive = SpherePrimitive::new(0, p1.clone(), CABLE_NODE_SPHERE_RADIUS, CABLE_NODE_COLOR);
-    let rm1: RawMesh = s1.triangulate();
-    out.push(rm1);
-
-    let p2: Point3<f32> = Point3::new(700.0, 0.0, 0.0);
-    let s2: SpherePrimitive = SpherePrimitive::new(1, p2.clone(), CABLE_NODE_SPHERE_RADIUS, CABLE_NODE_COLOR);
-    let rm2: RawMesh = s2.triangulate();
-    out.push(rm2);
-
-    let edge1: PipePrimitive = PipePrimitive::new(3, p1, p2, CABLE_EDGE_RADIUS, CABLE_EDGE_COLOR);
-    let rm4: RawMesh = edge1.triangulate();
-    out.push(rm4);
-
-    let p3: Point3<f32> = Point3::new(900.0, 0.0, 0.0);
-    let s3: SpherePrimitive = SpherePrimitive::new(2, p3.clone(), CABLE_NODE_SPHERE_RADIUS, CABLE_NODE_COLOR);
-    let rm3: RawMesh = s3.triangulate();
-    out.push(rm3);
-
-    let edge2: PipePrimitive = PipePrimitive::new(4, p2, p3, CABLE_EDGE_RADIUS, CABLE_EDGE_COLOR);
-    let rm5: RawMesh = edge2.triangulate();
-    out.push(rm5);
+//Reads single file pack (see pack_format), returns pack id from the header
+pub fn read_hull_pack(bytes: &[u8]) -> Result<(u32, Option<PackMeta>, HullData), LoadError> {
+    let streams = read_pack_streams(bytes)?;
+    let data = read_hull_streams(&streams)?;
+    Ok((streams.pack_id, streams.meta, data))
+}
 
-    out
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_hull_packed_from_files(pack: &HullPackFiles) -> Result<HullData, LoadError> {
+    let streams = pack.read_streams()?;
+    read_hull_streams(&streams).map_err(|e| e.in_file(pack.path().display().to_string()))
 }
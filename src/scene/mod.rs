@@ -8,6 +8,15 @@ pub mod scene_state;
 pub mod mesh_loader;
 pub mod scene_details;
 pub mod gpu_mem;
+pub mod pack_format;
+pub mod pack_stream;
+pub mod pack_upload;
+pub mod load_error;
+pub mod assembly;
+pub mod cable_network;
+pub mod cable_graph;
+pub mod instanced_pack;
+pub mod section;
 
 #[derive(Clone)]
 pub struct RawMesh {
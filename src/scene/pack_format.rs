@@ -0,0 +1,865 @@
+use std::io;
+use std::io::ErrorKind;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::{decompress_to_vec_with_limit, TINFLStatus};
+use ruzstd::decoding::StreamingDecoder;
+use serde::{Deserialize, Serialize};
+use super::load_error::LoadError;
+
+//Single file hull pack
+//header 16 bytes: magic[8] version:u16 stream_count:u16 pack_id:u32
+//stream table stream_count*40 bytes: kind:u16 codec:u16 checksum:u32 count:u64 offset:u64 packed_len:u64 raw_len:u64
+//then stream data, offsets are from the start of the file, all numbers are little endian
+pub const PACK_MAGIC: [u8; 8] = *b"PUTPACK\0";
+pub const PACK_FORMAT_VERSION: u16 = 1;
+pub const PACK_FILE_EXT: &str = "putpack";
+//vertex material_index and selection ids are stored as value*ID_MEM_OFFSET+pack_id, so pack ids are 0..ID_MEM_OFFSET
+pub const ID_MEM_OFFSET: u32 = 100;
+//the last pack slot is kept for the cable network, hull packs are 0..CABLE_PACK_ID
+pub const CABLE_PACK_ID: u32 = ID_MEM_OFFSET - 1;
+//largest value that packs with any pack id into a positive i32 material_index
+pub const MAX_RAW_ID_VALUE: u32 = (i32::MAX as u32 - (ID_MEM_OFFSET - 1)) / ID_MEM_OFFSET;
+//largest unpacked stream a reader allocates for, packs are split far below it (--budget of putout-pack)
+pub const MAX_STREAM_LEN: u64 = 1 << 30;
+const HEADER_SIZE: usize = 16;
+const STREAM_ENTRY_SIZE: usize = 40;
+const DEFLATE_LEVEL: u8 = 6;
+const ZSTD_LEVEL: i32 = 12;
+//zstd frame magic, headerless files (old layout, wasm_unpack) starting with it are zstd, others are deflate
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamKind {
+    Vertices = 1,
+    Indices = 2,
+    Bbxs = 3,
+    Hashes = 4,
+    Meta = 5,
+    CompactVertices = 6,
+    Parts = 7,
+    Quantization = 8,
+    PartInfo = 9,
+}
+
+impl StreamKind {
+    pub fn from_u16(v: u16) -> Option<StreamKind> {
+        match v {
+            1 => Some(StreamKind::Vertices),
+            2 => Some(StreamKind::Indices),
+            3 => Some(StreamKind::Bbxs),
+            4 => Some(StreamKind::Hashes),
+            5 => Some(StreamKind::Meta),
+            6 => Some(StreamKind::CompactVertices),
+            7 => Some(StreamKind::Parts),
+            8 => Some(StreamKind::Quantization),
+            9 => Some(StreamKind::PartInfo),
+            _ => None
+        }
+    }
+    //names of the old layout files, <N>data_<name>
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamKind::Vertices => "mesh",
+            StreamKind::Indices => "ind",
+            StreamKind::Bbxs => "bbx",
+            StreamKind::Hashes => "hash",
+            StreamKind::Meta => "meta",
+            StreamKind::CompactVertices => "cmesh",
+            StreamKind::Parts => "part",
+            StreamKind::Quantization => "quant",
+            StreamKind::PartInfo => "info",
+        }
+    }
+    //bytes per counted element, MeshVertex is 40 bytes, bbx is 6xf32, hash is (id,start,end)xu32, meta is json text,
+    //CompactVertex is 16 bytes, part is (id,material_index)xi32, quantization is origin and extent as 2 vec4<f32>,
+    //info is json text, see PartInfo
+    pub fn item_size(&self) -> usize {
+        match self {
+            StreamKind::Vertices => 40,
+            StreamKind::Indices => 4,
+            StreamKind::Bbxs => 24,
+            StreamKind::Hashes => 12,
+            StreamKind::Meta => 1,
+            StreamKind::CompactVertices => 16,
+            StreamKind::Parts => 8,
+            StreamKind::Quantization => 32,
+            StreamKind::PartInfo => 1,
+        }
+    }
+}
+
+//Mesh is a meshopt-style filter before zstd: indices are delta+zigzag coded, then the bytes of every element
+//are split into planes (all first bytes, all second bytes...) and each plane is delta coded byte by byte
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamCodec {
+    Stored = 0,
+    Deflate = 1,
+    Zstd = 2,
+    Mesh = 3,
+}
+
+impl StreamCodec {
+    pub const ALL: [StreamCodec; 4] = [StreamCodec::Stored, StreamCodec::Deflate, StreamCodec::Zstd, StreamCodec::Mesh];
+
+    pub fn from_u16(v: u16) -> Option<StreamCodec> {
+        match v {
+            0 => Some(StreamCodec::Stored),
+            1 => Some(StreamCodec::Deflate),
+            2 => Some(StreamCodec::Zstd),
+            3 => Some(StreamCodec::Mesh),
+            _ => None
+        }
+    }
+    pub fn from_name(name: &str) -> Option<StreamCodec> {
+        StreamCodec::ALL.iter().find(|c| c.name() == name).copied()
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamCodec::Stored => "stored",
+            StreamCodec::Deflate => "deflate",
+            StreamCodec::Zstd => "zstd",
+            StreamCodec::Mesh => "mesh",
+        }
+    }
+    //codec of a stream without a header
+    pub fn sniff(packed: &[u8]) -> StreamCodec {
+        if (packed.starts_with(&ZSTD_MAGIC)) { StreamCodec::Zstd } else { StreamCodec::Deflate }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn encode(&self, kind: StreamKind, raw: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            StreamCodec::Stored => Ok(raw.to_vec()),
+            StreamCodec::Deflate => Ok(compress_to_vec(raw, DEFLATE_LEVEL)),
+            StreamCodec::Zstd => {
+                zstd::bulk::compress(raw, ZSTD_LEVEL).map_err(|e| io::Error::new(e.kind(), format!("stream {}: can't pack zstd {} bytes ({})", kind.name(), raw.len(), e)))
+            }
+            StreamCodec::Mesh => StreamCodec::Zstd.encode(kind, mesh_filter(kind, raw).as_slice()),
+        }
+    }
+    //unpacks at most raw_len bytes, a stream that unpacks to more is an error before it is all in memory
+    pub fn decode(&self, kind: StreamKind, packed: &[u8], raw_len: u64) -> Result<Vec<u8>, LoadError> {
+        let too_long = || LoadError::Decompress { stream: kind.name(), message: format!("unpacks to more than {} bytes", raw_len) };
+        match self {
+            StreamCodec::Stored => Ok(packed.to_vec()),
+            StreamCodec::Deflate => {
+                let limit = raw_len.min(usize::MAX as u64) as usize;
+                decompress_to_vec_with_limit(packed, limit).map_err(|e| {
+                    match e.status {
+                        TINFLStatus::HasMoreOutput => too_long(),
+                        status => LoadError::Decompress { stream: kind.name(), message: format!("can't inflate {} bytes ({:?})", packed.len(), status) },
+                    }
+                })
+            }
+            StreamCodec::Zstd => {
+                let mut source: &[u8] = packed;
+                let mut raw: Vec<u8> = vec![];
+                //one byte over the limit tells a long stream from one of exactly raw_len
+                let decoded = StreamingDecoder::new(&mut source).map_err(|e| e.to_string()).and_then(|decoder| {
+                    io::Read::read_to_end(&mut io::Read::take(decoder, raw_len.saturating_add(1)), &mut raw).map_err(|e| e.to_string())
+                });
+                match decoded {
+                    Ok(_) => {
+                        if (raw.len() as u64 > raw_len) { Err(too_long()) } else { Ok(raw) }
+                    }
+                    Err(e) => Err(LoadError::Decompress { stream: kind.name(), message: format!("can't unpack zstd {} bytes ({})", packed.len(), e) }),
+                }
+            }
+            StreamCodec::Mesh => {
+                //the filter keeps the length, so the zstd frame is held to raw_len before it is unfiltered
+                let filtered = StreamCodec::Zstd.decode(kind, packed, raw_len)?;
+                Ok(mesh_unfilter(kind, filtered))
+            }
+        }
+    }
+}
+
+//tail bytes that don't make a whole element are kept as is
+#[cfg(not(target_arch = "wasm32"))]
+fn mesh_filter(kind: StreamKind, raw: &[u8]) -> Vec<u8> {
+    let item = kind.item_size();
+    let n = raw.len() / item;
+    let mut words: Vec<u8> = raw[0..n * item].to_vec();
+    if (kind == StreamKind::Indices) {
+        let mut prev: i32 = 0;
+        words.chunks_exact_mut(4).for_each(|w| {
+            let v = i32::from_le_bytes([w[0], w[1], w[2], w[3]]);
+            let d = v.wrapping_sub(prev);
+            prev = v;
+            w.copy_from_slice(&(((d << 1) ^ (d >> 31)) as u32).to_le_bytes());
+        });
+    }
+    let mut out: Vec<u8> = vec![0; raw.len()];
+    for b in 0..item {
+        let mut prev: u8 = 0;
+        for e in 0..n {
+            let v = words[e * item + b];
+            out[b * n + e] = v.wrapping_sub(prev);
+            prev = v;
+        }
+    }
+    out[n * item..].copy_from_slice(&raw[n * item..]);
+    out
+}
+
+pub fn mesh_unfilter(kind: StreamKind, filtered: Vec<u8>) -> Vec<u8> {
+    let item = kind.item_size();
+    let n = filtered.len() / item;
+    let mut out: Vec<u8> = vec![0; filtered.len()];
+    filtered[0..n * item].chunks_exact(n.max(1)).enumerate().for_each(|(b, plane)| {
+        mesh_unfilter_plane(plane, b, item, out.as_mut_slice());
+    });
+    out[n * item..].copy_from_slice(&filtered[n * item..]);
+    mesh_undelta(kind, &mut out[0..n * item]);
+    out
+}
+
+//byte plane b of a filtered stream into its place in the elements of out
+pub fn mesh_unfilter_plane(plane: &[u8], b: usize, item: usize, out: &mut [u8]) {
+    let mut prev: u8 = 0;
+    plane.iter().enumerate().for_each(|(e, v)| {
+        prev = prev.wrapping_add(*v);
+        out[e * item + b] = prev;
+    });
+}
+
+//last step once all planes are in place, words holds whole elements only
+pub fn mesh_undelta(kind: StreamKind, words: &mut [u8]) {
+    if (kind == StreamKind::Indices) {
+        let mut prev: i32 = 0;
+        words.chunks_exact_mut(4).for_each(|w| {
+            let z = u32::from_le_bytes([w[0], w[1], w[2], w[3]]);
+            let d = ((z >> 1) as i32) ^ -((z & 1) as i32);
+            prev = prev.wrapping_add(d);
+            w.copy_from_slice(&prev.to_le_bytes());
+        });
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PackMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    //xmin ymin zmin xmax ymax zmax
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbx: Option<[f32; 6]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+}
+
+//Optional per part attributes, the info stream and sidecar files are a json array of them,
+//e.g. [{"id":1904245,"name":"B12-S3-101","type":10,"block":"B12","section":"S3","grade":"AH36","thickness":12,"weight":351.5}]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PartInfo {
+    pub id: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    //HullPartTypes code
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub part_type: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    //material grade, e.g. AH36
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grade: Option<String>,
+    //mm
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thickness: Option<f32>,
+    //kg
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f32>,
+}
+
+pub fn parse_part_info(raw: &[u8]) -> Result<Vec<PartInfo>, LoadError> {
+    serde_json::from_slice(raw).map_err(|e| format_error(format!("stream info: {}", e)))
+}
+
+#[derive(Clone, Debug)]
+pub struct StreamEntry {
+    pub kind: StreamKind,
+    pub codec: StreamCodec,
+    pub checksum: u32,
+    pub count: u64,
+    pub offset: u64,
+    pub packed_len: u64,
+    pub raw_len: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct PackHeader {
+    pub version: u16,
+    pub pack_id: u32,
+    pub streams: Vec<StreamEntry>,
+}
+
+//Header and stream table from the start of a file, None while there are too few bytes for them,
+//stream ranges are checked against the table but not against the file size
+pub fn parse_header(bytes: &[u8]) -> Result<Option<PackHeader>, LoadError> {
+    if (bytes.len() >= PACK_MAGIC.len() && !is_pack_container(bytes)) {
+        return Err(format_error("not a hull pack, bad magic".to_string()));
+    }
+    if (bytes.len() < HEADER_SIZE) {
+        return Ok(None);
+    }
+    let version = read_u16(bytes, 8);
+    if (version == 0 || version > PACK_FORMAT_VERSION) {
+        return Err(format_error(format!("unsupported pack format version {}, this build reads up to {}", version, PACK_FORMAT_VERSION)));
+    }
+    let stream_count = read_u16(bytes, 10) as usize;
+    let pack_id = read_u32(bytes, 12);
+    let table_end = HEADER_SIZE + stream_count * STREAM_ENTRY_SIZE;
+    if (bytes.len() < table_end) {
+        return Ok(None);
+    }
+    let mut streams: Vec<StreamEntry> = Vec::with_capacity(stream_count);
+    for n in 0..stream_count {
+        let at = HEADER_SIZE + n * STREAM_ENTRY_SIZE;
+        let raw_kind = read_u16(bytes, at);
+        let raw_codec = read_u16(bytes, at + 2);
+        //unknown kinds are skipped so newer writers can add streams
+        let kind = match StreamKind::from_u16(raw_kind) {
+            None => { continue; }
+            Some(kind) => kind
+        };
+        let codec = StreamCodec::from_u16(raw_codec).ok_or_else(|| format_error(format!("stream {} has unknown codec {}", kind.name(), raw_codec)))?;
+        let entry = StreamEntry {
+            kind: kind,
+            codec: codec,
+            checksum: read_u32(bytes, at + 4),
+            count: read_u64(bytes, at + 8),
+            offset: read_u64(bytes, at + 16),
+            packed_len: read_u64(bytes, at + 24),
+            raw_len: read_u64(bytes, at + 32),
+        };
+        if (entry.offset < table_end as u64 || entry.offset.checked_add(entry.packed_len).is_none()) {
+            return Err(format_error(format!("stream {} at {}+{} is out of the file", kind.name(), entry.offset, entry.packed_len)));
+        }
+        if (entry.count.checked_mul(kind.item_size() as u64) != Some(entry.raw_len)) {
+            return Err(format_error(format!("stream {} has {} bytes for {} elements of {} bytes", kind.name(), entry.raw_len, entry.count, kind.item_size())));
+        }
+        streams.push(entry);
+    }
+    Ok(Some(PackHeader {
+        version: version,
+        pack_id: pack_id,
+        streams: streams,
+    }))
+}
+
+pub struct PackContainer<'a> {
+    pub header: PackHeader,
+    bytes: &'a [u8],
+}
+
+impl<'a> PackContainer<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, LoadError> {
+        if (!is_pack_container(bytes)) {
+            return Err(format_error("not a hull pack, bad magic".to_string()));
+        }
+        let header = match parse_header(bytes)? {
+            Some(header) => header,
+            None => {
+                if (bytes.len() < HEADER_SIZE) {
+                    return Err(format_error(format!("header is truncated, {} bytes", bytes.len())));
+                }
+                let table_end = HEADER_SIZE + read_u16(bytes, 10) as usize * STREAM_ENTRY_SIZE;
+                return Err(format_error(format!("stream table is truncated, need {} bytes, file has {}", table_end, bytes.len())));
+            }
+        };
+        for entry in &header.streams {
+            if (entry.offset + entry.packed_len > bytes.len() as u64) {
+                return Err(format_error(format!("stream {} at {}+{} is out of file size {}, file is truncated", entry.kind.name(), entry.offset, entry.packed_len, bytes.len())));
+            }
+        }
+        Ok(Self {
+            header: header,
+            bytes: bytes,
+        })
+    }
+
+    pub fn stream(&self, kind: StreamKind) -> Option<&StreamEntry> {
+        self.header.streams.iter().find(|s| s.kind == kind)
+    }
+
+    pub fn packed_stream(&self, entry: &StreamEntry) -> &'a [u8] {
+        &self.bytes[entry.offset as usize..(entry.offset + entry.packed_len) as usize]
+    }
+
+    pub fn read_stream(&self, kind: StreamKind) -> Result<Vec<u8>, LoadError> {
+        let entry = self.stream(kind).ok_or_else(|| format_error(format!("stream {} is missing", kind.name())))?;
+        let packed = self.packed_stream(entry);
+        let checksum = crc32(packed);
+        if (checksum != entry.checksum) {
+            return Err(LoadError::Checksum { stream: kind.name(), expected: entry.checksum, actual: checksum });
+        }
+        let raw = entry.codec.decode(kind, packed, entry.raw_len)?;
+        if (raw.len() as u64 != entry.raw_len) {
+            return Err(LoadError::Decompress { stream: kind.name(), message: format!("unpacked to {} bytes, expected {}", raw.len(), entry.raw_len) });
+        }
+        Ok(raw)
+    }
+
+    pub fn meta(&self) -> Result<Option<PackMeta>, LoadError> {
+        match self.stream(StreamKind::Meta) {
+            None => Ok(None),
+            Some(_) => {
+                let raw = self.read_stream(StreamKind::Meta)?;
+                let meta: PackMeta = serde_json::from_slice(raw.as_slice()).map_err(|e| format_error(format!("stream meta: {}", e)))?;
+                Ok(Some(meta))
+            }
+        }
+    }
+
+    pub fn part_info(&self) -> Result<Option<Vec<PartInfo>>, LoadError> {
+        match self.stream(StreamKind::PartInfo) {
+            None => Ok(None),
+            Some(_) => {
+                let raw = self.read_stream(StreamKind::PartInfo)?;
+                Ok(Some(parse_part_info(raw.as_slice())?))
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct PackWriter {
+    pack_id: u32,
+    codec: StreamCodec,
+    streams: Vec<(StreamKind, StreamCodec, u64, Vec<u8>)>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PackWriter {
+    pub fn new(pack_id: u32, codec: StreamCodec) -> Self {
+        Self {
+            pack_id: pack_id,
+            codec: codec,
+            streams: vec![],
+        }
+    }
+    pub fn add_stream(&mut self, kind: StreamKind, raw: &[u8]) {
+        let count = (raw.len() / kind.item_size()) as u64;
+        let codec = self.codec;
+        self.streams.retain(|s| s.0 != kind);
+        self.streams.push((kind, codec, count, raw.to_vec()));
+    }
+    pub fn set_meta(&mut self, meta: &PackMeta) -> io::Result<()> {
+        let raw = serde_json::to_vec(meta).map_err(|e| io::Error::new(ErrorKind::InvalidData, format!("stream meta: {}", e)))?;
+        self.add_stream(StreamKind::Meta, raw.as_slice());
+        Ok(())
+    }
+    pub fn set_part_info(&mut self, info: &[PartInfo]) -> io::Result<()> {
+        let raw = serde_json::to_vec(info).map_err(|e| io::Error::new(ErrorKind::InvalidData, format!("stream info: {}", e)))?;
+        self.add_stream(StreamKind::PartInfo, raw.as_slice());
+        Ok(())
+    }
+    //sum of unpacked stream sizes
+    pub fn raw_len(&self) -> usize {
+        self.streams.iter().map(|s| s.3.len()).sum()
+    }
+    //fails if a stream can't be packed, a pack is never written with it missing
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let packed: Vec<Vec<u8>> = self.streams.iter().map(|(kind, codec, _count, raw)| codec.encode(*kind, raw)).collect::<io::Result<Vec<Vec<u8>>>>()?;
+        let mut out: Vec<u8> = Vec::with_capacity(HEADER_SIZE + self.streams.len() * STREAM_ENTRY_SIZE + packed.iter().map(|p| p.len()).sum::<usize>());
+        out.extend_from_slice(&PACK_MAGIC);
+        out.extend_from_slice(&PACK_FORMAT_VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.streams.len() as u16).to_le_bytes());
+        out.extend_from_slice(&self.pack_id.to_le_bytes());
+        let mut offset = (HEADER_SIZE + self.streams.len() * STREAM_ENTRY_SIZE) as u64;
+        self.streams.iter().zip(packed.iter()).for_each(|((kind, codec, count, raw), p)| {
+            out.extend_from_slice(&(*kind as u16).to_le_bytes());
+            out.extend_from_slice(&(*codec as u16).to_le_bytes());
+            out.extend_from_slice(&crc32(p).to_le_bytes());
+            out.extend_from_slice(&count.to_le_bytes());
+            out.extend_from_slice(&offset.to_le_bytes());
+            out.extend_from_slice(&(p.len() as u64).to_le_bytes());
+            out.extend_from_slice(&(raw.len() as u64).to_le_bytes());
+            offset = offset + p.len() as u64;
+        });
+        packed.iter().for_each(|p| out.extend_from_slice(p));
+        Ok(out)
+    }
+}
+
+pub fn pack_raw_id(value: u32, pack_id: u32) -> u32 {
+    value * ID_MEM_OFFSET + pack_id
+}
+
+pub fn unpack_id(raw_id: u32) -> u32 {
+    let pack_id = raw_id % ID_MEM_OFFSET;
+    let id = (raw_id - pack_id) / ID_MEM_OFFSET;
+    id
+}
+
+pub fn unpack_packid(raw_id: u32) -> u32 {
+    let pack_id = raw_id % ID_MEM_OFFSET;
+    pack_id
+}
+
+//Unpacked streams of one pack, same for the container and the old four-file layout
+//a compact pack has c, p and q instead of v, the old layout is never compact
+#[derive(Default)]
+pub struct PackStreams {
+    pub pack_id: u32,
+    pub v: Vec<u8>,
+    pub i: Vec<u8>,
+    pub b: Vec<u8>,
+    pub t: Vec<u8>,
+    pub c: Vec<u8>,
+    pub p: Vec<u8>,
+    pub q: Vec<u8>,
+    pub meta: Option<PackMeta>,
+    //None if the pack has no info stream
+    pub info: Option<Vec<PartInfo>>,
+}
+
+impl PackStreams {
+    pub fn is_compact(&self) -> bool {
+        !self.c.is_empty() || !self.q.is_empty()
+    }
+}
+
+pub fn read_pack_streams(bytes: &[u8]) -> Result<PackStreams, LoadError> {
+    let container = PackContainer::parse(bytes)?;
+    let is_compact = container.stream(StreamKind::CompactVertices).is_some();
+    let read_if = |kind: StreamKind, is_needed: bool| {
+        if (is_needed) { container.read_stream(kind) } else { Ok(vec![]) }
+    };
+    Ok(PackStreams {
+        pack_id: container.header.pack_id,
+        v: read_if(StreamKind::Vertices, !is_compact)?,
+        i: container.read_stream(StreamKind::Indices)?,
+        b: container.read_stream(StreamKind::Bbxs)?,
+        t: container.read_stream(StreamKind::Hashes)?,
+        c: read_if(StreamKind::CompactVertices, is_compact)?,
+        p: read_if(StreamKind::Parts, is_compact)?,
+        q: read_if(StreamKind::Quantization, is_compact)?,
+        meta: container.meta()?,
+        info: container.part_info()?,
+    })
+}
+
+pub fn is_pack_container(bytes: &[u8]) -> bool {
+    bytes.len() >= PACK_MAGIC.len() && bytes[0..PACK_MAGIC.len()] == PACK_MAGIC
+}
+
+//CRC-32 (IEEE), same as zip/png
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+//continues a crc32 of the bytes before data, start with 0
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc: u32 = !crc;
+    data.iter().for_each(|b| {
+        crc = crc ^ (*b as u32);
+        for _ in 0..8 {
+            let mask = (!(crc & 1)).wrapping_add(1);
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    });
+    !crc
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+pub enum PackLayout {
+    //"<dir>/<name>.putpack"
+    Container(PathBuf),
+    //old layout, 4 deflated files "<dir>/<pack_id>data_mesh|ind|bbx|hash"
+    Legacy {
+        mesh: PathBuf,
+        ind: PathBuf,
+        bbx: PathBuf,
+        hash: PathBuf,
+    },
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+pub struct HullPackFiles {
+    pub pack_id: u32,
+    pub layout: PackLayout,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HullPackFiles {
+    pub fn new_legacy(dir: &Path, pack_id: u32) -> Self {
+        Self {
+            pack_id: pack_id,
+            layout: PackLayout::Legacy {
+                mesh: dir.join(format!("{}data_mesh", pack_id)),
+                ind: dir.join(format!("{}data_ind", pack_id)),
+                bbx: dir.join(format!("{}data_bbx", pack_id)),
+                hash: dir.join(format!("{}data_hash", pack_id)),
+            },
+        }
+    }
+    //only the header is read here, streams are checked on load
+    pub fn new_container(path: &Path) -> io::Result<Self> {
+        let mut header: Vec<u8> = vec![0; 16];
+        let mut file = fs::File::open(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        io::Read::read_exact(&mut file, header.as_mut_slice()).map_err(|_e| {
+            io::Error::new(ErrorKind::InvalidData, format!("{}: file is too short for a hull pack", path.display()))
+        })?;
+        if (!is_pack_container(header.as_slice())) {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("{}: not a hull pack, expected magic {:?}", path.display(), String::from_utf8_lossy(&PACK_MAGIC[0..7]))));
+        }
+        let pack_id = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+        Ok(Self {
+            pack_id: pack_id,
+            layout: PackLayout::Container(path.to_path_buf()),
+        })
+    }
+    pub fn path(&self) -> &Path {
+        match &self.layout {
+            PackLayout::Container(path) => path,
+            PackLayout::Legacy { mesh, .. } => mesh,
+        }
+    }
+    //sidecar part info "<dir>/<name>.parts.json" or "<dir>/<pack_id>data_parts.json" for the old layout
+    pub fn part_info_path(&self) -> PathBuf {
+        match &self.layout {
+            PackLayout::Container(path) => path.with_extension("parts.json"),
+            PackLayout::Legacy { mesh, .. } => mesh.with_file_name(format!("{}data_parts.json", self.pack_id)),
+        }
+    }
+    //None if there is no sidecar file
+    pub fn read_part_info(&self) -> Result<Option<Vec<PartInfo>>, LoadError> {
+        let path = self.part_info_path();
+        if (!path.is_file()) {
+            return Ok(None);
+        }
+        let file_error = |e: LoadError| e.in_file(path.display().to_string());
+        let raw: Vec<u8> = fs::read(&path).map_err(|e| file_error(LoadError::from(e)))?;
+        Ok(Some(parse_part_info(raw.as_slice()).map_err(file_error)?))
+    }
+    fn check_exists(&self) -> io::Result<()> {
+        match &self.layout {
+            PackLayout::Container(_) => {}
+            PackLayout::Legacy { mesh, ind, bbx, hash } => {
+                for path in [mesh, ind, bbx, hash] {
+                    if (!path.is_file()) {
+                        return Err(io::Error::new(ErrorKind::NotFound, format!("hull pack {} is incomplete, missing {}", self.pack_id, path.display())));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+//"3data_mesh" -> Some(3)
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_pack_file_name(path: &Path) -> Option<u32> {
+    let name = path.file_name()?.to_str()?;
+    let (id, stream) = name.split_once("data_")?;
+    match stream {
+        "mesh" | "ind" | "bbx" | "hash" => id.parse::<u32>().ok(),
+        _ => None
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_container_file_name(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == PACK_FILE_EXT)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn push_pack(packs: &mut Vec<HullPackFiles>, pack: HullPackFiles) -> io::Result<()> {
+    match packs.iter().find(|p| p.pack_id == pack.pack_id) {
+        None => {
+            packs.push(pack);
+            Ok(())
+        }
+        Some(other) => {
+            if (other.path() != pack.path()) {
+                Err(io::Error::new(ErrorKind::InvalidInput, format!("hull pack {} is given twice: {} and {}", pack.pack_id, other.path().display(), pack.path().display())))
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+//Each path is a model directory (all packs inside are taken), a *.putpack file or a single old layout pack file (any of its four streams)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn find_hull_packs(paths: &[PathBuf]) -> io::Result<Vec<HullPackFiles>> {
+    let mut packs: Vec<HullPackFiles> = vec![];
+    for path in paths {
+        if (path.is_dir()) {
+            let entries = fs::read_dir(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+            let mut entry_paths: Vec<PathBuf> = vec![];
+            for entry in entries {
+                entry_paths.push(entry?.path());
+            }
+            entry_paths.sort();
+            let mut found = false;
+            for entry_path in entry_paths {
+                if (is_container_file_name(&entry_path)) {
+                    found = true;
+                    push_pack(&mut packs, HullPackFiles::new_container(&entry_path)?)?;
+                } else {
+                    match parse_pack_file_name(&entry_path) {
+                        None => {}
+                        Some(pack_id) => {
+                            found = true;
+                            push_pack(&mut packs, HullPackFiles::new_legacy(path, pack_id))?;
+                        }
+                    }
+                }
+            }
+            if (!found) {
+                return Err(io::Error::new(ErrorKind::NotFound, format!("{}: no hull packs (*.{} or <N>data_mesh|ind|bbx|hash) in directory", path.display(), PACK_FILE_EXT)));
+            }
+        } else if (path.is_file()) {
+            if (is_container_file_name(path)) {
+                push_pack(&mut packs, HullPackFiles::new_container(path)?)?;
+            } else {
+                match parse_pack_file_name(path) {
+                    None => {
+                        return Err(io::Error::new(ErrorKind::InvalidInput, format!("{}: not a hull pack file, expected *.{} or <N>data_mesh|ind|bbx|hash", path.display(), PACK_FILE_EXT)));
+                    }
+                    Some(pack_id) => {
+                        let dir = path.parent().unwrap_or(Path::new("."));
+                        push_pack(&mut packs, HullPackFiles::new_legacy(dir, pack_id))?;
+                    }
+                }
+            }
+        } else {
+            return Err(io::Error::new(ErrorKind::NotFound, format!("{}: no such file or directory", path.display())));
+        }
+    }
+    for pack in &packs {
+        pack.check_exists()?;
+    }
+    packs.sort_by_key(|p| p.pack_id);
+    Ok(packs)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_legacy_stream(path: &Path, kind: StreamKind) -> Result<Vec<u8>, LoadError> {
+    let file_error = |e: LoadError| e.in_file(path.display().to_string());
+    let packed: Vec<u8> = fs::read(path).map_err(|e| file_error(LoadError::from(e)))?;
+    let decoded: Vec<u8> = StreamCodec::sniff(packed.as_slice()).decode(kind, packed.as_slice(), MAX_STREAM_LEN).map_err(file_error)?;
+    if (decoded.len() % kind.item_size() != 0) {
+        return Err(file_error(LoadError::Length { stream: kind.name(), len: decoded.len(), item_size: kind.item_size() }));
+    }
+    Ok(decoded)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HullPackFiles {
+    pub fn read_streams(&self) -> Result<PackStreams, LoadError> {
+        match &self.layout {
+            PackLayout::Container(path) => {
+                let file_error = |e: LoadError| e.in_file(path.display().to_string());
+                let bytes: Vec<u8> = fs::read(path).map_err(|e| file_error(LoadError::from(e)))?;
+                let streams = read_pack_streams(bytes.as_slice()).map_err(file_error)?;
+                if (streams.pack_id != self.pack_id) {
+                    return Err(file_error(format_error(format!("pack id changed from {} to {} since it was found", self.pack_id, streams.pack_id))));
+                }
+                Ok(streams)
+            }
+            PackLayout::Legacy { mesh, ind, bbx, hash } => {
+                Ok(PackStreams {
+                    pack_id: self.pack_id,
+                    v: read_legacy_stream(mesh, StreamKind::Vertices)?,
+                    i: read_legacy_stream(ind, StreamKind::Indices)?,
+                    b: read_legacy_stream(bbx, StreamKind::Bbxs)?,
+                    t: read_legacy_stream(hash, StreamKind::Hashes)?,
+                    ..Default::default()
+                })
+            }
+        }
+    }
+}
+
+fn format_error(message: String) -> LoadError {
+    LoadError::Format { message: message }
+}
+
+fn read_u16(b: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([b[at], b[at + 1]])
+}
+
+fn read_u32(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+fn read_u64(b: &[u8], at: usize) -> u64 {
+    let mut v: [u8; 8] = [0; 8];
+    v.copy_from_slice(&b[at..at + 8]);
+    u64::from_le_bytes(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_pack(codec: StreamCodec) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let v: Vec<u8> = (0..40 * 7).map(|n| (n * 31 % 251) as u8).collect();
+        let i: Vec<u8> = [0i32, 1, 2, 2, 1, 6].iter().flat_map(|n| n.to_le_bytes()).collect();
+        let mut writer = PackWriter::new(7, codec);
+        writer.add_stream(StreamKind::Vertices, v.as_slice());
+        writer.add_stream(StreamKind::Indices, i.as_slice());
+        writer.set_meta(&PackMeta { name: Some("hull".to_string()), bbx: None, units: Some("mm".to_string()) }).unwrap();
+        (writer.to_bytes().unwrap(), v, i)
+    }
+
+    #[test]
+    fn write_read_round_trip() {
+        StreamCodec::ALL.iter().for_each(|codec| {
+            let (bytes, v, i) = sample_pack(*codec);
+            let pack = PackContainer::parse(bytes.as_slice()).unwrap();
+            assert_eq!(pack.header.version, PACK_FORMAT_VERSION);
+            assert_eq!(pack.header.pack_id, 7);
+            assert_eq!(pack.stream(StreamKind::Vertices).map(|s| (s.codec, s.count)), Some((*codec, 7)));
+            assert_eq!(pack.stream(StreamKind::Indices).map(|s| s.count), Some(6));
+            assert_eq!(pack.read_stream(StreamKind::Vertices).unwrap(), v);
+            assert_eq!(pack.read_stream(StreamKind::Indices).unwrap(), i);
+            assert_eq!(pack.meta().unwrap().and_then(|m| m.units), Some("mm".to_string()));
+            assert!(pack.part_info().unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let (mut bytes, _v, _i) = sample_pack(StreamCodec::Stored);
+        let offset = PackContainer::parse(bytes.as_slice()).unwrap().stream(StreamKind::Indices).unwrap().offset as usize;
+        bytes[offset] ^= 0xFF;
+        let pack = PackContainer::parse(bytes.as_slice()).unwrap();
+        assert!(matches!(pack.read_stream(StreamKind::Indices), Err(LoadError::Checksum { stream: "ind", .. })));
+        assert!(pack.read_stream(StreamKind::Vertices).is_ok());
+    }
+
+    #[test]
+    fn truncated_pack() {
+        let (bytes, _v, _i) = sample_pack(StreamCodec::Deflate);
+        //header only, the table of 3 streams is cut
+        assert!(parse_header(&bytes[0..HEADER_SIZE + STREAM_ENTRY_SIZE]).unwrap().is_none());
+        assert!(matches!(PackContainer::parse(&bytes[0..HEADER_SIZE + STREAM_ENTRY_SIZE]), Err(LoadError::Format { .. })));
+        assert!(matches!(PackContainer::parse(&bytes[0..HEADER_SIZE - 1]), Err(LoadError::Format { .. })));
+        //table is whole, the data of the last stream is cut
+        assert!(matches!(PackContainer::parse(&bytes[0..bytes.len() - 1]), Err(LoadError::Format { .. })));
+        assert!(matches!(PackContainer::parse(b"NOTAPACK and more bytes"), Err(LoadError::Format { .. })));
+    }
+
+    //a small stream that unpacks far past the header length stops at it
+    #[test]
+    fn decode_limit() {
+        let raw: Vec<u8> = vec![0; 1 << 20];
+        [StreamCodec::Deflate, StreamCodec::Zstd, StreamCodec::Mesh].iter().for_each(|codec| {
+            let packed = codec.encode(StreamKind::Vertices, raw.as_slice()).unwrap();
+            assert!(packed.len() < 4096);
+            assert_eq!(codec.decode(StreamKind::Vertices, packed.as_slice(), raw.len() as u64).unwrap(), raw);
+            assert!(matches!(codec.decode(StreamKind::Vertices, packed.as_slice(), 4096), Err(LoadError::Decompress { stream: "mesh", .. })));
+        });
+    }
+}
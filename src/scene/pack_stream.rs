@@ -0,0 +1,315 @@
+use miniz_oxide::inflate::stream::{inflate, InflateState};
+use miniz_oxide::{DataFormat, MZError, MZFlush, MZStatus};
+use ruzstd::decoding::FrameDecoder;
+
+use crate::scene::load_error::LoadError;
+use crate::scene::pack_format::{crc32_update, mesh_undelta, mesh_unfilter_plane, parse_header, PackHeader, StreamCodec, StreamEntry, StreamKind};
+
+//decoded bytes are handed to the sink in slices up to this size
+const DECODE_SLICE: usize = 1 << 20;
+//a zstd frame header takes up to 18 bytes, shorter input waits for more unless the stream is complete
+const ZSTD_MAX_FRAME_HEADER: usize = 18;
+
+//Receives decoded streams of a pack while it is read, checksums are known only at the end of a stream,
+//so everything passed here must be dropped if push or finish returns an error
+pub trait StreamSink {
+    fn on_header(&mut self, header: &PackHeader) -> Result<(), LoadError>;
+    fn on_data(&mut self, kind: StreamKind, data: &[u8]) -> Result<(), LoadError>;
+}
+
+enum Decoder {
+    Stored,
+    Deflate(Box<InflateState>),
+    //input not yet taken by the decoder, zstd decodes whole blocks only
+    Zstd(Box<FrameDecoder>, Vec<u8>),
+    Mesh(Box<FrameDecoder>, Vec<u8>, MeshPlanes),
+}
+
+//Byte planes of a mesh stream are unfiltered one by one as they complete,
+//only the raw stream and one plane are held, not the whole filtered stream
+struct MeshPlanes {
+    kind: StreamKind,
+    raw_len: usize,
+    //elements in a plane
+    n: usize,
+    plane: Vec<u8>,
+    raw: Vec<u8>,
+    //decoded bytes so far
+    pos: usize,
+}
+
+struct StreamState {
+    entry: StreamEntry,
+    decoder: Decoder,
+    crc: u32,
+    received: u64,
+    written: u64,
+}
+
+//Single file pack read from chunks as they arrive, without the whole file in memory
+pub struct PackStreamReader {
+    head: Vec<u8>,
+    header: Option<PackHeader>,
+    streams: Vec<StreamState>,
+    pos: u64,
+    out: Vec<u8>,
+}
+
+impl PackStreamReader {
+    pub fn new() -> Self {
+        Self {
+            head: vec![],
+            header: None,
+            streams: vec![],
+            pos: 0,
+            out: vec![],
+        }
+    }
+    pub fn header(&self) -> Option<&PackHeader> {
+        self.header.as_ref()
+    }
+    pub fn push(&mut self, chunk: &[u8], sink: &mut impl StreamSink) -> Result<(), LoadError> {
+        if (self.header.is_some()) {
+            return self.feed(chunk, sink);
+        }
+        //bytes are kept only until the stream table is read
+        self.head.extend_from_slice(chunk);
+        match parse_header(self.head.as_slice())? {
+            None => Ok(()),
+            Some(header) => {
+                sink.on_header(&header)?;
+                let mut streams: Vec<StreamState> = header.streams.iter().map(|entry| StreamState::new(entry.clone())).collect();
+                streams.sort_by_key(|s| s.entry.offset);
+                self.streams = streams;
+                self.header = Some(header);
+                self.out = vec![0; DECODE_SLICE];
+                let head = std::mem::take(&mut self.head);
+                self.feed(head.as_slice(), sink)
+            }
+        }
+    }
+    //all streams must be complete, the file may have more bytes after them
+    pub fn finish(&mut self) -> Result<(), LoadError> {
+        if (self.header.is_none()) {
+            return Err(LoadError::Format { message: format!("header is truncated, {} bytes", self.head.len()) });
+        }
+        match self.streams.iter().find(|s| s.received < s.entry.packed_len) {
+            None => Ok(()),
+            Some(s) => {
+                Err(LoadError::Format { message: format!("stream {} has {} of {} bytes, file is truncated", s.entry.kind.name(), s.received, s.entry.packed_len) })
+            }
+        }
+    }
+    //data starts at file position self.pos
+    fn feed(&mut self, data: &[u8], sink: &mut impl StreamSink) -> Result<(), LoadError> {
+        let start = self.pos;
+        let end = start + data.len() as u64;
+        for s in self.streams.iter_mut() {
+            let from = start.max(s.entry.offset + s.received);
+            let to = end.min(s.entry.offset + s.entry.packed_len);
+            if (from < to) {
+                s.push(&data[(from - start) as usize..(to - start) as usize], self.out.as_mut_slice(), sink)?;
+            }
+        }
+        self.pos = end;
+        Ok(())
+    }
+}
+
+impl StreamState {
+    fn new(entry: StreamEntry) -> Self {
+        let decoder = match entry.codec {
+            StreamCodec::Stored => Decoder::Stored,
+            StreamCodec::Deflate => Decoder::Deflate(InflateState::new_boxed(DataFormat::Raw)),
+            StreamCodec::Zstd => Decoder::Zstd(Box::new(FrameDecoder::new()), vec![]),
+            StreamCodec::Mesh => Decoder::Mesh(Box::new(FrameDecoder::new()), vec![], MeshPlanes::new(entry.kind, entry.raw_len as usize)),
+        };
+        Self {
+            entry: entry,
+            decoder: decoder,
+            crc: 0,
+            received: 0,
+            written: 0,
+        }
+    }
+
+    fn push(&mut self, data: &[u8], out: &mut [u8], sink: &mut impl StreamSink) -> Result<(), LoadError> {
+        self.crc = crc32_update(self.crc, data);
+        self.received = self.received + data.len() as u64;
+        let is_complete = self.received == self.entry.packed_len;
+        if (is_complete && self.crc != self.entry.checksum) {
+            return Err(LoadError::Checksum { stream: self.entry.kind.name(), expected: self.entry.checksum, actual: self.crc });
+        }
+        let kind = self.entry.kind;
+        let raw_len = self.entry.raw_len;
+        let written = &mut self.written;
+        //a stream never gets more than raw_len bytes, sinks size their buffers by it
+        let mut emit = |decoded: &[u8], sink: &mut dyn FnMut(&[u8]) -> Result<(), LoadError>| -> Result<(), LoadError> {
+            if (*written + decoded.len() as u64 > raw_len) {
+                return Err(LoadError::Decompress { stream: kind.name(), message: format!("unpacks to more than {} bytes", raw_len) });
+            }
+            *written = *written + decoded.len() as u64;
+            sink(decoded)
+        };
+        match &mut self.decoder {
+            Decoder::Stored => {
+                emit(data, &mut |d| sink.on_data(kind, d))?;
+            }
+            Decoder::Deflate(state) => {
+                let mut input = data;
+                loop {
+                    let res = inflate(state, input, out, MZFlush::None);
+                    input = &input[res.bytes_consumed..];
+                    if (res.bytes_written > 0) {
+                        emit(&out[..res.bytes_written], &mut |d| sink.on_data(kind, d))?;
+                    }
+                    match res.status {
+                        Ok(MZStatus::StreamEnd) => { break; }
+                        Ok(_) => {}
+                        //no progress possible, wait for more input
+                        Err(MZError::Buf) if input.is_empty() => { break; }
+                        Err(e) => {
+                            return Err(LoadError::Decompress { stream: kind.name(), message: format!("can't inflate at byte {} ({:?})", self.received, e) });
+                        }
+                    }
+                    if (input.is_empty() && res.bytes_written < out.len()) {
+                        break;
+                    }
+                }
+            }
+            Decoder::Zstd(decoder, pending) => {
+                pending.extend_from_slice(data);
+                decode_zstd(kind, decoder, pending, is_complete, out, &mut |d| emit(d, &mut |d| sink.on_data(kind, d)))?;
+            }
+            Decoder::Mesh(decoder, pending, planes) => {
+                pending.extend_from_slice(data);
+                decode_zstd(kind, decoder, pending, is_complete, out, &mut |d| emit(d, &mut |d| {
+                    planes.push(d);
+                    Ok(())
+                }))?;
+                if (is_complete && planes.pos == planes.raw_len) {
+                    let raw = planes.finish();
+                    raw.chunks(DECODE_SLICE).try_for_each(|d| sink.on_data(kind, d))?;
+                }
+            }
+        }
+        if (is_complete && self.written != raw_len) {
+            return Err(LoadError::Decompress { stream: kind.name(), message: format!("unpacked to {} bytes, expected {}", self.written, raw_len) });
+        }
+        Ok(())
+    }
+}
+
+impl MeshPlanes {
+    fn new(kind: StreamKind, raw_len: usize) -> Self {
+        let n = raw_len / kind.item_size();
+        Self {
+            kind: kind,
+            raw_len: raw_len,
+            n: n,
+            plane: Vec::with_capacity(n.min(DECODE_SLICE)),
+            raw: vec![],
+            pos: 0,
+        }
+    }
+    //bytes past raw_len never get here
+    fn push(&mut self, mut data: &[u8]) {
+        let item = self.kind.item_size();
+        while (!data.is_empty()) {
+            if (self.pos >= self.n * item) {
+                //tail bytes that don't make a whole element are stored as is
+                if (self.raw.is_empty()) {
+                    self.raw = vec![0; self.raw_len];
+                }
+                self.raw[self.pos..self.pos + data.len()].copy_from_slice(data);
+                self.pos = self.pos + data.len();
+                break;
+            }
+            let take = data.len().min(self.n - self.plane.len());
+            self.plane.extend_from_slice(&data[..take]);
+            self.pos = self.pos + take;
+            data = &data[take..];
+            if (self.plane.len() == self.n) {
+                //raw_len is not verified before the checksum, the buffer is sized once a plane is decoded
+                if (self.raw.is_empty()) {
+                    self.raw = vec![0; self.raw_len];
+                }
+                mesh_unfilter_plane(self.plane.as_slice(), self.pos / self.n - 1, item, self.raw.as_mut_slice());
+                self.plane.clear();
+            }
+        }
+    }
+    fn finish(&mut self) -> Vec<u8> {
+        let mut raw = std::mem::take(&mut self.raw);
+        raw.resize(self.raw_len, 0);
+        let planes_len = self.n * self.kind.item_size();
+        mesh_undelta(self.kind, &mut raw[0..planes_len]);
+        raw
+    }
+}
+
+fn decode_zstd(kind: StreamKind, decoder: &mut FrameDecoder, pending: &mut Vec<u8>, is_complete: bool, out: &mut [u8],
+               emit: &mut dyn FnMut(&[u8]) -> Result<(), LoadError>) -> Result<(), LoadError> {
+    if (!is_complete && pending.len() < ZSTD_MAX_FRAME_HEADER) {
+        return Ok(());
+    }
+    let zstd_error = |e: String| LoadError::Decompress { stream: kind.name(), message: format!("can't unpack zstd ({})", e) };
+    loop {
+        let (read, written) = decoder.decode_from_to(pending.as_slice(), out).map_err(|e| zstd_error(e.to_string()))?;
+        pending.drain(..read);
+        if (written > 0) {
+            emit(&out[..written])?;
+        }
+        if (read == 0 && written == 0) {
+            break;
+        }
+    }
+    if (is_complete && !decoder.is_finished()) {
+        return Err(zstd_error(format!("frame is not complete, {} bytes left", pending.len())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::pack_format::PackWriter;
+
+    #[derive(Default)]
+    struct CollectSink {
+        vertices: Vec<u8>,
+        indices: Vec<u8>,
+    }
+
+    impl StreamSink for CollectSink {
+        fn on_header(&mut self, _header: &PackHeader) -> Result<(), LoadError> {
+            Ok(())
+        }
+        fn on_data(&mut self, kind: StreamKind, data: &[u8]) -> Result<(), LoadError> {
+            match kind {
+                StreamKind::Vertices => { self.vertices.extend_from_slice(data); }
+                StreamKind::Indices => { self.indices.extend_from_slice(data); }
+                _ => {}
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn chunked_read() {
+        let v: Vec<u8> = (0..40 * 300).map(|n| (n * 31 % 251) as u8).collect();
+        let i: Vec<u8> = (0..900i32).map(|n| n / 3 + n % 3 * 7).flat_map(|n| n.to_le_bytes()).collect();
+        StreamCodec::ALL.iter().for_each(|codec| {
+            let mut writer = PackWriter::new(7, *codec);
+            writer.add_stream(StreamKind::Vertices, v.as_slice());
+            writer.add_stream(StreamKind::Indices, i.as_slice());
+            let bytes = writer.to_bytes().unwrap();
+            let mut reader = PackStreamReader::new();
+            let mut sink = CollectSink::default();
+            bytes.chunks(3).for_each(|chunk| reader.push(chunk, &mut sink).unwrap());
+            reader.finish().unwrap();
+            assert_eq!(sink.vertices, v, "{}", codec.name());
+            assert_eq!(sink.indices, i, "{}", codec.name());
+        });
+    }
+}
@@ -0,0 +1,267 @@
+use std::mem::size_of;
+use std::rc::Rc;
+use log::warn;
+use parking_lot::RwLock;
+use wgpu::{Buffer, BufferUsages, Device, Queue};
+
+use crate::scene::gpu_mem::GpuMem;
+use crate::scene::load_error::LoadError;
+use crate::scene::mesh_loader::{check_hull_lengths, check_indices, read_bbxs, read_hashes, read_hull_streams};
+use crate::scene::pack_format::{parse_part_info, unpack_id, PackHeader, PackMeta, PackStreams, PartInfo, StreamEntry, StreamKind, CABLE_PACK_ID, MAX_STREAM_LEN};
+use crate::scene::pack_stream::{PackStreamReader, StreamSink};
+use crate::shared::mesh_common::MeshVertex;
+
+//staging is written to the GPU when it has this many bytes
+const UPLOAD_SLICE: usize = 4 << 20;
+//byte offset of material_index in MeshVertex
+const MATERIAL_INDEX_OFFSET: usize = 32;
+
+//GPU buffer filled in slices through a small staging vector
+struct GpuStream {
+    buffer: Buffer,
+    staging: Vec<u8>,
+    offset: u64,
+}
+
+impl GpuStream {
+    fn new(device: &Device, label: String, size: u64, usage: BufferUsages) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label.as_str()),
+            size: size,
+            usage: usage | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer: buffer,
+            staging: Vec::with_capacity(UPLOAD_SLICE.min(size as usize) + size_of::<MeshVertex>()),
+            offset: 0,
+        }
+    }
+    //writes whole items only, the rest waits in staging, on_slice gets every written slice
+    fn write(&mut self, queue: &Queue, data: &[u8], item_size: usize, is_last: bool, on_slice: &mut dyn FnMut(&[u8])) {
+        self.staging.extend_from_slice(data);
+        if (self.staging.len() < UPLOAD_SLICE && !is_last) {
+            return;
+        }
+        let len = self.staging.len() - self.staging.len() % item_size;
+        if (len > 0) {
+            queue.write_buffer(&self.buffer, self.offset, &self.staging[..len]);
+            on_slice(&self.staging[..len]);
+            self.offset = self.offset + len as u64;
+            self.staging.drain(..len);
+        }
+    }
+}
+
+struct UploadSink {
+    device: Rc<RwLock<Device>>,
+    queue: Rc<RwLock<Queue>>,
+    pack_id: u32,
+    is_compact: bool,
+    //full layout only, vertices and indices go to the GPU
+    v_stream: Option<GpuStream>,
+    i_stream: Option<GpuStream>,
+    v_len: usize,
+    i_len: usize,
+    //vertex count from the header, uploaded indices are checked against it
+    vertices: usize,
+    metadata: Vec<i32>,
+    //native picking reads vertices on CPU, wasm asks the front end
+    v: Vec<MeshVertex>,
+    meta: Vec<u8>,
+    info: Vec<u8>,
+    //the rest, and all streams of a compact pack
+    streams: PackStreams,
+}
+
+//Streamed load of one pack, chunks are decoded as they arrive, so the packed file and the unpacked
+//vertices are never in memory as a whole. The loaded pack is replaced only by finish, after all checks
+pub struct PackUpload {
+    reader: PackStreamReader,
+    sink: UploadSink,
+    error: Option<LoadError>,
+}
+
+impl PackUpload {
+    pub fn new(device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>) -> Self {
+        Self {
+            reader: PackStreamReader::new(),
+            sink: UploadSink {
+                device: device,
+                queue: queue,
+                pack_id: 0,
+                is_compact: false,
+                v_stream: None,
+                i_stream: None,
+                v_len: 0,
+                i_len: 0,
+                vertices: 0,
+                metadata: vec![],
+                v: vec![],
+                meta: vec![],
+                info: vec![],
+                streams: PackStreams::default(),
+            },
+            error: None,
+        }
+    }
+    //pack id from the header, None until it is read
+    pub fn pack_id(&self) -> Option<u32> {
+        self.reader.header().map(|h| h.pack_id)
+    }
+    //after the first error the rest of the chunks are skipped, finish returns it
+    pub fn push(&mut self, chunk: &[u8]) {
+        if (self.error.is_some()) {
+            return;
+        }
+        match self.reader.push(chunk, &mut self.sink) {
+            Ok(()) => {}
+            Err(e) => {
+                warn!("CANT STREAM HULL PACK {}", e);
+                self.sink.v_stream = None;
+                self.sink.i_stream = None;
+                self.error = Some(e);
+            }
+        }
+    }
+    //part info is None if the pack has no info stream
+    pub fn finish(mut self) -> Result<(GpuMem, Option<Vec<PartInfo>>), LoadError> {
+        match self.error {
+            None => {}
+            Some(e) => { return Err(e); }
+        }
+        self.reader.finish()?;
+        let mut sink = self.sink;
+        if (!sink.meta.is_empty()) {
+            let meta: PackMeta = serde_json::from_slice(sink.meta.as_slice()).map_err(|e| LoadError::Format { message: format!("stream meta: {}", e) })?;
+            sink.streams.meta = Some(meta);
+        }
+        let info = if (sink.info.is_empty()) { None } else { Some(parse_part_info(sink.info.as_slice())?) };
+        let mut mem = GpuMem::new(sink.device.clone(), sink.pack_id);
+        if (sink.is_compact) {
+            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_streams(&sink.streams)?;
+            mem.set_data(hm, i, meta_data, out_bbx, hull_mesh, bbxs);
+            return Ok((mem, info));
+        }
+        check_hull_lengths(sink.v_len, sink.i_len, sink.streams.b.as_slice(), sink.streams.t.as_slice())?;
+        match (sink.v_stream, sink.i_stream) {
+            (Some(v_stream), Some(i_stream)) => {
+                let (out_bbx, bbxs) = read_bbxs(sink.streams.b.as_slice());
+                let hull_mesh = read_hashes(sink.streams.t.as_slice());
+                mem.set_buffers(v_stream.buffer, i_stream.buffer, sink.v, sink.metadata, out_bbx, hull_mesh, bbxs);
+                Ok((mem, info))
+            }
+            _ => Err(LoadError::Format { message: "stream mesh or ind is missing".to_string() })
+        }
+    }
+}
+
+impl StreamSink for UploadSink {
+    fn on_header(&mut self, header: &PackHeader) -> Result<(), LoadError> {
+        if (header.pack_id >= CABLE_PACK_ID) {
+            return Err(LoadError::PackId { pack_id: header.pack_id });
+        }
+        self.pack_id = header.pack_id;
+        self.streams.pack_id = header.pack_id;
+        self.is_compact = header.streams.iter().any(|s| s.kind == StreamKind::CompactVertices);
+        let required = if (self.is_compact) {
+            vec![StreamKind::CompactVertices, StreamKind::Parts, StreamKind::Quantization, StreamKind::Indices, StreamKind::Bbxs, StreamKind::Hashes]
+        } else {
+            vec![StreamKind::Vertices, StreamKind::Indices, StreamKind::Bbxs, StreamKind::Hashes]
+        };
+        //the header is not verified yet, sizes are checked before anything is allocated by them
+        let max_len = MAX_STREAM_LEN.min(self.device.read().limits().max_buffer_size);
+        let mut entries: Vec<(StreamKind, &StreamEntry)> = vec![];
+        for kind in required {
+            match header.streams.iter().find(|s| s.kind == kind) {
+                None => {
+                    return Err(LoadError::Format { message: format!("stream {} is missing", kind.name()) });
+                }
+                Some(entry) => {
+                    if (entry.count.checked_mul(kind.item_size() as u64) != Some(entry.raw_len)) {
+                        return Err(LoadError::Format { message: format!("stream {} has {} bytes for {} elements of {} bytes", kind.name(), entry.raw_len, entry.count, kind.item_size()) });
+                    }
+                    if (entry.raw_len > max_len) {
+                        return Err(LoadError::Format { message: format!("stream {} has {} bytes, more than {} a buffer can take", kind.name(), entry.raw_len, max_len) });
+                    }
+                    entries.push((kind, entry));
+                }
+            }
+        }
+        for (kind, entry) in entries {
+            let size = entry.raw_len;
+            match (self.is_compact, kind) {
+                (false, StreamKind::Vertices) => {
+                    self.v_stream = Some(GpuStream::new(&self.device.read(), format!("Vertex Mesh Buffer {}", self.pack_id), size, BufferUsages::VERTEX));
+                    self.vertices = entry.count as usize;
+                    self.metadata = Vec::with_capacity(entry.count as usize);
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        self.v = Vec::with_capacity(entry.count as usize);
+                    }
+                }
+                (false, StreamKind::Indices) => {
+                    self.i_stream = Some(GpuStream::new(&self.device.read(), format!("Index Mesh Buffer {}", self.pack_id), size, BufferUsages::INDEX));
+                }
+                _ => { self.stream_mut(kind).reserve_exact(size as usize); }
+            }
+        }
+        Ok(())
+    }
+
+    fn on_data(&mut self, kind: StreamKind, data: &[u8]) -> Result<(), LoadError> {
+        let queue = self.queue.clone();
+        let queue = queue.read();
+        match (&mut self.v_stream, &mut self.i_stream, kind) {
+            (Some(v_stream), _, StreamKind::Vertices) => {
+                self.v_len = self.v_len + data.len();
+                let is_last = self.v_len as u64 == v_stream.buffer.size();
+                let metadata = &mut self.metadata;
+                #[cfg(not(target_arch = "wasm32"))]
+                let v = &mut self.v;
+                v_stream.write(&queue, data, size_of::<MeshVertex>(), is_last, &mut |slice| {
+                    slice.chunks_exact(size_of::<MeshVertex>()).for_each(|m| {
+                        let at = MATERIAL_INDEX_OFFSET;
+                        let material_index = u32::from_le_bytes([m[at], m[at + 1], m[at + 2], m[at + 3]]);
+                        metadata.push(unpack_id(material_index) as i32);
+                    });
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        v.extend(bytemuck::pod_collect_to_vec::<u8, MeshVertex>(slice));
+                    }
+                });
+            }
+            (_, Some(i_stream), StreamKind::Indices) => {
+                self.i_len = self.i_len + data.len();
+                let is_last = self.i_len as u64 == i_stream.buffer.size();
+                let vertices = self.vertices;
+                let mut checked: Result<(), LoadError> = Ok(());
+                i_stream.write(&queue, data, size_of::<i32>(), is_last, &mut |slice| {
+                    if (checked.is_ok()) {
+                        checked = check_indices(slice, vertices);
+                    }
+                });
+                checked?;
+            }
+            (_, _, StreamKind::Meta) => { self.meta.extend_from_slice(data); }
+            _ => { self.stream_mut(kind).extend_from_slice(data); }
+        }
+        Ok(())
+    }
+}
+
+impl UploadSink {
+    fn stream_mut(&mut self, kind: StreamKind) -> &mut Vec<u8> {
+        match kind {
+            StreamKind::Vertices => &mut self.streams.v,
+            StreamKind::Indices => &mut self.streams.i,
+            StreamKind::Bbxs => &mut self.streams.b,
+            StreamKind::Hashes => &mut self.streams.t,
+            StreamKind::Meta => &mut self.meta,
+            StreamKind::CompactVertices => &mut self.streams.c,
+            StreamKind::Parts => &mut self.streams.p,
+            StreamKind::Quantization => &mut self.streams.q,
+            StreamKind::PartInfo => &mut self.info,
+        }
+    }
+}
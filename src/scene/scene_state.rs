@@ -4,56 +4,77 @@ use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use cgmath::{InnerSpace, Point3};
 
-use itertools::Itertools;
 use log::{warn};
 use parking_lot::RwLock;
 
 
 use truck_base::bounding_box::{BoundingBox};
-use truck_polymesh::Attributes;
 
-use wgpu::{Buffer, Device};
+use wgpu::{Buffer, Device, Queue};
 use crate::device::message_controller::{ActionType};
-use crate::scene::{mesh_loader, RawMesh};
-use crate::shared::mesh_common::MeshVertex;
-use wgpu::util::DeviceExt;
+use crate::scene::mesh_loader;
+use crate::scene::assembly::{AssemblyAction, AssemblyNode, AssemblyTree};
+use crate::scene::cable_network::{CableNetwork, CableTrace, CABLE_PACK_ID};
+use crate::shared::mesh_common::HullVertices;
 use crate::gui::camera_base::{CameraBase, SHIP_FORWARD};
+use crate::gui::clipping::Clipping;
+use crate::gui::section_caps::SectionCaps;
+use crate::gui::outline::Outline;
+use crate::gui::ssao::Ssao;
+use crate::gui::sun::Sun;
+use crate::gui::light_temperature::LightTemperature;
 use crate::gui::slicer::Slicer;
-use crate::remote::hull_state;
-use crate::scene::gpu_mem::{GpuMem, unpack_id, unpack_packid};
-use crate::scene::mesh_loader::read_hull_unpacked_new_format;
-use crate::shared::materials_lib::{HIDDEN_HULL_MAT, Material, SELECTION_HULL_MAT};
+use crate::remote::common_state::PART_INFO;
+use crate::scene::gpu_mem::GpuMem;
+use crate::scene::load_error::LoadError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scene::pack_format::{HullPackFiles, PackLayout};
+use crate::scene::pack_format::{PackStreams, PartInfo};
+use crate::scene::pack_upload::PackUpload;
+use crate::shared::materials_lib::{HIDDEN_HULL_MAT, SELECTION_HULL_MAT};
 use crate::shared::Triangle;
 
 #[cfg(target_arch = "wasm32")]
 use crate::remote::hull_state::{get_bbx_array, get_index_array, get_types_array, get_vertex_array};
 #[cfg(target_arch = "wasm32")]
-use crate::remote::hull_state::get_mesh_vertex_by_id;
+use crate::scene::mesh_loader::read_hull_unpacked_new_format;
+
+#[cfg(not(target_arch = "wasm32"))]
+const PACK_READ_CHUNK: usize = 4 << 20;
 
 pub struct SceneState {
     device: Rc<RwLock<Device>>,
-    pub gpu_mems: Vec<GpuMem>,
+    queue: Rc<RwLock<Queue>>,
+    pub gpu_mems: HashMap<u32, GpuMem>,
     pub tot_bbx: BoundingBox<Point3<f64>>,
     pub camera: CameraBase,
     pub slicer: Slicer,
+    //planes and section box on top of the slicer, kept when packs are loaded or unloaded
+    pub clipping: Clipping,
+    pub section_caps: SectionCaps,
+    pub outline: Outline,
+    pub ssao: Ssao,
+    pub sun: Sun,
+    pub light_temperature: LightTemperature,
     selected_hull_ids: HashSet<i32>,
     hidden_hull_ids: HashSet<i32>,
+    //material set by assembly colour, shown when the part is neither hidden nor selected
+    colored_hull_ids: HashMap<i32, i32>,
+    pub assembly: AssemblyTree,
+    //cable lit by the highlight pipeline, see highlight_cable in MessageController
+    pub cable_trace: Option<CableTrace>,
+    pub is_cable_trace_dirty: bool,
     pub is_snap_dirty: bool,
     pub snap_vertex_buffer: Buffer,
-
+    //pack being streamed in chunks, see begin_pack_upload
+    pack_upload: Option<PackUpload>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub hull_pack_files: Vec<HullPackFiles>,
 }
 
 impl SceneState {
-    pub fn new(device: Rc<RwLock<Device>>) -> Self {
-        let mut gpu_mems: Vec<GpuMem> = vec![];
-        gpu_mems.push(GpuMem::new(device.clone(), 0));
-        gpu_mems.push(GpuMem::new(device.clone(), 1));
-        gpu_mems.push(GpuMem::new(device.clone(), 2));
-        gpu_mems.push(GpuMem::new(device.clone(), 3));
-        gpu_mems.push(GpuMem::new(device.clone(), 4));
-        gpu_mems.push(GpuMem::new(device.clone(), 5));
-        gpu_mems.push(GpuMem::new(device.clone(), 6));
-        gpu_mems.push(GpuMem::new(device.clone(), 7));
+    pub fn new(device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>) -> Self {
+        let gpu_mems: HashMap<u32, GpuMem> = HashMap::new();
 
         let snap_vertex_buffer: Buffer = device.clone().read().create_buffer(&wgpu::BufferDescriptor {
             label: Some("Snap Vertex"),
@@ -64,304 +85,271 @@ impl SceneState {
 
         Self {
             device: device,
+            queue: queue,
             gpu_mems: gpu_mems,
             tot_bbx: Default::default(),
             camera: CameraBase::default(),
             slicer: Slicer::default(),
+            clipping: Clipping::new(),
+            section_caps: SectionCaps::new(),
+            outline: Outline::new(),
+            ssao: Ssao::new(),
+            sun: Sun::new(),
+            light_temperature: LightTemperature::new(),
             selected_hull_ids: HashSet::new(),
             hidden_hull_ids: HashSet::new(),
+            colored_hull_ids: HashMap::new(),
+            assembly: AssemblyTree::default(),
+            cable_trace: None,
+            is_cable_trace_dirty: false,
             is_snap_dirty: false,
             snap_vertex_buffer: snap_vertex_buffer,
+            pack_upload: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            hull_pack_files: vec![],
         }
     }
     #[cfg(target_arch = "wasm32")]
-    pub fn set_hull_mesh0(&mut self) {
-        {
-            let vu = get_vertex_array(0);
-            let iu = get_index_array(0);
-            let bu = get_bbx_array(0);
-            let tu = get_types_array(0);
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_unpacked_new_format(vu.to_vec(), iu.to_vec(), bu.to_vec(), tu.to_vec());
-            let package_id: u32 = 0;
-            //warn!("LOADED {} {:?}",package_id, out_bbx.diagonal().magnitude());
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
-        }
-        //warn!("UNPACKED 0");
-    }
-    #[cfg(target_arch = "wasm32")]
-    pub fn set_hull_mesh1(&mut self) {
-        {
-            let vu = get_vertex_array(1);
-            let iu = get_index_array(1);
-            let bu = get_bbx_array(1);
-            let tu = get_types_array(1);
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_unpacked_new_format(vu.to_vec(), iu.to_vec(), bu.to_vec(), tu.to_vec());
-            //warn!("LOADED");
-            let package_id: u32 = 1;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
-        }
-        //warn!("UNPACKED 1");
+    pub fn set_hull_mesh(&mut self, package_id: u32) -> Result<(), LoadError> {
+        let vu = get_vertex_array(package_id as i32);
+        let iu = get_index_array(package_id as i32);
+        let bu = get_bbx_array(package_id as i32);
+        let tu = get_types_array(package_id as i32);
+        let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_unpacked_new_format(vu.to_vec(), iu.to_vec(), bu.to_vec(), tu.to_vec())?;
+        self.set_hull_data(package_id, hm, i, meta_data, out_bbx, hull_mesh, bbxs)
     }
 
-    #[cfg(target_arch = "wasm32")]
-    pub fn set_hull_mesh2(&mut self) {
-        {
-            let vu = get_vertex_array(2);
-            let iu = get_index_array(2);
-            let bu = get_bbx_array(2);
-            let tu = get_types_array(2);
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_unpacked_new_format(vu.to_vec(), iu.to_vec(), bu.to_vec(), tu.to_vec());
-            //warn!("LOADED");
-            let package_id: u32 = 2;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_hull_mesh(&mut self, package_id: u32) -> Result<(), LoadError> {
+        match self.hull_pack_files.iter().find(|p| p.pack_id == package_id) {
+            None => {
+                Err(LoadError::Format { message: format!("hull pack {} is not given in command line", package_id) })
+            }
+            Some(pack) => {
+                let pack = pack.clone();
+                let loaded = match &pack.layout {
+                    PackLayout::Container(path) => self.stream_pack_file(path, package_id),
+                    PackLayout::Legacy { .. } => {
+                        mesh_loader::read_hull_packed_from_files(&pack).and_then(|(hm, i, meta_data, out_bbx, hull_mesh, bbxs)| {
+                            self.set_hull_data(package_id, hm, i, meta_data, out_bbx, hull_mesh, bbxs)?;
+                            Ok(None)
+                        })
+                    }
+                };
+                match loaded? {
+                    Some(info) => { self.set_part_info(package_id, Some(info)); }
+                    //info stream wins over the sidecar file, a broken sidecar does not stop the pack
+                    None => {
+                        match pack.read_part_info() {
+                            Ok(info) => { self.set_part_info(package_id, info); }
+                            Err(e) => { warn!("CANT LOAD PART INFO {}: {}", package_id, e); }
+                        }
+                    }
+                }
+                Ok(())
+            }
         }
-        //warn!("UNPACKED 2");
     }
-    #[cfg(target_arch = "wasm32")]
-    pub fn set_hull_mesh3(&mut self) {
-        {
-            let vu = get_vertex_array(3);
-            let iu = get_index_array(3);
-            let bu = get_bbx_array(3);
-            let tu = get_types_array(3);
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_unpacked_new_format(vu.to_vec(), iu.to_vec(), bu.to_vec(), tu.to_vec());
-            //warn!("LOADED");
-            let package_id: u32 = 3;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
+
+    pub fn set_hull_mesh_remote(&mut self, load_level: i32, streams: &PackStreams) -> Result<(), LoadError> {
+        warn!("TRY LOAD HULL FROm REMOTE");
+        if (load_level < 0) {
+            return Err(LoadError::PackId { pack_id: load_level as u32 });
         }
-        //warn!("UNPACKED 3");
+        self.replace_pack(load_level as u32, streams)
     }
 
-    #[cfg(target_arch = "wasm32")]
-    pub fn set_hull_mesh4(&mut self) {
-        {
-            let vu = get_vertex_array(4);
-            let iu = get_index_array(4);
-            let bu = get_bbx_array(4);
-            let tu = get_types_array(4);
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_unpacked_new_format(vu.to_vec(), iu.to_vec(), bu.to_vec(), tu.to_vec());
-            //warn!("LOADED");
-            let package_id: u32 = 4;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
+    //streams are parsed first, so a broken pack leaves the loaded one untouched
+    pub fn replace_pack(&mut self, package_id: u32, streams: &PackStreams) -> Result<(), LoadError> {
+        if (package_id >= CABLE_PACK_ID) {
+            return Err(LoadError::PackId { pack_id: package_id });
         }
-        //warn!("UNPACKED 4");
+        let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) =
+            mesh_loader::read_hull_streams(streams)?;
+        self.set_hull_data(package_id, hm, i, meta_data, out_bbx, hull_mesh, bbxs)?;
+        self.set_part_info(package_id, streams.info.clone());
+        Ok(())
     }
 
-    #[cfg(target_arch = "wasm32")]
-    pub fn set_hull_mesh5(&mut self) {
-        {
-            let vu = get_vertex_array(5);
-            let iu = get_index_array(5);
-            let bu = get_bbx_array(5);
-            let tu = get_types_array(5);
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_unpacked_new_format(vu.to_vec(), iu.to_vec(), bu.to_vec(), tu.to_vec());
-            //warn!("LOADED");
-            let package_id: u32 = 5;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
+    //releases GPU buffers of the pack, returns false if the pack is not loaded
+    pub fn unload_pack(&mut self, package_id: u32) -> bool {
+        if (self.remove_pack(package_id)) {
+            self.update_tot_bbx();
+            true
+        } else {
+            false
         }
-        //warn!("UNPACKED 5");
     }
-    #[cfg(target_arch = "wasm32")]
-    pub fn set_hull_mesh6(&mut self) {
-        {
-            let vu = get_vertex_array(6);
-            let iu = get_index_array(6);
-            let bu = get_bbx_array(6);
-            let tu = get_types_array(6);
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_unpacked_new_format(vu.to_vec(), iu.to_vec(), bu.to_vec(), tu.to_vec());
-            //warn!("LOADED");
-            let package_id: u32 = 6;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
+
+    fn remove_pack(&mut self, package_id: u32) -> bool {
+        match self.gpu_mems.remove(&package_id) {
+            None => { false }
+            Some(mem) => {
+                match PART_INFO.lock() {
+                    Ok(mut m) => { m.remove_pack(package_id); }
+                    Err(_e) => { warn!("CANT LOCK PART_INFO MEM") }
+                }
+                mem.mesh_hash.keys().for_each(|id| {
+                    self.selected_hull_ids.remove(id);
+                    self.hidden_hull_ids.remove(id);
+                    self.colored_hull_ids.remove(id);
+                });
+                self.refresh_hull_remote_selected();
+                self.refresh_hull_remote_hidden();
+                if (package_id == CABLE_PACK_ID) {
+                    self.set_cable_trace(None);
+                }
+                self.is_snap_dirty = true;
+                true
+            }
         }
-        //warn!("UNPACKED 6");
     }
-    #[cfg(target_arch = "wasm32")]
-    pub fn set_hull_mesh7(&mut self) {
-        {
-            let vu = get_vertex_array(7);
-            let iu = get_index_array(7);
-            let bu = get_bbx_array(7);
-            let tu = get_types_array(7);
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = read_hull_unpacked_new_format(vu.to_vec(), iu.to_vec(), bu.to_vec(), tu.to_vec());
-           // warn!("LOADED");
-            let package_id: u32 = 7;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
+
+    //tot_bbx only grows on load, so it is rebuilt from the packs that are left
+    fn update_tot_bbx(&mut self) {
+        let mut tot_bbx: BoundingBox<Point3<f64>> = Default::default();
+        self.gpu_mems.values().for_each(|mem| {
+            tot_bbx += mem.tot_loc_bbx.clone();
+        });
+        self.tot_bbx = tot_bbx;
+        if (self.gpu_mems.is_empty()) {
+            self.slicer = Slicer::default();
+        } else {
             self.slicer.set_by_bbx(&self.tot_bbx);
         }
-       // warn!("UNPACKED 7");
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_hull_mesh0(&mut self) {
-        {
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = mesh_loader::read_hull_packed_new_format0();
-            let package_id: u32 = 0;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
+    //pack id is packed into vertex material_index and into selection buffer, so it must stay below ID_MEM_OFFSET,
+    //and the last id is the cable network
+    fn set_hull_data(&mut self, package_id: u32, hm: HullVertices, i: Vec<i32>, meta_data: Vec<i32>, out_bbx: BoundingBox<Point3<f64>>, hull_mesh: HashMap<i32, (i32, i32, i32)>, bbxs: Vec<BoundingBox<Point3<f64>>>) -> Result<(), LoadError> {
+        if (package_id >= CABLE_PACK_ID) {
+            return Err(LoadError::PackId { pack_id: package_id });
         }
-        //warn!("UNPACKED 0");
+        let mut mem = GpuMem::new(self.device.clone(), package_id);
+        mem.set_data(hm, i, meta_data, out_bbx, hull_mesh, bbxs);
+        self.insert_pack(mem);
+        Ok(())
     }
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_hull_mesh1(&mut self) {
-        {
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = mesh_loader::read_hull_packed_new_format1();
-            let package_id: u32 = 1;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
+
+    fn insert_pack(&mut self, mem: GpuMem) {
+        let out_bbx = mem.tot_loc_bbx.clone();
+        let is_replaced = self.remove_pack(mem.id);
+        self.gpu_mems.insert(mem.id, mem);
+        if (is_replaced) {
+            self.update_tot_bbx();
+        } else {
             self.tot_bbx += out_bbx;
             self.slicer.set_by_bbx(&self.tot_bbx);
         }
-        //warn!("UNPACKED 1");
     }
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_hull_mesh2(&mut self) {
-        {
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = mesh_loader::read_hull_packed_new_format2();
-            let package_id: u32 = 2;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
-        }
-        //warn!("UNPACKED 2");
+
+    //cable network is built into its own pack, vertices stay on CPU for picking on web too
+    pub fn set_cable_network(&mut self, network: &CableNetwork) {
+        let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = network.to_hull_data(CABLE_PACK_ID);
+        warn!("CABLE NETWORK: {} NODES {} LINKS {} PARTS", network.nodes.len(), network.links.len(), hull_mesh.len());
+        let mut mem = GpuMem::new(self.device.clone(), CABLE_PACK_ID);
+        mem.is_local = true;
+        mem.set_data(hm, i, meta_data, out_bbx, hull_mesh, bbxs);
+        self.insert_pack(mem);
     }
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_hull_mesh3(&mut self) {
-        {
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) = mesh_loader::read_hull_packed_new_format3();
-            let package_id: u32 = 3;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
-        }
-        //warn!("UNPACKED 3");
+
+    pub fn set_cable_trace(&mut self, trace: Option<CableTrace>) {
+        self.cable_trace = trace;
+        self.is_cable_trace_dirty = true;
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_hull_mesh4(&mut self) {
-        {
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) =
-                mesh_loader::read_hull_packed_new_format4();
-            let package_id: u32 = 4;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
+    //None keeps info set for the pack from outside, e.g. by set_part_info on web
+    fn set_part_info(&mut self, package_id: u32, info: Option<Vec<PartInfo>>) {
+        match info {
+            None => {}
+            Some(info) => {
+                match PART_INFO.lock() {
+                    Ok(mut m) => { m.set_pack(package_id, info); }
+                    Err(_e) => { warn!("CANT LOCK PART_INFO MEM") }
+                }
+            }
         }
-       // warn!("UNPACKED 4");
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_hull_mesh5(&mut self) {
-        {
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) =
-                mesh_loader::read_hull_packed_new_format5();
-            let package_id: u32 = 5;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
+    pub fn part_info(&self, package_id: u32, id: i32) -> Option<PartInfo> {
+        match PART_INFO.lock() {
+            Ok(m) => m.get(package_id, id).cloned(),
+            Err(_e) => {
+                warn!("CANT LOCK PART_INFO MEM");
+                None
+            }
         }
-        //warn!("UNPACKED 5");
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_hull_mesh6(&mut self) {
-        {
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) =
-                mesh_loader::read_hull_packed_new_format6();
-            let package_id: u32 = 6;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
-        }
-        //warn!("UNPACKED 6");
+    //info of the part under a picked vertex, see screen_oid
+    pub fn part_info_by_vertex_index(&self, vertex_index: i32, package_id: u32) -> Option<PartInfo> {
+        let id = self.gpu_mems.get(&package_id).and_then(|mem| mem.get_id_by_vertex_index(vertex_index as usize))?;
+        self.part_info(package_id, id)
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_hull_mesh7(&mut self) {
-        {
-            let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) =
-                mesh_loader::read_hull_packed_new_format7();
-            let package_id: u32 = 7;
-            self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx.clone(), hull_mesh, bbxs);
-            self.tot_bbx += out_bbx;
-            self.slicer.set_by_bbx(&self.tot_bbx);
+    //Streamed load: chunks of a single file pack are pushed as they arrive, vertices go to the GPU in slices.
+    //A new begin drops the unfinished upload, the loaded pack with the same id stays until finish succeeds
+    pub fn begin_pack_upload(&mut self) {
+        self.pack_upload = Some(PackUpload::new(self.device.clone(), self.queue.clone()));
+    }
+    pub fn push_pack_upload(&mut self, chunk: &[u8]) {
+        match &mut self.pack_upload {
+            None => { warn!("HULL PACK UPLOAD IS NOT STARTED"); }
+            Some(upload) => { upload.push(chunk); }
         }
-        //warn!("UNPACKED 7");
     }
-
-
-    pub fn set_hull_mesh_remote(&mut self, load_level: i32, decoded_v: &[u8], decoded_i: &[u8], decoded_b: &[u8], decoded_t: &[u8]) {
-        warn!("TRY LOAD HULL FROm REMOTE");
-        let (hm, i, meta_data, out_bbx, hull_mesh, bbxs) =
-            mesh_loader::read_hull_unpacked_new_format_remote(load_level, decoded_v, decoded_i, decoded_b, decoded_t);
-        let package_id: u32 = load_level as u32;
-        self.tot_bbx += &out_bbx;
-        self.gpu_mems[package_id as usize].set_data(hm, i, meta_data, out_bbx, hull_mesh, bbxs);
-        self.slicer.set_by_bbx(&self.tot_bbx);
+    pub fn finish_pack_upload(&mut self) -> Result<u32, LoadError> {
+        let upload = self.pack_upload.take().ok_or(LoadError::Format { message: "pack upload is not started".to_string() })?;
+        let (mem, info) = upload.finish()?;
+        let pack_id = mem.id;
+        self.insert_pack(mem);
+        self.set_part_info(pack_id, info);
+        Ok(pack_id)
     }
 
-    #[cfg(target_arch = "wasm32")]
-    pub fn screen_oid(&mut self, action: ActionType, id: i32, pack_id: u32) -> bool {
-        let mut is_scene_modified = false;
-        if (id != 0) {
-            let bin=get_mesh_vertex_by_id( pack_id as i32,id ).to_vec();
-            let meshes: &[MeshVertex] =bytemuck::cast_slice(bin.as_slice());
-            if(meshes.len()>0){
-                let mesh=meshes[0];
-                match action {
-                    ActionType::Select => {
-                        self.select_by_id(mesh.id, pack_id);
-                        self.refresh_hull_remote_selected();
-                        is_scene_modified
-                    }
-                    ActionType::Hide => {
-                        self.hide_by_id(mesh.id, pack_id);
-                        self.refresh_hull_remote_hidden();
-                        is_scene_modified = true;
-                        is_scene_modified
-                    }
-                    ActionType::Evaluate => {
-                        is_scene_modified
-                    }
-                }
-            }else{
-                is_scene_modified
+    //container packs are read in chunks, so the packed file is never in memory as a whole
+    #[cfg(not(target_arch = "wasm32"))]
+    fn stream_pack_file(&mut self, path: &std::path::Path, package_id: u32) -> Result<Option<Vec<PartInfo>>, LoadError> {
+        let file_error = |e: LoadError| e.in_file(path.display().to_string());
+        let mut file = std::fs::File::open(path).map_err(|e| file_error(LoadError::from(e)))?;
+        let mut upload = PackUpload::new(self.device.clone(), self.queue.clone());
+        let mut chunk: Vec<u8> = vec![0; PACK_READ_CHUNK];
+        loop {
+            let n = std::io::Read::read(&mut file, chunk.as_mut_slice()).map_err(|e| file_error(LoadError::from(e)))?;
+            if (n == 0) {
+                break;
             }
-        } else {
-            is_scene_modified
+            upload.push(&chunk[..n]);
+        }
+        if (upload.pack_id().map_or(false, |id| id != package_id)) {
+            return Err(file_error(LoadError::Format { message: format!("pack id changed from {} to {} since it was found", package_id, upload.pack_id().unwrap_or(0)) }));
         }
+        let (mem, info) = upload.finish().map_err(file_error)?;
+        self.insert_pack(mem);
+        Ok(info)
     }
+
     #[cfg(not(target_arch = "wasm32"))]
+    pub fn hull_pack_id_at(&self, index: usize) -> Option<u32> {
+        self.hull_pack_files.get(index).map(|p| p.pack_id)
+    }
+
     pub fn screen_oid(&mut self, action: ActionType, id: i32, pack_id: u32) -> bool {
         let mut is_scene_modified = false;
         if id != 0 {
-            match self.gpu_mems[pack_id as usize].v.get(id as usize) {
+            match self.gpu_mems.get(&pack_id).and_then(|mem| mem.get_id_by_vertex_index(id as usize)) {
                 None => {
                     is_scene_modified
                 }
-                Some(mesh) => {
+                Some(oid) => {
                     match action {
                         ActionType::Select => {
-                            self.select_by_id(mesh.id, pack_id);
+                            self.select_by_id(oid, pack_id);
                             self.refresh_hull_remote_selected();
                             is_scene_modified
                         }
                         ActionType::Hide => {
-                            self.hide_by_id(mesh.id, pack_id);
+                            self.hide_by_id(oid, pack_id);
                             self.refresh_hull_remote_hidden();
                             is_scene_modified = true;
                             is_scene_modified
@@ -380,77 +368,193 @@ impl SceneState {
         if index < 2 {
             None
         } else {
-            self.gpu_mems[pack_id].get_triangle_by_vertex_index(index)
+            match self.gpu_mems.get(&(pack_id as u32)) {
+                None => { None }
+                Some(mem) => { mem.get_triangle_by_vertex_index(index) }
+            }
         }
     }
     pub fn on_render(&mut self) { self.camera.update(self.tot_bbx.clone()) }
     pub fn unselect_by_id(&mut self, id: i32) {
-        self.gpu_mems.iter_mut().for_each(|mem| {
-            if (mem.set_default_by_id(id.clone())) {
-                self.selected_hull_ids.remove(&id);
-            }
-        });
+        if (self.selected_hull_ids.remove(&id)) {
+            self.apply_material(id);
+        }
     }
     pub fn unselect_all(&mut self) {
-        let selected: Vec<i32> = self.selected_hull_ids.clone().into_iter().collect();
-        selected.iter().for_each(|id| {
-            self.gpu_mems.iter_mut().for_each(|mem| {
-                if (mem.set_default_by_id(id.clone())) {
-                    self.selected_hull_ids.remove(&id);
-                }
-            });
-        });
+        let selected: Vec<i32> = self.selected_hull_ids.drain().collect();
+        selected.iter().for_each(|id| self.apply_material(*id));
     }
     pub fn select_by_id(&mut self, oid: i32, pack_id: u32) {
         self.unselect_all();
-        if (self.gpu_mems[pack_id as usize].select_by_id(oid)) {
+        if (self.gpu_mems.get(&pack_id).map_or(false, |mem| mem.mesh_hash.contains_key(&oid))) {
             self.selected_hull_ids.insert(oid);
+            self.apply_material(oid);
         }
     }
     pub fn select_by_ids(&mut self, ids: HashSet<i32>) {
         self.unselect_all();
-        ids.iter().for_each(|id| {
-            self.gpu_mems.iter_mut().for_each(|mem| {
-                if (mem.select_by_id(id.clone())) {
-                    self.selected_hull_ids.insert(id.clone());
-                }
-            });
+        ids.iter().filter(|id| self.is_loaded(**id)).copied().collect::<Vec<i32>>().into_iter().for_each(|id| {
+            self.selected_hull_ids.insert(id);
+            self.apply_material(id);
         });
     }
     pub fn unhide_by_id(&mut self, id: i32) {
-        self.gpu_mems.iter_mut().for_each(|mem| {
-            if (mem.set_default_by_id(id.clone())) {
-                self.hidden_hull_ids.remove(&id);
-            }
-        });
+        if (self.hidden_hull_ids.remove(&id)) {
+            self.apply_material(id);
+        }
     }
     pub fn unhide_all(&mut self) {
-        let hidden: Vec<i32> = self.hidden_hull_ids.clone().into_iter().collect();
-        hidden.iter().for_each(|id| {
-            self.gpu_mems.iter_mut().for_each(|mem| {
-                if (mem.set_default_by_id(id.clone())) {
-                    self.hidden_hull_ids.remove(&id);
-                }
-            });
-        });
+        let hidden: Vec<i32> = self.hidden_hull_ids.drain().collect();
+        hidden.iter().for_each(|id| self.apply_material(*id));
     }
     pub fn hide_by_id(&mut self, oid: i32, pack_id: u32) {
-        if (self.gpu_mems[pack_id as usize].hide_by_id(oid)) {
-            self.selected_hull_ids.insert(oid);
+        if (self.gpu_mems.get(&pack_id).map_or(false, |mem| mem.mesh_hash.contains_key(&oid))) {
+            self.hidden_hull_ids.insert(oid);
+            self.apply_material(oid);
         }
     }
     pub fn hide_by_ids(&mut self, ids: HashSet<i32>) {
         self.unhide_all();
-        ids.iter().for_each(|id| {
-            self.gpu_mems.iter_mut().for_each(|mem| {
-                if (mem.hide_by_id(id.clone())) {
-                    self.hidden_hull_ids.insert(id.clone());
+        ids.iter().filter(|id| self.is_loaded(**id)).copied().collect::<Vec<i32>>().into_iter().for_each(|id| {
+            self.hidden_hull_ids.insert(id);
+            self.apply_material(id);
+        });
+    }
+    fn is_loaded(&self, id: i32) -> bool {
+        self.gpu_mems.values().any(|mem| mem.mesh_hash.contains_key(&id))
+    }
+    //hidden wins over selected, selected over assembly colour, so toggling one state keeps the others
+    fn apply_material(&mut self, id: i32) {
+        let mat = if (self.hidden_hull_ids.contains(&id)) {
+            Some(HIDDEN_HULL_MAT)
+        } else if (self.selected_hull_ids.contains(&id)) {
+            Some(SELECTION_HULL_MAT)
+        } else {
+            self.colored_hull_ids.get(&id).copied()
+        };
+        for mem in self.gpu_mems.values_mut() {
+            let is_found = match mat {
+                None => mem.set_default_by_id(id),
+                Some(mat) => mem.set_material_by_id(id, mat),
+            };
+            if (is_found) {
+                break;
+            }
+        }
+    }
+
+    //id -> material over the default one, hidden and selected parts keep their state
+    pub fn color_by_ids(&mut self, colors: HashMap<i32, i32>) {
+        colors.into_iter().for_each(|(id, mat)| {
+            self.colored_hull_ids.insert(id, mat);
+            self.apply_material(id);
+        });
+    }
+    pub fn clear_color_by_ids(&mut self, ids: Vec<i32>) {
+        ids.into_iter().for_each(|id| {
+            if (self.colored_hull_ids.remove(&id).is_some()) {
+                self.apply_material(id);
+            }
+        });
+    }
+
+    //builds the tree again if part info changed, see PartInfoState
+    pub fn check_assembly(&mut self) {
+        match PART_INFO.try_lock() {
+            Ok(mut m) => {
+                if (m.is_dirty) {
+                    self.assembly = AssemblyTree::build(m.packs.values().flat_map(|p| p.values()));
+                    m.is_dirty = false;
                 }
-            });
+            }
+            Err(_e) => { warn!("CANT LOCK PART_INFO MEM") }
+        }
+    }
+    //loaded ids under the node, the ship is every loaded part, None if there is no such node
+    pub fn assembly_ids(&self, node: &AssemblyNode) -> Option<Vec<i32>> {
+        match node {
+            AssemblyNode::Ship => Some(self.gpu_mems.values().flat_map(|mem| mem.mesh_hash.keys()).copied().collect()),
+            _ => self.assembly.ids(node).map(|ids| ids.into_iter().filter(|id| self.is_loaded(*id)).collect()),
+        }
+    }
+    //box of the loaded parts among ids, empty if none of them is loaded
+    pub fn bbx_of_ids(&self, ids: &[i32]) -> BoundingBox<Point3<f64>> {
+        let mut bbx: BoundingBox<Point3<f64>> = BoundingBox::default();
+        ids.iter().for_each(|id| {
+            match self.gpu_mems.values().find_map(|mem| mem.get_bbx_by_oid(*id)) {
+                None => {}
+                Some(b) => { bbx += b; }
+            }
         });
+        bbx
+    }
+    //returns true if the scene has to be drawn again
+    pub fn assembly_action(&mut self, action: AssemblyAction, node: &AssemblyNode) -> bool {
+        let ids = match self.assembly_ids(node) {
+            None => {
+                warn!("ASSEMBLY NODE {} IS NOT FOUND", node.path());
+                return false;
+            }
+            Some(ids) => ids
+        };
+        match action {
+            AssemblyAction::Select => {
+                self.select_by_ids(ids.into_iter().collect());
+                self.refresh_hull_remote_selected();
+            }
+            AssemblyAction::Hide => {
+                ids.into_iter().for_each(|id| {
+                    if (self.hidden_hull_ids.insert(id)) {
+                        self.apply_material(id);
+                    }
+                });
+                self.refresh_hull_remote_hidden();
+            }
+            AssemblyAction::Show => {
+                ids.into_iter().for_each(|id| self.unhide_by_id(id));
+                self.refresh_hull_remote_hidden();
+            }
+            AssemblyAction::Isolate => {
+                let shown: HashSet<i32> = ids.into_iter().collect();
+                let all: Vec<i32> = self.gpu_mems.values().flat_map(|mem| mem.mesh_hash.keys()).copied().collect();
+                all.into_iter().for_each(|id| {
+                    let is_changed = if (shown.contains(&id)) { self.hidden_hull_ids.remove(&id) } else { self.hidden_hull_ids.insert(id) };
+                    if (is_changed) {
+                        self.apply_material(id);
+                    }
+                });
+                self.refresh_hull_remote_hidden();
+            }
+            AssemblyAction::Zoom => {
+                let bbx = self.bbx_of_ids(&ids);
+                if (bbx.is_empty()) {
+                    return false;
+                }
+                self.zoom_to_bbx(&bbx);
+            }
+            AssemblyAction::Color(mat) => {
+                self.color_by_ids(ids.into_iter().map(|id| (id, mat)).collect());
+            }
+            AssemblyAction::ClearColor => {
+                self.clear_color_by_ids(ids);
+            }
+        }
+        true
+    }
+    //caps are drawn only while something cuts the model
+    pub fn is_section_capped(&self) -> bool {
+        self.section_caps.is_on && (self.slicer.is_cutting() || self.clipping.is_active())
+    }
+    //camera looks along the ship from a distance that fits the box diagonal
+    pub fn zoom_to_bbx(&mut self, bbx: &BoundingBox<Point3<f64>>) {
+        let center = bbx.center();
+        let p: Point3<f32> = Point3::new(center.x as f32, center.y as f32, center.z as f32);
+        let offset = (bbx.diagonal().magnitude() as f32).max(10.0);
+        let eye = p - SHIP_FORWARD * offset;
+        self.camera.move_and_look_at(eye, p);
     }
     pub fn zoom_to(&mut self, oid: i32) {
-        self.gpu_mems.iter().for_each(|mem| {
+        self.gpu_mems.values().for_each(|mem| {
             match mem.get_bbx_by_oid(oid) {
                 None => {}
                 Some(bbx) => {
@@ -473,4 +577,65 @@ impl SceneState {
         // #[cfg(target_arch = "wasm32")]
         //hull_state::hide_hull_parts_remote(web_sys::js_sys::Int32Array::from(Vec::from_iter(self.hidden_hull_ids.clone()).as_slice()));
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::mesh_common::MeshVertex;
+
+    //headless device, None where no adapter is found
+    fn test_device() -> Option<(Rc<RwLock<Device>>, Rc<RwLock<Queue>>)> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).ok()?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).ok()?;
+        Some((Rc::new(RwLock::new(device)), Rc::new(RwLock::new(queue))))
+    }
+
+    //one triangle per part, parts 11 and 12 in block B1, 13 in B2
+    fn block_pack(pack_id: u32) -> PackStreams {
+        let boxes: [([f32; 3], [f32; 3]); 3] = [([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]), ([4.0, -2.0, 0.0], [5.0, 0.0, 3.0]), ([20.0, 20.0, 20.0], [21.0, 21.0, 21.0])];
+        let mut v: Vec<MeshVertex> = vec![];
+        let mut b: Vec<f32> = vec![];
+        let mut t: Vec<u32> = vec![];
+        let mut info: Vec<PartInfo> = vec![];
+        boxes.iter().enumerate().for_each(|(k, (min, max))| {
+            let id = 11 + k as i32;
+            [*min, [max[0], min[1], min[2]], *max].iter().for_each(|p| {
+                let mut m = MeshVertex::default();
+                m.position = [p[0], p[1], p[2], 1.0];
+                m.id = id;
+                v.push(m);
+            });
+            b.extend_from_slice(&[min[0], min[1], min[2], max[0], max[1], max[2]]);
+            t.extend_from_slice(&[id as u32, k as u32 * 3, k as u32 * 3 + 2]);
+            info.push(PartInfo { id: id, block: Some(if (k < 2) { "B1" } else { "B2" }.to_string()), ..Default::default() });
+        });
+        PackStreams {
+            pack_id: pack_id,
+            v: bytemuck::cast_slice(&v).to_vec(),
+            i: bytemuck::cast_slice(&[0i32, 1, 2, 3, 4, 5, 6, 7, 8]).to_vec(),
+            b: bytemuck::cast_slice(&b).to_vec(),
+            t: bytemuck::cast_slice(&t).to_vec(),
+            info: Some(info),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn zoom_to_block() {
+        let (device, queue) = match test_device() {
+            None => { return; }
+            Some(d) => d
+        };
+        let mut scene = SceneState::new(device, queue);
+        scene.replace_pack(3, &block_pack(3)).unwrap();
+        scene.check_assembly();
+        let block = AssemblyNode::Block("B1".to_string());
+        let ids = scene.assembly_ids(&block).unwrap();
+        let bbx = scene.bbx_of_ids(&ids);
+        assert_eq!(bbx.min(), Point3::new(0.0, -2.0, 0.0));
+        assert_eq!(bbx.max(), Point3::new(5.0, 1.0, 3.0));
+        assert!(scene.assembly_action(AssemblyAction::Zoom, &block));
+        assert!(!scene.assembly_action(AssemblyAction::Zoom, &AssemblyNode::Block("B3".to_string())));
+    }
+}
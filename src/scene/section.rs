@@ -0,0 +1,492 @@
+use std::collections::{BTreeMap, HashMap};
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashSet;
+use std::fmt::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3};
+use log::warn;
+#[cfg(not(target_arch = "wasm32"))]
+use truck_base::bounding_box::BoundingBox;
+
+use crate::gui::clipping::ClipPlane;
+use crate::gui::slicer::Slicer;
+use crate::scene::gpu_mem::{visit_hull_triangles, GpuMem};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scene::mesh_loader::{read_hull_packed_from_files, HullData};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scene::pack_format::find_hull_packs;
+use crate::shared::mesh_common::HullVertices;
+use crate::shared::materials_lib::{CABLE_TY_MAX, CABLE_TY_MIN, HullPartTypes, Material, TY_HULL_OTHERS, TY_HULL_OUTERPLATES, TY_HULL_PLATES, TY_HULL_PROFILES};
+
+//segment ends closer than this (mm) are joined into one polyline
+const SECTION_WELD_TOLERANCE: f64 = 0.01;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SectionFormat {
+    Svg,
+    Dxf,
+}
+
+impl SectionFormat {
+    pub fn from_name(name: &str) -> Option<SectionFormat> {
+        match name.to_lowercase().as_str() {
+            "svg" => Some(SectionFormat::Svg),
+            "dxf" => Some(SectionFormat::Dxf),
+            _ => None,
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SectionFormat::Svg => "svg",
+            SectionFormat::Dxf => "dxf",
+        }
+    }
+}
+
+//a cut of one part, in the 2d frame of the Section
+#[derive(Clone, Debug)]
+pub struct SectionPolyline {
+    pub id: i32,
+    pub ty: i32,
+    pub points: Vec<[f64; 2]>,
+    pub is_closed: bool,
+}
+
+//Plane cut of the visible parts, e.g. a deck plan. The cut is drawn as seen from the side the normal
+//points to: u to the right, v up, v follows world z (world y if the plane is near horizontal).
+//Drawing coordinates are world coordinates along u and v, so a deck plan keeps the x and y of the model
+pub struct Section {
+    pub plane: ClipPlane,
+    pub u: Vector3<f64>,
+    pub v: Vector3<f64>,
+    //per (ty, id), so the output is grouped by layer and stable
+    segments: BTreeMap<(i32, i32), Vec<[[f64; 2]; 2]>>,
+}
+
+impl Section {
+    pub fn new(plane: ClipPlane) -> Self {
+        let n = Vector3::new(plane.normal.x as f64, plane.normal.y as f64, plane.normal.z as f64);
+        let up = if (n.z.abs() > 0.9) { Vector3::unit_y() } else { Vector3::unit_z() };
+        let v = (up - n * n.dot(up)).normalize();
+        Self {
+            plane: plane,
+            u: v.cross(n),
+            v: v,
+            segments: BTreeMap::new(),
+        }
+    }
+    //the plane of the first moved sensor (z max, z min, x max, x min, y max, y min),
+    //facing the removed side, so a cut from above gives a plan seen from above
+    pub fn plane_from_slicer(slicer: &Slicer) -> Option<ClipPlane> {
+        let (x_max, x_min, y_max, y_min, z_max, z_min) = slicer.slice_positions();
+        let cuts = [
+            (slicer.z_max_sensor < 1.0, Point3::new(0.0, 0.0, z_max), Vector3::unit_z()),
+            (slicer.z_min_sensor > 0.0, Point3::new(0.0, 0.0, z_min), -Vector3::unit_z()),
+            (slicer.x_max_sensor < 1.0, Point3::new(x_max, 0.0, 0.0), Vector3::unit_x()),
+            (slicer.x_min_sensor > 0.0, Point3::new(x_min, 0.0, 0.0), -Vector3::unit_x()),
+            (slicer.y_max_sensor < 1.0, Point3::new(0.0, y_max, 0.0), Vector3::unit_y()),
+            (slicer.y_min_sensor > 0.0, Point3::new(0.0, y_min, 0.0), -Vector3::unit_y()),
+        ];
+        cuts.iter().find(|c| c.0).and_then(|c| ClipPlane::new(c.1, c.2))
+    }
+    pub fn add_pack(&mut self, mem: &GpuMem) {
+        mem.visit_visible_triangles(|id, ty, tri| self.add_triangle(id, ty, tri));
+    }
+    //a pack read from its streams, e.g. by the command line without a GPU
+    pub fn add_hull_data(&mut self, vertices: &HullVertices, i: &[i32], metadata: &[i32]) {
+        let f = |id: i32, ty: i32, tri: [[f32; 3]; 3]| self.add_triangle(id, ty, tri);
+        match vertices {
+            HullVertices::Full(v) => visit_hull_triangles(v, i, None, None, metadata, f),
+            HullVertices::Compact(compact) => visit_hull_triangles(&[], i, Some(compact), None, metadata, f),
+            HullVertices::Instanced(instanced) => visit_hull_triangles(&[], i, None, Some(instanced), metadata, f),
+        }
+    }
+    pub fn add_triangle(&mut self, id: i32, ty: i32, tri: [[f32; 3]; 3]) {
+        let o = self.plane.point.cast::<f64>().unwrap_or(Point3::origin());
+        let n = self.plane.normal.cast::<f64>().unwrap_or(Vector3::unit_z());
+        let p = tri.map(|c| Vector3::new(c[0] as f64, c[1] as f64, c[2] as f64) - o.to_vec());
+        let d = p.map(|c| n.dot(c));
+        let mut ends: Vec<[f64; 2]> = vec![];
+        [(0, 1), (1, 2), (2, 0)].iter().for_each(|(a, b)| {
+            if ((d[*a] >= 0.0) == (d[*b] >= 0.0)) {
+                return;
+            }
+            //always from the positive corner, so both triangles of an edge give the same point
+            let (pos, neg) = if (d[*a] >= 0.0) { (*a, *b) } else { (*b, *a) };
+            let t = d[pos] / (d[pos] - d[neg]);
+            let q = o.to_vec() + p[pos] + (p[neg] - p[pos]) * t;
+            ends.push([self.u.dot(q), self.v.dot(q)]);
+        });
+        if (ends.len() == 2 && ends[0] != ends[1]) {
+            self.segments.entry((ty, id)).or_insert_with(Vec::new).push([ends[0], ends[1]]);
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+    //segments of a part joined end to end, open chains first, then the closed loops
+    pub fn polylines(&self) -> Vec<SectionPolyline> {
+        let mut ret: Vec<SectionPolyline> = vec![];
+        self.segments.iter().for_each(|((ty, id), segments)| {
+            let key = |p: &[f64; 2]| ((p[0] / SECTION_WELD_TOLERANCE).round() as i64, (p[1] / SECTION_WELD_TOLERANCE).round() as i64);
+            let mut ends: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+            segments.iter().enumerate().for_each(|(k, s)| {
+                ends.entry(key(&s[0])).or_insert_with(Vec::new).push(k);
+                ends.entry(key(&s[1])).or_insert_with(Vec::new).push(k);
+            });
+            let mut is_used: Vec<bool> = vec![false; segments.len()];
+            let walk = |start: usize, from: usize, is_used: &mut Vec<bool>| {
+                let mut points: Vec<[f64; 2]> = vec![segments[start][from]];
+                let mut end = segments[start][1 - from];
+                is_used[start] = true;
+                loop {
+                    points.push(end);
+                    let next = ends.get(&key(&end)).and_then(|ks| ks.iter().find(|k| !is_used[**k]).copied());
+                    match next {
+                        None => break,
+                        Some(k) => {
+                            is_used[k] = true;
+                            end = if (key(&segments[k][0]) == key(&end)) { segments[k][1] } else { segments[k][0] };
+                        }
+                    }
+                }
+                let is_closed = points.len() > 3 && key(&points[0]) == key(&points[points.len() - 1]);
+                if (is_closed) {
+                    points.pop();
+                }
+                SectionPolyline { id: *id, ty: *ty, points: drop_collinear(points, is_closed), is_closed: is_closed }
+            };
+            let mut open_ends: Vec<((i64, i64), usize)> = ends.iter().filter(|(_p, ks)| ks.len() % 2 == 1).map(|(p, ks)| (*p, ks[0])).collect();
+            open_ends.sort();
+            open_ends.iter().for_each(|(p, k)| {
+                if (!is_used[*k]) {
+                    let from = if (key(&segments[*k][0]) == *p) { 0 } else { 1 };
+                    ret.push(walk(*k, from, &mut is_used));
+                }
+            });
+            (0..segments.len()).for_each(|k| {
+                if (!is_used[k]) {
+                    ret.push(walk(k, 0, &mut is_used));
+                }
+            });
+        });
+        ret
+    }
+    pub fn export(&self, format: SectionFormat) -> String {
+        match format {
+            SectionFormat::Svg => self.to_svg(),
+            SectionFormat::Dxf => self.to_dxf(),
+        }
+    }
+    //plan of the cut, one layer (group) per part type, y is flipped so v points up
+    pub fn to_svg(&self) -> String {
+        let polylines = self.polylines();
+        let (min, max) = bounds(&polylines);
+        let margin = ((max[0] - min[0]).max(max[1] - min[1]) * 0.02).max(1.0);
+        let mut s = String::new();
+        let _ = writeln!(s, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+        let _ = writeln!(s, "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:inkscape=\"http://www.inkscape.org/namespaces/inkscape\" viewBox=\"{:.3} {:.3} {:.3} {:.3}\">",
+                         min[0] - margin, -max[1] - margin, max[0] - min[0] + 2.0 * margin, max[1] - min[1] + 2.0 * margin);
+        let _ = writeln!(s, "<!-- section through {:?} normal {:?} -->", self.plane.point, self.plane.normal);
+        by_type(&polylines).iter().for_each(|(ty, lines)| {
+            let name = layer_name(*ty);
+            let _ = writeln!(s, "<g id=\"{}\" inkscape:groupmode=\"layer\" inkscape:label=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\">", name, name, svg_color(*ty));
+            lines.iter().enumerate().for_each(|(k, l)| {
+                let points: Vec<String> = l.points.iter().map(|p| format!("{:.3},{:.3}", p[0] + 0.0, -p[1] + 0.0)).collect();
+                let tag = if (l.is_closed) { "polygon" } else { "polyline" };
+                let _ = writeln!(s, "<{} id=\"part_{}_{}\" vector-effect=\"non-scaling-stroke\" points=\"{}\"/>", tag, l.id, k, points.join(" "));
+            });
+            let _ = writeln!(s, "</g>");
+        });
+        let _ = writeln!(s, "</svg>");
+        s
+    }
+    //ASCII DXF R12, one layer per part type, a POLYLINE per cut with the part id in a comment before it
+    pub fn to_dxf(&self) -> String {
+        let polylines = self.polylines();
+        let layers = by_type(&polylines);
+        let mut s = String::new();
+        let mut pair = |code: i32, value: &str| {
+            let _ = writeln!(s, "{}\n{}", code, value);
+        };
+        pair(999, &format!("section through {:?} normal {:?}", self.plane.point, self.plane.normal));
+        pair(0, "SECTION");
+        pair(2, "HEADER");
+        pair(9, "$ACADVER");
+        pair(1, "AC1009");
+        pair(0, "ENDSEC");
+        pair(0, "SECTION");
+        pair(2, "TABLES");
+        pair(0, "TABLE");
+        pair(2, "LTYPE");
+        pair(70, "1");
+        pair(0, "LTYPE");
+        pair(2, "CONTINUOUS");
+        pair(70, "0");
+        pair(3, "Solid line");
+        pair(72, "65");
+        pair(73, "0");
+        pair(40, "0.0");
+        pair(0, "ENDTAB");
+        pair(0, "TABLE");
+        pair(2, "LAYER");
+        pair(70, &layers.len().to_string());
+        layers.keys().for_each(|ty| {
+            pair(0, "LAYER");
+            pair(2, &layer_name(*ty));
+            pair(70, "0");
+            pair(62, &dxf_color(*ty).to_string());
+            pair(6, "CONTINUOUS");
+        });
+        pair(0, "ENDTAB");
+        pair(0, "ENDSEC");
+        pair(0, "SECTION");
+        pair(2, "ENTITIES");
+        layers.iter().for_each(|(ty, lines)| {
+            let layer = layer_name(*ty);
+            lines.iter().for_each(|l| {
+                pair(999, &format!("part {}", l.id));
+                pair(0, "POLYLINE");
+                pair(8, &layer);
+                pair(66, "1");
+                pair(10, "0.0");
+                pair(20, "0.0");
+                pair(30, "0.0");
+                pair(70, if (l.is_closed) { "1" } else { "0" });
+                l.points.iter().for_each(|p| {
+                    pair(0, "VERTEX");
+                    pair(8, &layer);
+                    pair(10, &format!("{:.4}", p[0]));
+                    pair(20, &format!("{:.4}", p[1]));
+                    pair(30, "0.0");
+                });
+                pair(0, "SEQEND");
+                pair(8, &layer);
+            });
+        });
+        pair(0, "ENDSEC");
+        pair(0, "EOF");
+        s
+    }
+}
+
+//putout section: the drawings of a cut, the plane is given or is the first moved sensor of a slicer over the model box
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let mut plane: Option<ClipPlane> = None;
+    let mut sensors: Option<Vec<f32>> = None;
+    let mut outs: Vec<PathBuf> = vec![];
+    let mut paths: Vec<PathBuf> = vec![];
+    let mut it = args.into_iter();
+    let floats = |name: &str, value: Option<String>| -> Result<Vec<f32>, String> {
+        let value = value.ok_or(format!("{} needs a value", name))?;
+        let values: Result<Vec<f32>, _> = value.split(',').map(|v| v.trim().parse::<f32>()).collect();
+        match values {
+            Ok(v) if v.len() == 6 => Ok(v),
+            _ => Err(format!("{} needs 6 numbers, got {}", name, value)),
+        }
+    };
+    while let Some(arg) = it.next() {
+        if (arg == "--plane") {
+            let v = floats("--plane", it.next())?;
+            plane = Some(ClipPlane::new(Point3::new(v[0], v[1], v[2]), Vector3::new(v[3], v[4], v[5])).ok_or("--plane has no normal".to_string())?);
+        } else if (arg == "--slicer") {
+            sensors = Some(floats("--slicer", it.next())?);
+        } else if (arg == "-o" || arg == "--out") {
+            outs.push(PathBuf::from(it.next().ok_or("-o needs a file".to_string())?));
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    if (paths.is_empty()) {
+        return Err("section needs a model directory or pack files".to_string());
+    }
+    if (outs.is_empty()) {
+        outs = vec![PathBuf::from("section.svg"), PathBuf::from("section.dxf")];
+    }
+    let formats: Vec<SectionFormat> = outs.iter().map(|o| {
+        o.extension().and_then(|e| e.to_str()).and_then(SectionFormat::from_name).ok_or(format!("{} is not a .svg or .dxf file", o.display()))
+    }).collect::<Result<Vec<SectionFormat>, String>>()?;
+    let packs = find_hull_packs(&paths).map_err(|e| e.to_string())?;
+    let hulls = packs.iter().map(|pack| read_hull_packed_from_files(pack).map_err(|e| e.to_string())).collect::<Result<Vec<HullData>, String>>()?;
+    let plane = match (plane, sensors) {
+        (Some(p), _) => p,
+        (None, sensors) => {
+            let mut tot_bbx: BoundingBox<Point3<f64>> = BoundingBox::default();
+            hulls.iter().for_each(|h| tot_bbx += &h.3);
+            let s = sensors.unwrap_or(vec![1.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+            let mut slicer = Slicer::new();
+            slicer.set_by_bbx(&tot_bbx);
+            slicer.set_slicer(s[0], s[1], s[2], s[3], s[4], s[5]);
+            Section::plane_from_slicer(&slicer).ok_or("give --plane, or --slicer with a moved sensor".to_string())?
+        }
+    };
+    let mut section = Section::new(plane);
+    hulls.iter().for_each(|h| section.add_hull_data(&h.0, &h.1, &h.2));
+    if (section.is_empty()) {
+        warn!("SECTION: NOTHING IS CUT BY {:?}", plane);
+    }
+    let polylines = section.polylines();
+    println!("section through {:?} normal {:?}: {} parts, {} polylines", plane.point, plane.normal,
+             polylines.iter().map(|l| l.id).collect::<HashSet<i32>>().len(), polylines.len());
+    outs.iter().zip(formats.iter()).map(|(out, format)| {
+        fs::write(out, section.export(*format)).map_err(|e| format!("{}: {}", out.display(), e))?;
+        println!("  {}", out.display());
+        Ok(())
+    }).collect::<Result<Vec<()>, String>>()?;
+    Ok(())
+}
+
+//points on the line of their neighbours, a cut of a flat face gives one per triangle edge
+fn drop_collinear(points: Vec<[f64; 2]>, is_closed: bool) -> Vec<[f64; 2]> {
+    let is_on_line = |a: &[f64; 2], b: &[f64; 2], c: &[f64; 2]| {
+        let cross = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        let l = ((c[0] - a[0]).powi(2) + (c[1] - a[1]).powi(2)).sqrt();
+        cross.abs() <= SECTION_WELD_TOLERANCE * l
+    };
+    let mut ret: Vec<[f64; 2]> = vec![];
+    points.iter().enumerate().for_each(|(k, p)| {
+        let next = match points.get(k + 1) {
+            None if (is_closed) => ret.first().copied(),
+            next => next.copied(),
+        };
+        match (ret.last(), next) {
+            (Some(prev), Some(next)) if (is_on_line(prev, p, &next)) => {}
+            _ => ret.push(*p),
+        }
+    });
+    //the first point of a loop may be on the line of the last and the second
+    if (is_closed && ret.len() > 3 && is_on_line(&ret[ret.len() - 1], &ret[0], &ret[1])) {
+        ret.remove(0);
+    }
+    ret
+}
+
+fn by_type(polylines: &[SectionPolyline]) -> BTreeMap<i32, Vec<&SectionPolyline>> {
+    let mut ret: BTreeMap<i32, Vec<&SectionPolyline>> = BTreeMap::new();
+    polylines.iter().for_each(|l| ret.entry(l.ty).or_insert_with(Vec::new).push(l));
+    ret
+}
+
+fn bounds(polylines: &[SectionPolyline]) -> ([f64; 2], [f64; 2]) {
+    let mut min = [f64::MAX, f64::MAX];
+    let mut max = [f64::MIN, f64::MIN];
+    polylines.iter().flat_map(|l| l.points.iter()).for_each(|p| {
+        min = [min[0].min(p[0]), min[1].min(p[1])];
+        max = [max[0].max(p[0]), max[1].max(p[1])];
+    });
+    if (min[0] > max[0]) {
+        warn!("SECTION: NOTHING IS CUT");
+        return ([0.0, 0.0], [0.0, 0.0]);
+    }
+    (min, max)
+}
+
+//upper case letters, digits and underscores, the same names for svg ids and dxf layers
+fn layer_name(ty: i32) -> String {
+    match (ty, HullPartTypes::from_code(ty)) {
+        (CABLE_TY_MIN..=CABLE_TY_MAX, _) => format!("CABLE_{}", ty - CABLE_TY_MIN),
+        (_, Some(t)) => t.name().to_uppercase(),
+        (_, None) => format!("TYPE_{}", ty),
+    }
+}
+
+fn svg_color(ty: i32) -> String {
+    let materials = Material::generate_materials();
+    let c = materials.get(Material::type_to_color(ty) as usize).map_or([0.0, 0.0, 0.0, 1.0], |m| m.color);
+    let to_srgb = |c: f32| {
+        let s = if (c <= 0.0031308) { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+        (s.clamp(0.0, 1.0) * 255.0).round() as u8
+    };
+    format!("#{:02x}{:02x}{:02x}", to_srgb(c[0]), to_srgb(c[1]), to_srgb(c[2]))
+}
+
+//AutoCAD colour index, R12 has no true colours
+fn dxf_color(ty: i32) -> i32 {
+    match Material::type_to_color(ty) {
+        TY_HULL_OUTERPLATES => 1,
+        TY_HULL_PROFILES => 3,
+        TY_HULL_PLATES => 5,
+        TY_HULL_OTHERS => 8,
+        _ => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECK_PLATE: i32 = 10;
+    const SHELL_PLATE: i32 = 9;
+
+    //two triangles of a face, corners in order around it
+    fn add_quad(section: &mut Section, id: i32, ty: i32, q: [[f32; 3]; 4]) {
+        section.add_triangle(id, ty, [q[0], q[1], q[2]]);
+        section.add_triangle(id, ty, [q[0], q[2], q[3]]);
+    }
+
+    //closed box from min to max, top and bottom included
+    fn add_box(section: &mut Section, id: i32, ty: i32, min: [f32; 3], max: [f32; 3]) {
+        let c = |i: usize| [if (i & 1 == 0) { min[0] } else { max[0] }, if (i & 2 == 0) { min[1] } else { max[1] }, if (i & 4 == 0) { min[2] } else { max[2] }];
+        [[0, 1, 3, 2], [4, 6, 7, 5], [0, 4, 5, 1], [1, 5, 7, 3], [3, 7, 6, 2], [2, 6, 4, 0]].iter().for_each(|f| {
+            add_quad(section, id, ty, f.map(c));
+        });
+    }
+
+    //deck plan at z = 0.5: part 1 is two boxes, part 2 an L of two plates, part 3 a cable triangle
+    fn deck_section() -> Section {
+        let mut section = Section::new(ClipPlane::new(Point3::new(0.0, 0.0, 0.5), Vector3::unit_z()).unwrap());
+        add_box(&mut section, 1, DECK_PLATE, [0.0, 0.0, 0.0], [4.0, 2.0, 1.0]);
+        add_box(&mut section, 1, DECK_PLATE, [10.0, 0.0, 0.0], [12.0, 2.0, 1.0]);
+        add_quad(&mut section, 2, SHELL_PLATE, [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 0.0, 1.0], [0.0, 0.0, 1.0]]);
+        add_quad(&mut section, 2, SHELL_PLATE, [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 1.0, 1.0], [0.0, 0.0, 1.0]]);
+        section.add_triangle(3, CABLE_TY_MIN + 2, [[0.0, 5.0, 0.0], [1.0, 5.0, 0.0], [0.0, 5.0, 1.0]]);
+        section
+    }
+
+    #[test]
+    fn polylines_of_box_and_profile() {
+        let polylines = deck_section().polylines();
+        assert_eq!(polylines.len(), 4);
+        //by type, the L first
+        let profile = &polylines[0];
+        assert_eq!((profile.id, profile.is_closed), (2, false));
+        assert_eq!(profile.points, vec![[0.0, 1.0], [0.0, 0.0], [2.0, 0.0]]);
+        let loops: Vec<&SectionPolyline> = polylines.iter().filter(|l| l.id == 1).collect();
+        assert_eq!(loops.len(), 2);
+        loops.iter().for_each(|l| {
+            assert!(l.is_closed);
+            assert_eq!(l.points.len(), 4);
+        });
+        let cable = &polylines[3];
+        assert_eq!((cable.id, cable.is_closed, cable.points.len()), (3, false, 2));
+    }
+
+    #[test]
+    fn drop_collinear_points() {
+        let square = vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [2.0, 1.0], [2.0, 2.0], [0.0, 2.0], [0.0, 1.0]];
+        assert_eq!(drop_collinear(square, true), vec![[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]);
+        //a loop that starts on a side
+        let square = vec![[1.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0], [0.0, 0.0]];
+        assert_eq!(drop_collinear(square, true), vec![[2.0, 0.0], [2.0, 2.0], [0.0, 2.0], [0.0, 0.0]]);
+        //ends of an open chain stay
+        let chain = vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [2.0, 1.0]];
+        assert_eq!(drop_collinear(chain, false), vec![[0.0, 0.0], [2.0, 0.0], [2.0, 1.0]]);
+    }
+
+    #[test]
+    fn dxf_layers() {
+        let dxf = deck_section().to_dxf();
+        let layers: Vec<&str> = dxf.lines().collect::<Vec<&str>>().windows(4)
+            .filter(|w| w[0] == "0" && w[1] == "LAYER" && w[2] == "2")
+            .map(|w| w[3])
+            .collect();
+        assert_eq!(layers, vec!["SHELLPLATE", "DECKPLATE", "CABLE_2"]);
+        assert_eq!(dxf.matches("\nPOLYLINE\n").count(), 4);
+        assert_eq!(dxf.matches("\nPOLYLINE\n8\nDECKPLATE\n66\n1\n10\n0.0\n20\n0.0\n30\n0.0\n70\n1\n").count(), 2);
+        assert!(dxf.ends_with("0\nEOF\n"));
+    }
+}
@@ -68,6 +68,17 @@ struct HighLightCabNodes {
      positions:array<vec4<f32>>
 };
 @binding(5) @group(0) var<storage, read> hlCabNodes : HighLightCabNodes;
+//instanced layout, see MeshInstance: the template vertex is moved by the instance transform
+struct InstanceInput {
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
+    @location(8) normal_0: vec4<f32>,
+    @location(9) normal_1: vec4<f32>,
+    @location(10) normal_2: vec4<f32>,
+    @location(11) base_vertex: i32,
+};
 
 struct Output {
     @builtin(position) position : vec4<f32>,
@@ -88,6 +99,21 @@ fn vs_main(in:VertexInput) -> Output {
     return output;
 }
 
+@vertex
+fn vs_instanced(in:VertexInput,inst:InstanceInput) -> Output {
+    let model = mat4x4<f32>(inst.model_0, inst.model_1, inst.model_2, inst.model_3);
+    let normal_model = mat3x3<f32>(inst.normal_0.xyz, inst.normal_1.xyz, inst.normal_2.xyz);
+    let position = model * vec4<f32>(in.position.xyz, 1.0);
+
+    var output: Output;
+    output.mat_id=in.material_index;
+
+    output.position = camera.mvp * position;
+    output.world_position = position;
+    output.world_normal = vec4<f32>(normalize(normal_model * in.normal.xyz), 1.0);
+    return output;
+}
+
 @fragment
 fn fs_main(in:Output) ->  @location(0) vec4<f32> {
   let material:LightUniforms=light_uniformsArray[1];
@@ -0,0 +1,31 @@
+//composite of the translucent layer written by fs_translucent of shader.wgsl over the opaque frame
+
+//rgb the weighted sum of premultiplied colours, a the weighted sum of alphas
+@binding(0) @group(0) var accum_buffer : texture_2d<f32>;
+//product of (1 - alpha) of all translucent surfaces over the pixel, 1 is none
+@binding(1) @group(0) var revealage_buffer : texture_2d<f32>;
+
+struct Output {
+    @builtin(position) position : vec4<f32>,
+};
+
+//one triangle over the screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32) -> Output {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: Output;
+    output.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return output;
+}
+
+@fragment
+fn fs_main(in:Output) -> @location(0) vec4<f32> {
+    let p = vec2<i32>(in.position.xy);
+    let revealage = textureLoad(revealage_buffer, p, 0).x;
+    if(revealage >= 1.0){
+        discard;
+    }
+    let accum = textureLoad(accum_buffer, p, 0);
+    let color = accum.xyz / clamp(accum.w, 1e-4, 5e4);
+    return vec4<f32>(color, 1.0 - revealage);
+}
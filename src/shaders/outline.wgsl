@@ -0,0 +1,75 @@
+//silhouette and crease edges drawn over the hull, from the edge buffer written by fs_main and fs_cap of shader.wgsl
+
+struct OutlineStyle {
+    color : vec4<f32>,
+    //x line width px, y cosine of the crease angle, z depth jump as a part of the distance
+    params : vec4<f32>,
+};
+
+//xy octahedral normal (2.0 if none), z distance to the eye, w part key + 1, 0 is background
+@binding(0) @group(0) var edge_buffer : texture_2d<f32>;
+@binding(1) @group(0) var<uniform> style : OutlineStyle;
+
+struct Output {
+    @builtin(position) position : vec4<f32>,
+};
+
+//one triangle over the screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32) -> Output {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: Output;
+    output.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return output;
+}
+
+fn oct_decode(e:vec2<f32>) -> vec3<f32> {
+    var n = vec3<f32>(e.x, e.y, 1.0 - abs(e.x) - abs(e.y));
+    let t = max(-n.z, 0.0);
+    n.x = n.x + select(t, -t, n.x >= 0.0);
+    n.y = n.y + select(t, -t, n.y >= 0.0);
+    return normalize(n);
+}
+
+//a is the pixel, b a neighbour; the line goes on the nearer side so silhouettes lie on the part
+fn is_edge(a:vec4<f32>, b:vec4<f32>) -> bool {
+    if(b.w == 0.0){
+        return true;
+    }
+    if(a.z > b.z){
+        return false;
+    }
+    if(a.w != b.w){
+        return true;
+    }
+    if(b.z - a.z > style.params.z * a.z){
+        return true;
+    }
+    if(a.x > 1.5 || b.x > 1.5){
+        return false;
+    }
+    return dot(oct_decode(a.xy), oct_decode(b.xy)) < style.params.y;
+}
+
+@fragment
+fn fs_main(in:Output) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(edge_buffer));
+    let p = vec2<i32>(in.position.xy);
+    let a = textureLoad(edge_buffer, p, 0);
+    if(a.w == 0.0){
+        discard;
+    }
+    let radius = max(1, i32(round(style.params.x)));
+    var is_line = false;
+    for(var r = 1; r <= radius; r = r + 1){
+        let ns = array<vec2<i32>, 4>(vec2<i32>(r, 0), vec2<i32>(-r, 0), vec2<i32>(0, r), vec2<i32>(0, -r));
+        for(var k = 0; k < 4; k = k + 1){
+            let q = clamp(p + ns[k], vec2<i32>(0), size - 1);
+            is_line = is_line || is_edge(a, textureLoad(edge_buffer, q, 0));
+        }
+    }
+    if(!is_line){
+        discard;
+    }
+    return style.color;
+}
@@ -61,9 +61,29 @@ struct Slice {
     z_min:f32,
     z_minA:f32,
     z_minB:f32,
+    //ClipUniform: planes keep the side their normal points to, the section box keeps its inside
+    clip_count:vec4<i32>,
+    clip_planes:array<vec4<f32>, 8>,
+    box_center:vec4<f32>,
+    box_axes:array<vec4<f32>, 3>,
 };
 @binding(4) @group(0) var<uniform> slice : Slice;
 
+fn clipped(p:vec3<f32>) -> bool {
+    for(var i: i32 = 0; i < slice.clip_count.x; i++) {
+        let plane=slice.clip_planes[i];
+        if(dot(plane.xyz, p) + plane.w < 0.0){return true;}
+    }
+    if(slice.clip_count.y==1){
+        let d=p - slice.box_center.xyz;
+        for(var k: i32 = 0; k < 3; k++) {
+            let axis=slice.box_axes[k];
+            if(abs(dot(axis.xyz, d)) > axis.w){return true;}
+        }
+    }
+    return false;
+}
+
 struct SnapObject{
     t_snap_point_x: f32,
     t_snap_point_y: f32,
@@ -75,16 +95,32 @@ struct SnapObject{
 struct VertexMetaData {
      ids:array<i32>
 };
-@binding(6) @group(0) var<storage, read> vertex_meta_data0 : VertexMetaData;
-@binding(7) @group(0) var<storage, read> vertex_meta_data1 : VertexMetaData;
-@binding(8) @group(0) var<storage, read> vertex_meta_data2 : VertexMetaData;
-@binding(9) @group(0) var<storage, read> vertex_meta_data3 : VertexMetaData;
-@binding(10) @group(0) var<storage, read> vertex_meta_data4 : VertexMetaData;
-@binding(11) @group(0) var<storage, read> vertex_meta_data5 : VertexMetaData;
-@binding(12) @group(0) var<storage, read> vertex_meta_data6 : VertexMetaData;
-@binding(13) @group(0) var<storage, read> vertex_meta_data7 : VertexMetaData;
+//one pack per draw call, set as group 1 for each GpuMem
+@binding(0) @group(1) var<storage, read> vertex_meta_data : VertexMetaData;
 
 
+//compact layout, vertices are pulled through the index buffer so vertex_index is the index position
+//and get_triangle_by_vertex_index can find the triangle of a pixel, see CompactVertex and PartEntry
+struct Quantization {
+    origin : vec4<f32>,
+    extent : vec4<f32>,
+};
+@binding(1) @group(1) var<uniform> quantization : Quantization;
+@binding(2) @group(1) var<storage, read> compact_parts : array<vec2<i32>>;
+@binding(3) @group(1) var<storage, read> compact_vertices : array<vec4<u32>>;
+@binding(4) @group(1) var<storage, read> compact_indices : array<u32>;
+
+//instanced layout, see MeshInstance: the template vertex is moved by the instance transform
+struct InstanceInput {
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
+    @location(8) normal_0: vec4<f32>,
+    @location(9) normal_1: vec4<f32>,
+    @location(10) normal_2: vec4<f32>,
+    @location(11) base_vertex: i32,
+};
 
 struct Output {
     @builtin(position) position : vec4<f32>,
@@ -98,32 +134,7 @@ fn vs_main(@builtin(vertex_index) vertex_index : u32,in:VertexInput) -> Output {
     let raw_id=u32(in.material_index);
     let pack_id:u32=raw_id%100;
     let mat_id:u32=(raw_id-pack_id)/100;
-    var hull_meta_data=0;
-      if(pack_id==0){
-          hull_meta_data=vertex_meta_data0.ids[vertex_index];
-      }
-      if(pack_id==1){
-          hull_meta_data=vertex_meta_data1.ids[vertex_index];
-      }
-      if(pack_id==2){
-        hull_meta_data=vertex_meta_data2.ids[vertex_index];
-
-      }
-      if(pack_id==3){
-         hull_meta_data=vertex_meta_data3.ids[vertex_index];
-      }
-      if(pack_id==4){
-         hull_meta_data=vertex_meta_data4.ids[vertex_index];
-      }
-      if(pack_id==5){
-          hull_meta_data=vertex_meta_data5.ids[vertex_index];
-     }
-     if(pack_id==6){
-       hull_meta_data=vertex_meta_data6.ids[vertex_index];
-     }
-     if(pack_id==7){
-         hull_meta_data=vertex_meta_data7.ids[vertex_index];
-     }
+    let hull_meta_data:i32=vertex_meta_data.ids[vertex_index];
 
     var output: Output;
     output.originalpos= in.position;
@@ -133,6 +144,40 @@ fn vs_main(@builtin(vertex_index) vertex_index : u32,in:VertexInput) -> Output {
     return output;
 }
 
+@vertex
+fn vs_compact(@builtin(vertex_index) vertex_index : u32) -> Output {
+    let v_index:u32=compact_indices[vertex_index];
+    let cv:vec4<u32>=compact_vertices[v_index];
+    let q = vec4<f32>(unpack2x16unorm(cv.x), unpack2x16unorm(cv.y));
+    let position = vec4<f32>(quantization.origin.xyz + q.xyz * quantization.extent.xyz, 1.0);
+    let raw_id=u32(compact_parts[cv.w].y);
+    let pack_id:u32=raw_id%100;
+    let hull_meta_data:i32=vertex_meta_data.ids[v_index];
+
+    var output: Output;
+    output.originalpos= position;
+    output.mat_id=hull_meta_data;
+    output.pack_id=i32(vertex_index*100)+i32(pack_id);
+    output.position = camera.mvp  * position;
+    return output;
+}
+
+//the id is the index the vertex would have in the full layout, see MeshInstance
+@vertex
+fn vs_instanced(@builtin(vertex_index) vertex_index : u32,@builtin(instance_index) instance_index : u32,in:VertexInput,inst:InstanceInput) -> Output {
+    let model = mat4x4<f32>(inst.model_0, inst.model_1, inst.model_2, inst.model_3);
+    let position = model * vec4<f32>(in.position.xyz, 1.0);
+    let raw_id=u32(in.material_index);
+    let pack_id:u32=raw_id%100;
+    let hull_meta_data:i32=vertex_meta_data.ids[instance_index];
+
+    var output: Output;
+    output.originalpos= position;
+    output.mat_id=hull_meta_data;
+    output.pack_id=(inst.base_vertex+i32(vertex_index))*100+i32(pack_id);
+    output.position = camera.mvp  * position;
+    return output;
+}
 
 
 @fragment
@@ -142,6 +187,7 @@ fn fs_main(in:Output) ->   @location(0)  vec4<i32>{
     || in.originalpos.y>slice.y_max || in.originalpos.y<slice.y_min
     || in.originalpos.z>slice.z_max || in.originalpos.z<slice.z_min
     ) { discard;};
+     if(clipped(in.originalpos.xyz)){discard;}
      if(in.mat_id==0){discard;}
 
      //let out_value=i32(in.mat_pack.x*100) + i32(in.mat_pack.y);
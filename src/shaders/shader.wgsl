@@ -30,7 +30,9 @@ struct Camera {
 struct CameraUniforms {
     light_position : vec4<f32>,
     eye_position : vec4<f32>,
-    resolution : vec4<f32>
+    resolution : vec4<f32>,
+    //linear rgb of the colour temperature of the eye light, white by default
+    light_color : vec4<f32>
 };
 @binding(1) @group(0) var<uniform> camera_uniforms : CameraUniforms;
 
@@ -61,9 +63,29 @@ struct Slice {
     z_min:f32,
     z_minA:f32,
     z_minB:f32,
+    //ClipUniform: planes keep the side their normal points to, the section box keeps its inside
+    clip_count:vec4<i32>,
+    clip_planes:array<vec4<f32>, 8>,
+    box_center:vec4<f32>,
+    box_axes:array<vec4<f32>, 3>,
 };
 @binding(4) @group(0) var<uniform> slice : Slice;
 
+fn clipped(p:vec3<f32>) -> bool {
+    for(var i: i32 = 0; i < slice.clip_count.x; i++) {
+        let plane=slice.clip_planes[i];
+        if(dot(plane.xyz, p) + plane.w < 0.0){return true;}
+    }
+    if(slice.clip_count.y==1){
+        let d=p - slice.box_center.xyz;
+        for(var k: i32 = 0; k < 3; k++) {
+            let axis=slice.box_axes[k];
+            if(abs(dot(axis.xyz, d)) > axis.w){return true;}
+        }
+    }
+    return false;
+}
+
 struct SnapObject{
     t_snap_point_x: f32,
     t_snap_point_y: f32,
@@ -75,17 +97,74 @@ struct SnapObject{
 struct VertexMetaData {
      ids:array<i32>
 };
-@binding(6) @group(0) var<storage, read> vertex_meta_data0 : VertexMetaData;
-@binding(7) @group(0) var<storage, read> vertex_meta_data1 : VertexMetaData;
-@binding(8) @group(0) var<storage, read> vertex_meta_data2 : VertexMetaData;
-@binding(9) @group(0) var<storage, read> vertex_meta_data3 : VertexMetaData;
-@binding(10) @group(0) var<storage, read> vertex_meta_data4 : VertexMetaData;
-@binding(11) @group(0) var<storage, read> vertex_meta_data5 : VertexMetaData;
-@binding(12) @group(0) var<storage, read> vertex_meta_data6 : VertexMetaData;
-@binding(13) @group(0) var<storage, read> vertex_meta_data7 : VertexMetaData;
+//one pack per draw call, set as group 1 for each GpuMem
+@binding(0) @group(1) var<storage, read> vertex_meta_data : VertexMetaData;
+
+
+//directional light, see SunUniform
+struct Sun {
+    light_vp : mat4x4<f32>,
+    //xyz toward the sun, w 1 if it is on
+    direction : vec4<f32>,
+    //rgb times intensity
+    color : vec4<f32>,
+    //x texel size of the shadow map, y depth bias
+    params : vec4<f32>,
+};
+@binding(7) @group(0) var<uniform> sun : Sun;
+//depth seen from the sun, written by the shadow pass before the hull pass
+@binding(0) @group(2) var shadow_map : texture_depth_2d;
+@binding(1) @group(2) var shadow_sampler : sampler_comparison;
 
+//style of the section caps by material, see CapStyle
+struct CapStyle {
+    color : vec4<f32>,
+    //x 0 solid, 1 lines, 2 cross; y spacing px, z angle, w line width px
+    hatch : vec4<f32>,
+};
+@binding(6) @group(0) var<uniform> cap_styles : array<CapStyle, 140>;
 
+//compact layout, see CompactVertex, positions are unorm16 over the pack box, normals are octahedral snorm16
+struct CompactVertexInput {
+    @location(0) position: vec4<f32>,
+    @location(1) normal: vec2<f32>,
+    @location(2) part: u32,
+};
+struct Quantization {
+    origin : vec4<f32>,
+    extent : vec4<f32>,
+};
+@binding(1) @group(1) var<uniform> quantization : Quantization;
+//id and material index of every part of a compact pack, only vs_compact reads it
+@binding(2) @group(1) var<storage, read> compact_parts : array<vec2<i32>>;
+
+fn oct_decode(e:vec2<f32>) -> vec3<f32> {
+    var n = vec3<f32>(e.x, e.y, 1.0 - abs(e.x) - abs(e.y));
+    let t = max(-n.z, 0.0);
+    n.x = n.x + select(t, -t, n.x >= 0.0);
+    n.y = n.y + select(t, -t, n.y >= 0.0);
+    return normalize(n);
+}
 
+fn oct_encode(n:vec3<f32>) -> vec2<f32> {
+    let p = n.xy / (abs(n.x) + abs(n.y) + abs(n.z));
+    if(n.z >= 0.0){
+        return p;
+    }
+    return (1.0 - abs(p.yx)) * select(vec2<f32>(-1.0), vec2<f32>(1.0), p >= vec2<f32>(0.0));
+}
+
+//instanced layout, see MeshInstance: the template vertex is moved by the instance transform
+struct InstanceInput {
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
+    @location(8) normal_0: vec4<f32>,
+    @location(9) normal_1: vec4<f32>,
+    @location(10) normal_2: vec4<f32>,
+    @location(11) base_vertex: i32,
+};
 
 struct Output {
     @builtin(position) position : vec4<f32>,
@@ -93,40 +172,26 @@ struct Output {
     @location(1) world_position : vec4<f32>,
     @location(2) @interpolate(flat)  mat_id: i32,
     @location(3) originalpos : vec4<f32>,
+    //part id, the instance for instanced packs, tells the outline pass where one part meets another
+    @location(4) @interpolate(flat) part_key: i32,
 };
 
-@vertex
-fn vs_main(@builtin(vertex_index) vertex_index : u32,in:VertexInput) -> Output {
+//colour and the edge buffer read by outline.wgsl: octahedral normal, distance to the eye and part key + 1, 0 is background
+struct MeshTargets {
+    @location(0) color : vec4<f32>,
+    @location(1) edge : vec4<f32>,
+};
+
+fn edge_data(in:Output, normal:vec3<f32>) -> vec4<f32> {
+    let e = select(oct_encode(normal), vec2<f32>(2.0), dot(normal, normal) == 0.0);
+    return vec4<f32>(e, distance(camera_uniforms.eye_position.xyz, in.world_position.xyz), f32((in.part_key & 0xffffff) + 1));
+}
+
+fn mesh_output(vertex_index : u32,in:VertexInput) -> Output {
     let raw_id=in.material_index;
     let pack_id:i32=raw_id%100;
     let mat_id:i32=(raw_id-pack_id)/100;
-    var hull_meta_data=37;
-    if(pack_id==0){
-        hull_meta_data=vertex_meta_data0.ids[vertex_index];
-    }
-    if(pack_id==1){
-        hull_meta_data=vertex_meta_data1.ids[vertex_index];
-    }
-    if(pack_id==2){
-      hull_meta_data=vertex_meta_data2.ids[vertex_index];
-
-    }
-    if(pack_id==3){
-       hull_meta_data=vertex_meta_data3.ids[vertex_index];
-    }
-    if(pack_id==4){
-       hull_meta_data=vertex_meta_data4.ids[vertex_index];
-    }
-    if(pack_id==5){
-        hull_meta_data=vertex_meta_data5.ids[vertex_index];
-   }
-   if(pack_id==6){
-     hull_meta_data=vertex_meta_data6.ids[vertex_index];
-   }
-   if(pack_id==7){
-       hull_meta_data=vertex_meta_data7.ids[vertex_index];
-   }
-
+    let hull_meta_data:i32=vertex_meta_data.ids[vertex_index];
 
     var output: Output;
     output.originalpos= in.position;
@@ -135,20 +200,130 @@ fn vs_main(@builtin(vertex_index) vertex_index : u32,in:VertexInput) -> Output {
     output.position = camera.mvp  * in.position;
     output.world_position = in.position;
     output.world_normal = in.normal;
+    output.part_key = in.id;
+    return output;
+}
+
+fn compact_output(vertex_index : u32,in:CompactVertexInput) -> Output {
+    let position = vec4<f32>(quantization.origin.xyz + in.position.xyz * quantization.extent.xyz, 1.0);
+    let hull_meta_data:i32=vertex_meta_data.ids[vertex_index];
+
+    var output: Output;
+    output.originalpos= position;
+    output.mat_id=hull_meta_data;
+
+    output.position = camera.mvp  * position;
+    output.world_position = position;
+    output.world_normal = vec4<f32>(oct_decode(in.normal), 1.0);
+    output.part_key = compact_parts[in.part].x;
+    return output;
+}
+
+//metadata is per instance
+fn instanced_output(instance_index : u32,in:VertexInput,inst:InstanceInput) -> Output {
+    let model = mat4x4<f32>(inst.model_0, inst.model_1, inst.model_2, inst.model_3);
+    let normal_model = mat3x3<f32>(inst.normal_0.xyz, inst.normal_1.xyz, inst.normal_2.xyz);
+    let position = model * vec4<f32>(in.position.xyz, 1.0);
+    let hull_meta_data:i32=vertex_meta_data.ids[instance_index];
+
+    var output: Output;
+    output.originalpos= position;
+    output.mat_id=hull_meta_data;
+
+    output.position = camera.mvp  * position;
+    output.world_position = position;
+    output.world_normal = vec4<f32>(normalize(normal_model * in.normal.xyz), 1.0);
+    output.part_key = i32(instance_index);
+    return output;
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32,in:VertexInput) -> Output {
+    return mesh_output(vertex_index, in);
+}
+
+@vertex
+fn vs_compact(@builtin(vertex_index) vertex_index : u32,in:CompactVertexInput) -> Output {
+    return compact_output(vertex_index, in);
+}
+
+@vertex
+fn vs_instanced(@builtin(instance_index) instance_index : u32,in:VertexInput,inst:InstanceInput) -> Output {
+    return instanced_output(instance_index, in, inst);
+}
+
+//the same vertices seen from the sun, for the shadow map
+@vertex
+fn vs_shadow(@builtin(vertex_index) vertex_index : u32,in:VertexInput) -> Output {
+    var output = mesh_output(vertex_index, in);
+    output.position = sun.light_vp * output.world_position;
     return output;
 }
 
+@vertex
+fn vs_shadow_compact(@builtin(vertex_index) vertex_index : u32,in:CompactVertexInput) -> Output {
+    var output = compact_output(vertex_index, in);
+    output.position = sun.light_vp * output.world_position;
+    return output;
+}
+
+@vertex
+fn vs_shadow_instanced(@builtin(instance_index) instance_index : u32,in:VertexInput,inst:InstanceInput) -> Output {
+    var output = instanced_output(instance_index, in, inst);
+    output.position = sun.light_vp * output.world_position;
+    return output;
+}
+
+//hidden, cut and translucent parts cast no shadow
 @fragment
-fn fs_main(in:Output) ->  @location(0) vec4<f32> {
+fn fs_shadow(in:Output) {
     if(
     in.originalpos.x>slice.x_max || in.originalpos.x<slice.x_min
     || in.originalpos.y>slice.y_max || in.originalpos.y<slice.y_min
     || in.originalpos.z>slice.z_max || in.originalpos.z<slice.z_min
     ) { discard;};
+    if(clipped(in.originalpos.xyz)){discard;}
+    if(in.mat_id==0){discard;}
+    if(light_uniformsArray[in.mat_id].color.a<1.0){discard;}
+}
 
+//part of the light of the sun reaching p, 3x3 filtered
+fn shadow(p:vec3<f32>) -> f32 {
+    let c = sun.light_vp * vec4<f32>(p, 1.0);
+    let ndc = c.xyz / c.w;
+    let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    if(any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || ndc.z > 1.0){
+        return 1.0;
+    }
+    var lit = 0.0;
+    for(var y = -1; y <= 1; y++) {
+        for(var x = -1; x <= 1; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * sun.params.x;
+            lit = lit + textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, ndc.z - sun.params.y);
+        }
+    }
+    return lit / 9.0;
+}
 
-   if(in.mat_id!=0){
-      let material:LightUniforms=light_uniformsArray[in.mat_id];
+//diffuse light of the sun, plates are lit on the side seen from the eye
+fn sun_light(in:Output, material:LightUniforms) -> vec3<f32> {
+    if(sun.direction.w == 0.0){
+        return vec3<f32>(0.0);
+    }
+    let view_dir:vec3<f32> = normalize(camera_uniforms.eye_position.xyz - in.world_position.xyz);
+    var n:vec3<f32> = normalize(in.world_normal.xyz);
+    if(dot(n, view_dir) < 0.0){
+        n = -n;
+    }
+    let diffuse:f32 = max(dot(n, sun.direction.xyz), 0.0);
+    if(diffuse == 0.0){
+        return vec3<f32>(0.0);
+    }
+    return material.color.xyz * material.diffuse_intensity * diffuse * shadow(in.world_position.xyz) * sun.color.xyz;
+}
+
+//head light at the eye, ambient, diffuse and specular from the material
+fn head_light(in:Output, material:LightUniforms) -> vec4<f32> {
       let kd:f32=material.diffuse_intensity;
       let ks:f32=material.specular_intensity;
       let specular_factor:f32=material.specular_shininess;
@@ -163,20 +338,99 @@ fn fs_main(in:Output) ->  @location(0) vec4<f32> {
             let diffuse_color_head_light:vec4<f32> = diffuze_color * diffuse_strength_head_light;
             let specular_strength_head_light:f32 = pow(max(dot(in.world_normal.xyz, half_dir_head_light), 0.0), specular_factor);//8 is specular round
             let specular_color_head_light:vec4<f32> =light_color*specular_strength_head_light ;
-            let head_light_contribution:vec4<f32>=diffuse_color_head_light*kd + specular_color_head_light*ks;
+            let tint:vec4<f32> = vec4<f32>(camera_uniforms.light_color.xyz, 1.0);
+            return (diffuse_color_head_light*kd + specular_color_head_light*ks) * tint;
+}
 
-           if(diffuze_color.a==1.0){
-               return vec4<f32>(head_light_contribution.xyz,1.0);
-           }else{
-               return vec4<f32>(head_light_contribution);
-           }
+//opaque materials only, translucent ones are drawn by fs_translucent after them
+@fragment
+fn fs_main(in:Output) ->  MeshTargets {
+    if(
+    in.originalpos.x>slice.x_max || in.originalpos.x<slice.x_min
+    || in.originalpos.y>slice.y_max || in.originalpos.y<slice.y_min
+    || in.originalpos.z>slice.z_max || in.originalpos.z<slice.z_min
+    ) { discard;};
+    if(clipped(in.originalpos.xyz)){discard;}
+
+
+   if(in.mat_id!=0){
+      let material:LightUniforms=light_uniformsArray[in.mat_id];
+      if(material.color.a<1.0){discard;}
+      let head_light_contribution:vec4<f32>=head_light(in, material);
+
+           var out: MeshTargets;
+           out.edge = edge_data(in, normalize(in.world_normal.xyz));
+           out.color = vec4<f32>(head_light_contribution.xyz + sun_light(in, material),1.0);
+           return out;
    }
    else{discard;}
 
 
 
     //FOR WASM
-   return vec4<f32>(1.0,1.0,1.0,0.0);
+   return MeshTargets(vec4<f32>(1.0,1.0,1.0,0.0), vec4<f32>(0.0));
+}
+
+//weighted blended order independent transparency, summed colour and the product of (1 - alpha), see oit.wgsl
+struct OitTargets {
+    @location(0) accum : vec4<f32>,
+    @location(1) revealage : f32,
+};
+
+//translucent materials over the opaque depth, without writing it, so the order of packs does not matter
+@fragment
+fn fs_translucent(in:Output) ->  OitTargets {
+    if(
+    in.originalpos.x>slice.x_max || in.originalpos.x<slice.x_min
+    || in.originalpos.y>slice.y_max || in.originalpos.y<slice.y_min
+    || in.originalpos.z>slice.z_max || in.originalpos.z<slice.z_min
+    ) { discard;};
+    if(clipped(in.originalpos.xyz)){discard;}
+    if(in.mat_id==0){discard;}
+
+    let material:LightUniforms=light_uniformsArray[in.mat_id];
+    let alpha:f32=material.color.a;
+    if(alpha>=1.0){discard;}
+    let color:vec3<f32>=head_light(in, material).xyz + sun_light(in, material);
+    //nearer surfaces weigh more, the distance is in metres
+    let d:f32=distance(camera_uniforms.eye_position.xyz, in.world_position.xyz)*0.001;
+    let w:f32=alpha*clamp(10.0/(1e-5 + pow(d/5.0, 2.0) + pow(d/200.0, 6.0)), 1e-2, 3e3);
+    return OitTargets(vec4<f32>(color*alpha, alpha)*w, alpha);
+}
+
+//back faces seen through a cut, filled flat in screen space so the section reads like a drawing
+@fragment
+fn fs_cap(in:Output) ->  MeshTargets {
+    if(
+    in.originalpos.x>slice.x_max || in.originalpos.x<slice.x_min
+    || in.originalpos.y>slice.y_max || in.originalpos.y<slice.y_min
+    || in.originalpos.z>slice.z_max || in.originalpos.z<slice.z_min
+    ) { discard;};
+    if(clipped(in.originalpos.xyz)){discard;}
+    if(in.mat_id==0){discard;}
+    //translucent parts show their back faces anyway
+    if(light_uniformsArray[in.mat_id].color.a<1.0){discard;}
+
+    let style:CapStyle=cap_styles[in.mat_id];
+    let kind:i32=i32(style.hatch.x);
+    var is_line=false;
+    if(kind>=1){
+        is_line=hatch_line(in.position.xy, style.hatch.z, style.hatch.y, style.hatch.w);
+    }
+    if(kind==2){
+        is_line=is_line || hatch_line(in.position.xy, style.hatch.z + PI/2.0, style.hatch.y, style.hatch.w);
+    }
+    //no normal, so a cap only has edges where it meets the faces of its part
+    let edge = edge_data(in, vec3<f32>(0.0));
+    if(is_line){
+        return MeshTargets(vec4<f32>(style.color.xyz*0.35, 1.0), edge);
+    }
+    return MeshTargets(vec4<f32>(style.color.xyz, 1.0), edge);
+}
+
+fn hatch_line(uv:vec2<f32>, angle:f32, spacing:f32, width:f32) -> bool {
+    let d:f32=dot(uv, vec2<f32>(-sin(angle), cos(angle)));
+    return d - floor(d/spacing)*spacing < width;
 }
 
 /*
@@ -58,9 +58,29 @@ struct Slice {
     z_min:f32,
     z_minA:f32,
     z_minB:f32,
+    //ClipUniform: planes keep the side their normal points to, the section box keeps its inside
+    clip_count:vec4<i32>,
+    clip_planes:array<vec4<f32>, 8>,
+    box_center:vec4<f32>,
+    box_axes:array<vec4<f32>, 3>,
 };
 @binding(4) @group(0) var<uniform> slice : Slice;
 
+fn clipped(p:vec3<f32>) -> bool {
+    for(var i: i32 = 0; i < slice.clip_count.x; i++) {
+        let plane=slice.clip_planes[i];
+        if(dot(plane.xyz, p) + plane.w < 0.0){return true;}
+    }
+    if(slice.clip_count.y==1){
+        let d=p - slice.box_center.xyz;
+        for(var k: i32 = 0; k < 3; k++) {
+            let axis=slice.box_axes[k];
+            if(abs(dot(axis.xyz, d)) > axis.w){return true;}
+        }
+    }
+    return false;
+}
+
 struct SnapObject{
     p : vec4<f32>,
     p0 : vec4<f32>,
@@ -70,17 +90,6 @@ struct SnapObject{
 }
 @binding(5) @group(0) var<uniform> snap_object : SnapObject;
 
-struct VertexMetaData {
-     ids:array<i32>
-};
-@binding(6) @group(0) var<storage, read> vertex_meta_data0 : VertexMetaData;
-@binding(7) @group(0) var<storage, read> vertex_meta_data1 : VertexMetaData;
-@binding(8) @group(0) var<storage, read> vertex_meta_data2 : VertexMetaData;
-@binding(9) @group(0) var<storage, read> vertex_meta_data3 : VertexMetaData;
-@binding(10) @group(0) var<storage, read> vertex_meta_data4 : VertexMetaData;
-@binding(11) @group(0) var<storage, read> vertex_meta_data5 : VertexMetaData;
-@binding(12) @group(0) var<storage, read> vertex_meta_data6 : VertexMetaData;
-@binding(13) @group(0) var<storage, read> vertex_meta_data7 : VertexMetaData;
 
 struct Output {
     @builtin(position) position : vec4<f32>,
@@ -162,7 +171,10 @@ fn fs_main(in:Output) ->  @location(0) vec4<f32> {
 
          let uv:vec2<f32> = in.position.xy;
 
-        finalColor += ( circle(uv, c, 5.0, 3.0)+ circle(uv, c, 16.5, 1.5) ) * white1;
+        //a point left on clipped geometry is not shown
+        if(!clipped(snap_object.p.xyz)){
+            finalColor += ( circle(uv, c, 5.0, 3.0)+ circle(uv, c, 16.5, 1.5) ) * white1;
+        }
 
           switch dim_mode{
             case 1: {
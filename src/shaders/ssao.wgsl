@@ -0,0 +1,131 @@
+//screen space ambient occlusion of the hull, after ssao.frag: samples in a hemisphere around the pixel
+//are projected back to the screen and count as occluded when the surface there is nearer to the eye
+
+const KERNEL_SIZE:i32=16;
+
+struct SsaoUniforms {
+    vp : mat4x4<f32>,
+    inv_vp : mat4x4<f32>,
+    eye_position : vec4<f32>,
+    //x radius in model units, y strength, z blur radius px, w depth bias as a part of the radius
+    params : vec4<f32>,
+    //hemisphere around z, lengths up to 1
+    kernel : array<vec4<f32>, 16>,
+};
+
+@binding(0) @group(0) var<uniform> ssao : SsaoUniforms;
+//xy octahedral normal (2.0 if none), z distance to the eye, w part key + 1, 0 is background
+@binding(1) @group(0) var edge_buffer : texture_2d<f32>;
+//fs_ssao reads the depth buffer of the hull pass, fs_blur the occlusion written by fs_ssao
+@binding(2) @group(0) var depth_buffer : texture_depth_2d;
+@binding(3) @group(0) var occlusion_buffer : texture_2d<f32>;
+
+struct Output {
+    @builtin(position) position : vec4<f32>,
+};
+
+//one triangle over the screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32) -> Output {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: Output;
+    output.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return output;
+}
+
+fn oct_decode(e:vec2<f32>) -> vec3<f32> {
+    var n = vec3<f32>(e.x, e.y, 1.0 - abs(e.x) - abs(e.y));
+    let t = max(-n.z, 0.0);
+    n.x = n.x + select(t, -t, n.x >= 0.0);
+    n.y = n.y + select(t, -t, n.y >= 0.0);
+    return normalize(n);
+}
+
+//interleaved gradient noise, turns the kernel from pixel to pixel, the blur smooths the pattern out
+fn noise_angle(p:vec2<f32>) -> f32 {
+    return 6.28318530718 * fract(52.9829189 * fract(dot(p, vec2<f32>(0.06711056, 0.00583715))));
+}
+
+@fragment
+fn fs_ssao(in:Output) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(edge_buffer));
+    let p = vec2<i32>(in.position.xy);
+    let a = textureLoad(edge_buffer, p, 0);
+    let depth = textureLoad(depth_buffer, p, 0);
+    if(a.w == 0.0 || depth >= 1.0){
+        return vec4<f32>(1.0);
+    }
+    let radius = ssao.params.x;
+    //the depth buffer gives the ray of the pixel, the edge buffer its exact length, far depths are too coarse for it
+    let ndc = vec2<f32>(in.position.x / f32(size.x) * 2.0 - 1.0, 1.0 - in.position.y / f32(size.y) * 2.0);
+    let h = ssao.inv_vp * vec4<f32>(ndc, depth, 1.0);
+    let ray = normalize(h.xyz / h.w - ssao.eye_position.xyz);
+    let position = ssao.eye_position.xyz + ray * a.z;
+    //plates are seen from both sides and caps have no normal, the hemisphere is turned to the eye
+    var normal = -ray;
+    if(a.x < 1.5){
+        normal = oct_decode(a.xy);
+    }
+    if(dot(normal, ray) > 0.0){
+        normal = -normal;
+    }
+
+    let angle = noise_angle(in.position.xy);
+    var random = vec3<f32>(cos(angle), sin(angle), 0.0);
+    if(abs(normal.z) > 0.9){
+        random = vec3<f32>(0.0, cos(angle), sin(angle));
+    }
+    let tangent = normalize(random - normal * dot(random, normal));
+    let binormal = cross(normal, tangent);
+    let tbn = mat3x3<f32>(tangent, binormal, normal);
+
+    var occlusion = 0.0;
+    for(var i = 0; i < KERNEL_SIZE; i = i + 1){
+        let s = position + tbn * ssao.kernel[i].xyz * radius;
+        let c = ssao.vp * vec4<f32>(s, 1.0);
+        if(c.w <= 0.0){
+            continue;
+        }
+        let uv = c.xy / c.w;
+        let q = vec2<i32>(vec2<f32>((uv.x * 0.5 + 0.5) * f32(size.x), (0.5 - uv.y * 0.5) * f32(size.y)));
+        if(any(q < vec2<i32>(0)) || any(q >= size)){
+            continue;
+        }
+        let b = textureLoad(edge_buffer, q, 0);
+        if(b.w == 0.0){
+            continue;
+        }
+        //the sample lies on the ray of q, far surfaces in front of it do not count
+        if(b.z < distance(ssao.eye_position.xyz, s) - ssao.params.w * radius){
+            occlusion = occlusion + smoothstep(0.0, 1.0, radius / abs(a.z - b.z));
+        }
+    }
+    let ao = clamp(1.0 - ssao.params.y * occlusion / f32(KERNEL_SIZE), 0.0, 1.0);
+    return vec4<f32>(ao, ao, ao, 1.0);
+}
+
+//box blur that stays on surfaces at the same distance, the result multiplies the frame
+@fragment
+fn fs_blur(in:Output) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(edge_buffer));
+    let p = vec2<i32>(in.position.xy);
+    let a = textureLoad(edge_buffer, p, 0);
+    if(a.w == 0.0){
+        return vec4<f32>(1.0);
+    }
+    let r = i32(ssao.params.z);
+    var sum = 0.0;
+    var weight = 0.0;
+    for(var y = -r; y <= r; y = y + 1){
+        for(var x = -r; x <= r; x = x + 1){
+            let q = clamp(p + vec2<i32>(x, y), vec2<i32>(0), size - 1);
+            let b = textureLoad(edge_buffer, q, 0);
+            if(b.w != 0.0 && abs(b.z - a.z) < ssao.params.x){
+                sum = sum + textureLoad(occlusion_buffer, q, 0).x;
+                weight = weight + 1.0;
+            }
+        }
+    }
+    let ao = sum / max(weight, 1.0);
+    return vec4<f32>(ao, ao, ao, 1.0);
+}
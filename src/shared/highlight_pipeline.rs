@@ -1,15 +1,17 @@
 use std::mem::size_of;
 use std::rc::Rc;
 use parking_lot::RwLock;
-use wgpu::{BindGroup, BindGroupLayout, BlendFactor, BlendOperation, Buffer, BufferAddress, Device, Face, FrontFace, PipelineLayout, RenderPipeline, TextureFormat};
+use wgpu::{BindGroup, BindGroupLayout, BlendFactor, BlendOperation, Buffer, BufferAddress, Device, Face, FrontFace, PipelineLayout, Queue, RenderPipeline, TextureFormat};
 use crate::shared::materials_lib::{Material, MATERIALS_COUNT};
-use crate::shared::mesh_common::MeshVertex;
+use crate::shared::mesh_common::{MeshInstance, MeshVertex};
 
 
 pub struct HighlightPipeLine {
     uniform_bind_group_layout: BindGroupLayout,
     pub hl_uniform_bind_group: BindGroup,
     pub hl_render_pipeline: RenderPipeline,
+    //same for packs in the instanced layout
+    pub hl_instanced_render_pipeline: RenderPipeline,
     pub render_mode: i32,
     pub camera_uniform_buffer: Buffer,
     pub material_uniform_buffer: Buffer,
@@ -17,6 +19,8 @@ pub struct HighlightPipeLine {
     pub mode_uniform_buffer: Buffer,
     pub slice_uniform_buffer: Buffer,
     pub high_light_cab_nodes_buffer: Buffer,
+    //points in high_light_cab_nodes_buffer, nothing is highlighted when 0
+    pub cab_nodes_count: usize,
 }
 
 impl HighlightPipeLine {
@@ -169,14 +173,14 @@ impl HighlightPipeLine {
             bind_group_layouts: &[&uniform_bind_group_layout],
             push_constant_ranges: &[],
         });
-        let hl_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("HL Pipeline"),
+        let create_pipeline = |label: &str, entry_point: &str, buffers: &[wgpu::VertexBufferLayout]| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
             layout: Some(&render_pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
-                entry_point: Some("vs_main"),
+                entry_point: Some(entry_point),
                 compilation_options: Default::default(),
-                buffers: &[MeshVertex::desc()],
+                buffers: buffers,
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
@@ -215,11 +219,14 @@ impl HighlightPipeLine {
             multiview: None,
             cache: None,
         });
+        let hl_render_pipeline: RenderPipeline = create_pipeline("HL Pipeline", "vs_main", &[MeshVertex::desc()]);
+        let hl_instanced_render_pipeline: RenderPipeline = create_pipeline("HL Instanced Pipeline", "vs_instanced", &[MeshVertex::desc(), MeshInstance::desc()]);
 
         Self {
             uniform_bind_group_layout: uniform_bind_group_layout,
             hl_uniform_bind_group: hl_uniform_bind_group,
             hl_render_pipeline: hl_render_pipeline,
+            hl_instanced_render_pipeline: hl_instanced_render_pipeline,
             render_mode: 0,
             camera_uniform_buffer: camera_uniform_buffer,
             material_uniform_buffer: material_uniform_buffer,
@@ -227,7 +234,7 @@ impl HighlightPipeLine {
             mode_uniform_buffer: mode_uniform_buffer,
             slice_uniform_buffer: slice_uniform_buffer,
             high_light_cab_nodes_buffer: high_light_cab_nodes_buffer,
-
+            cab_nodes_count: 0,
         }
     }
 
@@ -280,4 +287,23 @@ impl HighlightPipeLine {
 
 
     }
-}
\ No newline at end of file
+
+    //points lit by the highlight shader, see HighLightCabNodes in highlight_shader.wgsl
+    pub fn set_cab_nodes(&mut self, device: &Device, queue: &Queue, points: &[[f32; 4]]) {
+        self.fit_cab_buffer(device, points.len());
+        if (!points.is_empty()) {
+            queue.write_buffer(&self.high_light_cab_nodes_buffer, 0, bytemuck::cast_slice(points));
+        }
+        self.cab_nodes_count = points.len();
+    }
+
+    //the pipeline has its own uniforms, same layout as the mesh pipeline ones
+    pub fn update_uniforms(&self, queue: &Queue, mvp: &[f32; 16], dirs: &[f32; 16], forward: &[f32; 3], eye_position: &[f32; 3], materials: &Vec<Material>) {
+        queue.write_buffer(&self.camera_uniform_buffer, 0, bytemuck::cast_slice(mvp));
+        queue.write_buffer(&self.camera_uniform_buffer, 64, bytemuck::cast_slice(dirs));
+        queue.write_buffer(&self.camera_uniform_buffer, 128, bytemuck::cast_slice(forward));
+        queue.write_buffer(&self.light_uniform_buffer, 0, bytemuck::cast_slice(eye_position));
+        queue.write_buffer(&self.light_uniform_buffer, 16, bytemuck::cast_slice(eye_position));
+        queue.write_buffer(&self.material_uniform_buffer, 0, bytemuck::cast_slice(materials));
+    }
+}
@@ -9,6 +9,9 @@ pub const PIPE_TY_MIN: i32 = 20;
 pub const PIPE_TY_MAX: i32 = 40;
 pub const EQ_TY_MIN: i32 = 80;
 pub const EQ_TY_MAX: i32 = 100;
+//cable network parts, a node of type t is CABLE_TY_MIN + t, links between nodes of different types are CABLE_TY_MIN
+pub const CABLE_TY_MIN: i32 = 200;
+pub const CABLE_TY_MAX: i32 = 220;
 
 pub const TY_HULL_PROFILES: i32 = 74;
 pub const TY_HULL_PLATES: i32 = 84;
@@ -17,7 +20,10 @@ pub const TY_HULL_OTHERS: i32 = 15;
 pub const MATERIALS_COUNT: usize = 140;
 pub const SELECTION_HULL_MAT: i32 = 1;
 pub const HIDDEN_HULL_MAT: i32 = 0;
+//OPENCOLORS shades follow the hidden, selection and 8 spare materials
+const PALETTE_FIRST_MAT: usize = 10;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HullPartTypes {
     _ShellLongitudinal = 0,
     Decklongitudinal = 1,
@@ -56,6 +62,27 @@ pub enum HullPartTypes {
     Importedpart = 38,
 }
 
+impl HullPartTypes {
+    pub const ALL: [HullPartTypes; 35] = [
+        HullPartTypes::_ShellLongitudinal, HullPartTypes::Decklongitudinal, HullPartTypes::_Hullframe, HullPartTypes::Deckbeam,
+        HullPartTypes::Stifflongbulkhead, HullPartTypes::Stifftransbulkhead, HullPartTypes::Generalprofile, HullPartTypes::_Foundationprofil,
+        HullPartTypes::Internalplate, HullPartTypes::_Shellplate, HullPartTypes::Deckplate, HullPartTypes::Templateplate,
+        HullPartTypes::_Foundationplate, HullPartTypes::Shellmarkingplate, HullPartTypes::Corrugatedplate, HullPartTypes::_InternalPlateFHull,
+        HullPartTypes::_InternalProfileFHull, HullPartTypes::_InternalLCFHull, HullPartTypes::_InternalStandardPlateFHull, HullPartTypes::_InternalStraightProfileFHull,
+        HullPartTypes::_InternalCollarPlateFHull, HullPartTypes::_InternalGridProfile, HullPartTypes::_InternalBracketFHull, HullPartTypes::_InternalMacroPlateFHull,
+        HullPartTypes::_InternalCurvedProfileFHull, HullPartTypes::InternalCorrugatedStandardPlate, HullPartTypes::InternalCorrugatedMacroPlate, HullPartTypes::DevelopedShellorDeckPlate,
+        HullPartTypes::ShellorDeckBendingTemplate, HullPartTypes::JigBasePart, HullPartTypes::Straightweb, HullPartTypes::Straightflange,
+        HullPartTypes::Shapedweb, HullPartTypes::Shapedflange, HullPartTypes::Importedpart,
+    ];
+    pub fn from_code(code: i32) -> Option<HullPartTypes> {
+        HullPartTypes::ALL.iter().find(|t| **t as i32 == code).copied()
+    }
+    //variant name without the leading underscore of unused types
+    pub fn name(&self) -> String {
+        format!("{:?}", self).trim_start_matches('_').to_string()
+    }
+}
+
 pub static OPENCOLORS: phf::Map<&'static str, &'static [u32; 10]> = phf_map! {
 "gray"=>&[0xf8f9fa,0xf1f3f5,0xe9ecef,0xdee2e6,0xced4da,0xadb5bd,0x868e96,0x495057,0x343a40,0x212529],
 "red"=>&[0xfff5f5,0xffe3e3,0xffc9c9,0xffa8a8,0xff8787,0xff6b6b,0xfa5252,0xf03e3e,0xe03131,0xc92a2a],
@@ -126,8 +153,29 @@ impl Material {
         });
         ret
     }
+    //material of an OPENCOLORS shade, e.g. ("red", 6), same order as generate_materials
+    pub fn palette_index(group: &str, shade: usize) -> Option<i32> {
+        if (shade >= 10) {
+            return None;
+        }
+        OPENCOLORS.keys().position(|k| *k == group).map(|n| (PALETTE_FIRST_MAT + n * 10 + shade) as i32)
+    }
+    //node t_ype/typeid of the cable network
+    pub fn cable_color(cable_ty: i32) -> i32 {
+        let (group, shade) = match cable_ty {
+            0 => ("gray", 6),
+            1 => ("red", 7),
+            2 => ("blue", 7),
+            3 => ("green", 7),
+            4 => ("yellow", 7),
+            5 => ("violet", 7),
+            _ => ("orange", 7),
+        };
+        Material::palette_index(group, shade).unwrap_or(TY_HULL_OTHERS)
+    }
     pub fn type_to_color(ty: i32) -> i32 {
         match ty {
+            CABLE_TY_MIN..=CABLE_TY_MAX => Material::cable_color(ty - CABLE_TY_MIN),
             0 | 2 | 7 | 16 | 19 | 21 | 24 | 17 => TY_HULL_PROFILES, //PROFILES
             8 | 12 | 15 | 18 | 20 | 22 | 23 => TY_HULL_PLATES,//PLATES
             9 => TY_HULL_OUTERPLATES,//HULL PLATES
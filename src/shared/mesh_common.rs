@@ -38,6 +38,202 @@ impl MeshVertex {
 
 }
 
+//Compact layout: vertices are shared inside a part and drawn by index,
+//position is quantized to u16 over the pack Quantization box, normal is octahedral snorm16,
+//part is the index into the pack PartEntry table (same order as the hash and bbx streams)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct CompactVertex {
+    pub position: [u16; 4],
+    pub normal: [i16; 2],
+    pub part: u32,
+}
+
+impl CompactVertex {
+    pub fn new(q: &Quantization, p: [f32; 3], n: [f32; 3], part: u32) -> Self {
+        let qp = q.quantize(p);
+        Self {
+            position: [qp[0], qp[1], qp[2], 0],
+            normal: oct_encode(n),
+            part: part,
+        }
+    }
+    pub fn position(&self, q: &Quantization) -> [f32; 3] {
+        q.dequantize([self.position[0], self.position[1], self.position[2]])
+    }
+    pub fn normal(&self) -> [f32; 3] {
+        oct_decode(self.normal)
+    }
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![0=>Unorm16x4, 1=>Snorm16x2, 2=>Uint32];
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<CompactVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+//id and material_index of a part, what MeshVertex repeats in every vertex
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct PartEntry {
+    pub id: i32,
+    pub material_index: i32,
+}
+
+//Pack box the compact positions are quantized over, position = origin + q/65535 * extent
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct Quantization {
+    pub origin: [f32; 4],
+    pub extent: [f32; 4],
+}
+
+impl Quantization {
+    pub fn from_bbx(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self {
+            origin: [min[0], min[1], min[2], 1.0],
+            extent: [(max[0] - min[0]).max(0.0), (max[1] - min[1]).max(0.0), (max[2] - min[2]).max(0.0), 0.0],
+        }
+    }
+    pub fn quantize(&self, p: [f32; 3]) -> [u16; 3] {
+        [0, 1, 2].map(|k| {
+            if (self.extent[k] > 0.0) {
+                (((p[k] - self.origin[k]) / self.extent[k]).clamp(0.0, 1.0) * 65535.0).round() as u16
+            } else {
+                0
+            }
+        })
+    }
+    pub fn dequantize(&self, q: [u16; 3]) -> [f32; 3] {
+        [0, 1, 2].map(|k| self.origin[k] + q[k] as f32 / 65535.0 * self.extent[k])
+    }
+}
+
+pub fn oct_encode(n: [f32; 3]) -> [i16; 2] {
+    let l1 = n[0].abs() + n[1].abs() + n[2].abs();
+    if (l1 == 0.0) {
+        return [0, 0];
+    }
+    let (mut x, mut y) = (n[0] / l1, n[1] / l1);
+    if (n[2] < 0.0) {
+        let (ox, oy) = (x, y);
+        x = (1.0 - oy.abs()) * if (ox >= 0.0) { 1.0 } else { -1.0 };
+        y = (1.0 - ox.abs()) * if (oy >= 0.0) { 1.0 } else { -1.0 };
+    }
+    [(x.clamp(-1.0, 1.0) * 32767.0).round() as i16, (y.clamp(-1.0, 1.0) * 32767.0).round() as i16]
+}
+
+//same as oct_decode in shader.wgsl
+pub fn oct_decode(e: [i16; 2]) -> [f32; 3] {
+    let x = (e[0] as f32 / 32767.0).max(-1.0);
+    let y = (e[1] as f32 / 32767.0).max(-1.0);
+    let z = 1.0 - x.abs() - y.abs();
+    let t = (-z).max(0.0);
+    let x = if (x >= 0.0) { x - t } else { x + t };
+    let y = if (y >= 0.0) { y - t } else { y + t };
+    let l = (x * x + y * y + z * z).sqrt();
+    if (l == 0.0) {
+        [1.0, 0.0, 0.0]
+    } else {
+        [x / l, y / l, z / l]
+    }
+}
+
+//Vertices of a pack in one of the three layouts
+pub enum HullVertices {
+    Full(Vec<MeshVertex>),
+    Compact(CompactMesh),
+    Instanced(InstancedMesh),
+}
+
+pub struct CompactMesh {
+    pub v: Vec<CompactVertex>,
+    pub parts: Vec<PartEntry>,
+    pub quantization: Quantization,
+}
+
+//Instanced layout: template meshes in MeshVertex are drawn once per instance, moved by its transform.
+//Instances of a template follow each other, so one draw takes all of them. A part is one or more instances
+//in a row, parts[k] is the part of instance k and metadata is per instance
+pub struct InstancedMesh {
+    pub v: Vec<MeshVertex>,
+    pub templates: Vec<MeshTemplate>,
+    pub instances: Vec<MeshInstance>,
+    pub parts: Vec<PartEntry>,
+}
+
+//vertices and instances of one template, ranges of the instanced draw
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MeshTemplate {
+    pub first_vertex: u32,
+    pub vertex_count: u32,
+    pub first_instance: u32,
+    pub instance_count: u32,
+}
+
+//model is the column major transform, normal_model the inverse transpose of its 3x3.
+//base_vertex + vertex index is the index the vertex would have in the Full layout, the selection pass
+//writes it so picking reads instances the same way as expanded triangles
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct MeshInstance {
+    pub model: [[f32; 4]; 4],
+    pub normal_model: [[f32; 4]; 3],
+    pub base_vertex: i32,
+    pub template: u32,
+    pub _pad: [u32; 2],
+}
+
+impl MeshInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![4=>Float32x4, 5=>Float32x4, 6=>Float32x4, 7=>Float32x4, 8=>Float32x4, 9=>Float32x4, 10=>Float32x4, 11=>Sint32];
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<MeshInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+    pub fn transform(&self, p: [f32; 4]) -> [f32; 3] {
+        let m = &self.model;
+        [0, 1, 2].map(|r| m[0][r] * p[0] + m[1][r] * p[1] + m[2][r] * p[2] + m[3][r])
+    }
+}
+
+impl InstancedMesh {
+    //first index of the instance in the Full layout
+    fn first_vertex_of(&self, instance: usize) -> i64 {
+        let inst = &self.instances[instance];
+        inst.base_vertex as i64 + self.templates[inst.template as usize].first_vertex as i64
+    }
+    //instance and the vertex of its template under a vertex index written by the selection pass
+    pub fn find(&self, vertex_index: usize) -> Option<(usize, usize)> {
+        //first vertices grow with the instance index, k is the first instance past vertex_index
+        let (mut k, mut hi) = (0, self.instances.len());
+        while (k < hi) {
+            let mid = (k + hi) / 2;
+            if (self.first_vertex_of(mid) <= vertex_index as i64) { k = mid + 1; } else { hi = mid; }
+        }
+        if (k == 0) {
+            return None;
+        }
+        let local = (vertex_index as i64 - self.first_vertex_of(k - 1)) as usize;
+        let template = self.templates.get(self.instances[k - 1].template as usize)?;
+        if (local < template.vertex_count as usize) { Some((k - 1, local)) } else { None }
+    }
+    pub fn part_by_vertex_index(&self, vertex_index: usize) -> Option<&PartEntry> {
+        self.find(vertex_index).and_then(|(k, _local)| self.parts.get(k))
+    }
+    //corners of the picked triangle moved by its instance
+    pub fn triangle_by_vertex_index(&self, vertex_index: usize) -> Option<(i32, [[f32; 3]; 3])> {
+        let (k, local) = self.find(vertex_index)?;
+        let inst = &self.instances[k];
+        let first = self.templates[inst.template as usize].first_vertex as usize + local - local % 3;
+        let corners = self.v.get(first..first + 3)?;
+        Some((self.parts[k].id, [0, 1, 2].map(|c| inst.transform(corners[c].position))))
+    }
+}
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
@@ -1,13 +1,37 @@
 use std::rc::Rc;
 use parking_lot::{RwLock};
-use wgpu::{BindGroup, BindGroupLayout, BlendFactor, BlendOperation, Device, Face, FrontFace, PipelineLayout, RenderPipeline, TextureFormat};
-use crate::shared::mesh_common::{MeshVertex, SnapVertex};
+use wgpu::{BindGroup, BindGroupLayout, BlendFactor, Buffer, BlendOperation, Device, Face, FrontFace, PipelineLayout, RenderPipeline, TextureFormat};
+use crate::shared::mesh_common::{CompactVertex, MeshInstance, MeshVertex, SnapVertex};
+use crate::shared::oit_pipeline::{ACCUM_BUFFER_FORMAT, REVEALAGE_BUFFER_FORMAT};
+use crate::shared::outline_pipeline::EDGE_BUFFER_FORMAT;
+use crate::shared::shadow_map::{ShadowMap, SHADOW_MAP_FORMAT};
 use crate::shared::shared_buffers::SharedBuffers;
 
 pub struct MeshPipeLine {
     mesh_bind_group_layout: BindGroupLayout,
+    metadata_bind_group_layout: BindGroupLayout,
     pub mesh_render_pipeline: RenderPipeline,
 
+    compact_bind_group_layout: BindGroupLayout,
+    pub compact_mesh_render_pipeline: RenderPipeline,
+    pub compact_selection_render_pipeline: RenderPipeline,
+
+    pub instanced_mesh_render_pipeline: RenderPipeline,
+    pub instanced_selection_render_pipeline: RenderPipeline,
+
+    pub cap_render_pipeline: RenderPipeline,
+    pub compact_cap_render_pipeline: RenderPipeline,
+    pub instanced_cap_render_pipeline: RenderPipeline,
+
+    pub translucent_render_pipeline: RenderPipeline,
+    pub compact_translucent_render_pipeline: RenderPipeline,
+    pub instanced_translucent_render_pipeline: RenderPipeline,
+
+    pub shadow_map: ShadowMap,
+    pub shadow_render_pipeline: RenderPipeline,
+    pub compact_shadow_render_pipeline: RenderPipeline,
+    pub instanced_shadow_render_pipeline: RenderPipeline,
+
     snap_bind_group_layout: BindGroupLayout,
     pub snap_render_pipeline: RenderPipeline,
 
@@ -89,9 +113,9 @@ impl MeshPipeLine {
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 6,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
                         min_binding_size: None,
                     },
@@ -99,59 +123,22 @@ impl MeshPipeLine {
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 7,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 8,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 9,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
-                wgpu::BindGroupLayoutEntry {
-                    binding: 10,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
-                wgpu::BindGroupLayoutEntry {
-                    binding: 11,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
                         min_binding_size: None,
                     },
                     count: None,
                 },
-
+            ],
+            label: Some("mesh Bind Group Layout"),
+        });
+        //group 1 is set per pack, so the count of packs does not touch shaders
+        let metadata_bind_group_layout: BindGroupLayout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
                 wgpu::BindGroupLayoutEntry {
-                    binding: 12,
+                    binding: 0,
                     visibility: wgpu::ShaderStages::VERTEX,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Storage { read_only: true },
@@ -160,52 +147,194 @@ impl MeshPipeLine {
                     },
                     count: None,
                 },
+            ],
+            label: Some("metadata Bind Group Layout"),
+        });
+        //group 2 is the shadow map, the shadow pass draws into it with the layouts without it
+        let shadow_map = ShadowMap::new(&device);
+        let mesh_pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Render Pipeline Layout"),
+            bind_group_layouts: &[&mesh_bind_group_layout, &metadata_bind_group_layout, &shadow_map.bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let mesh_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Render Pipeline"),
+            layout: Some(&mesh_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &mesh_shader,
+                entry_point:Some("vs_main") ,
+                compilation_options: Default::default(),
+                buffers: &[MeshVertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &mesh_shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: format,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor: BlendFactor::SrcAlpha,
+                                dst_factor: BlendFactor::OneMinusSrcAlpha,
+                                operation: BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent::OVER,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: EDGE_BUFFER_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::default(),
+                cull_mode: Some(Face::Back),
+                unclipped_depth: false,
+                polygon_mode: Default::default(),
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
 
+        //COMPACT MESH PIPELINE
+        //group 1 of a compact pack: metadata, quantization, part table and, for the selection pass, vertices and indices
+        let storage_entry = |binding: u32| wgpu::BindGroupLayoutEntry {
+            binding: binding,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let compact_bind_group_layout: BindGroupLayout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                storage_entry(0),
                 wgpu::BindGroupLayoutEntry {
-                    binding: 13,
+                    binding: 1,
                     visibility: wgpu::ShaderStages::VERTEX,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
                         min_binding_size: None,
                     },
                     count: None,
                 },
-
-
+                storage_entry(2),
+                storage_entry(3),
+                storage_entry(4),
             ],
-            label: Some("mesh Bind Group Layout"),
+            label: Some("compact Bind Group Layout"),
         });
-        let mesh_pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Mesh Render Pipeline Layout"),
-            bind_group_layouts: &[&mesh_bind_group_layout],
+        let compact_mesh_pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Compact Mesh Render Pipeline Layout"),
+            bind_group_layouts: &[&mesh_bind_group_layout, &compact_bind_group_layout, &shadow_map.bind_group_layout],
             push_constant_ranges: &[],
         });
-        let mesh_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Mesh Render Pipeline"),
+        let compact_mesh_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Compact Mesh Render Pipeline"),
+            layout: Some(&compact_mesh_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &mesh_shader,
+                entry_point: Some("vs_compact"),
+                compilation_options: Default::default(),
+                buffers: &[CompactVertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &mesh_shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: format,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor: BlendFactor::SrcAlpha,
+                                dst_factor: BlendFactor::OneMinusSrcAlpha,
+                                operation: BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent::OVER,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: EDGE_BUFFER_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::default(),
+                cull_mode: Some(Face::Back),
+                unclipped_depth: false,
+                polygon_mode: Default::default(),
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        //INSTANCED MESH PIPELINE
+        //templates and instances as two vertex buffers, metadata is indexed by instance
+        let instanced_mesh_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Instanced Mesh Render Pipeline"),
             layout: Some(&mesh_pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &mesh_shader,
-                entry_point:Some("vs_main") ,
+                entry_point: Some("vs_instanced"),
                 compilation_options: Default::default(),
-                buffers: &[MeshVertex::desc()],
+                buffers: &[MeshVertex::desc(), MeshInstance::desc()],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &mesh_shader,
                 entry_point: Some("fs_main"),
                 compilation_options: Default::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent {
-                            src_factor: BlendFactor::SrcAlpha,
-                            dst_factor: BlendFactor::OneMinusSrcAlpha,
-                            operation: BlendOperation::Add,
-                        },
-                        alpha: wgpu::BlendComponent::OVER,
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: format,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor: BlendFactor::SrcAlpha,
+                                dst_factor: BlendFactor::OneMinusSrcAlpha,
+                                operation: BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent::OVER,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
                     }),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
+                    Some(wgpu::ColorTargetState {
+                        format: EDGE_BUFFER_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
             }),
             primitive: wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleList,
@@ -295,90 +424,6 @@ impl MeshPipeLine {
                     },
                     count: None,
                 },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 6,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 7,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 8,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 9,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 10,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
-                wgpu::BindGroupLayoutEntry {
-                    binding: 11,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
-                wgpu::BindGroupLayoutEntry {
-                    binding: 12,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
-                wgpu::BindGroupLayoutEntry {
-                    binding: 13,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
             ],
             label: Some("snap Bind Group Layout"),
         });
@@ -501,96 +546,12 @@ impl MeshPipeLine {
                     },
                     count: None,
                 },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 6,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 7,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 8,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 9,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 10,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
-                wgpu::BindGroupLayoutEntry {
-                    binding: 11,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
-                wgpu::BindGroupLayoutEntry {
-                    binding: 12,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
-                wgpu::BindGroupLayoutEntry {
-                    binding: 13,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-
             ],
             label: Some("sel Bind Group Layout"),
         });
         let selection_pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Selection Pipeline Layout"),
-            bind_group_layouts: &[&selection_bind_group_layout],
+            bind_group_layouts: &[&selection_bind_group_layout, &metadata_bind_group_layout],
             push_constant_ranges: &[],
         });
         let selection_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -635,10 +596,294 @@ impl MeshPipeLine {
             cache: None,
         });
 
+        //SECTION CAP PIPELINES
+        //back faces only, they are seen where a cut opens a part, see fs_cap in shader.wgsl
+        let cap_pipeline = |label: &str, layout: &PipelineLayout, entry_point: &str, buffers: &[wgpu::VertexBufferLayout]| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &mesh_shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                buffers: buffers,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &mesh_shader,
+                entry_point: Some("fs_cap"),
+                compilation_options: Default::default(),
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: EDGE_BUFFER_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::default(),
+                cull_mode: Some(Face::Front),
+                unclipped_depth: false,
+                polygon_mode: Default::default(),
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let cap_render_pipeline: RenderPipeline = cap_pipeline("Cap Render Pipeline", &mesh_pipeline_layout, "vs_main", &[MeshVertex::desc()]);
+        let compact_cap_render_pipeline: RenderPipeline = cap_pipeline("Compact Cap Render Pipeline", &compact_mesh_pipeline_layout, "vs_compact", &[CompactVertex::desc()]);
+        let instanced_cap_render_pipeline: RenderPipeline = cap_pipeline("Instanced Cap Render Pipeline", &mesh_pipeline_layout, "vs_instanced", &[MeshVertex::desc(), MeshInstance::desc()]);
+
+        //TRANSLUCENT PIPELINES
+        //weighted blended transparency after the opaque pass: depth tested but not written, see fs_translucent in shader.wgsl
+        let translucent_pipeline = |label: &str, layout: &PipelineLayout, entry_point: &str, buffers: &[wgpu::VertexBufferLayout]| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &mesh_shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                buffers: buffers,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &mesh_shader,
+                entry_point: Some("fs_translucent"),
+                compilation_options: Default::default(),
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: ACCUM_BUFFER_FORMAT,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor: BlendFactor::One,
+                                dst_factor: BlendFactor::One,
+                                operation: BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent {
+                                src_factor: BlendFactor::One,
+                                dst_factor: BlendFactor::One,
+                                operation: BlendOperation::Add,
+                            },
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: REVEALAGE_BUFFER_FORMAT,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor: BlendFactor::Zero,
+                                dst_factor: BlendFactor::OneMinusSrc,
+                                operation: BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent::REPLACE,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::default(),
+                cull_mode: Some(Face::Back),
+                unclipped_depth: false,
+                polygon_mode: Default::default(),
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let translucent_render_pipeline: RenderPipeline = translucent_pipeline("Translucent Render Pipeline", &mesh_pipeline_layout, "vs_main", &[MeshVertex::desc()]);
+        let compact_translucent_render_pipeline: RenderPipeline = translucent_pipeline("Compact Translucent Render Pipeline", &compact_mesh_pipeline_layout, "vs_compact", &[CompactVertex::desc()]);
+        let instanced_translucent_render_pipeline: RenderPipeline = translucent_pipeline("Instanced Translucent Render Pipeline", &mesh_pipeline_layout, "vs_instanced", &[MeshVertex::desc(), MeshInstance::desc()]);
+
+        //SHADOW PIPELINES
+        //depth only, seen from the sun; both faces so thin plates cast shadows whichever side faces it
+        let shadow_pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&mesh_bind_group_layout, &metadata_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let compact_shadow_pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Compact Shadow Pipeline Layout"),
+            bind_group_layouts: &[&mesh_bind_group_layout, &compact_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shadow_pipeline = |label: &str, layout: &PipelineLayout, entry_point: &str, buffers: &[wgpu::VertexBufferLayout]| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &mesh_shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                buffers: buffers,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &mesh_shader,
+                entry_point: Some("fs_shadow"),
+                compilation_options: Default::default(),
+                targets: &[],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::default(),
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: Default::default(),
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: SHADOW_MAP_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let shadow_render_pipeline: RenderPipeline = shadow_pipeline("Shadow Render Pipeline", &shadow_pipeline_layout, "vs_shadow", &[MeshVertex::desc()]);
+        let compact_shadow_render_pipeline: RenderPipeline = shadow_pipeline("Compact Shadow Render Pipeline", &compact_shadow_pipeline_layout, "vs_shadow_compact", &[CompactVertex::desc()]);
+        let instanced_shadow_render_pipeline: RenderPipeline = shadow_pipeline("Instanced Shadow Render Pipeline", &shadow_pipeline_layout, "vs_shadow_instanced", &[MeshVertex::desc(), MeshInstance::desc()]);
+
+        //COMPACT SELECTION PIPELINE
+        //no vertex buffers, vs_compact reads vertices through the index storage buffer
+        let compact_selection_pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Compact Selection Pipeline Layout"),
+            bind_group_layouts: &[&selection_bind_group_layout, &compact_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let compact_selection_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Compact Selection Pipeline"),
+            layout: Some(&compact_selection_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &selection_shader,
+                entry_point: Some("vs_compact"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &selection_shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: TextureFormat::Rgba32Sint,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::default(),
+                cull_mode: Some(Face::Back),
+                unclipped_depth: false,
+                polygon_mode: Default::default(),
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        //INSTANCED SELECTION PIPELINE
+        let instanced_selection_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Instanced Selection Pipeline"),
+            layout: Some(&selection_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &selection_shader,
+                entry_point: Some("vs_instanced"),
+                compilation_options: Default::default(),
+                buffers: &[MeshVertex::desc(), MeshInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &selection_shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: TextureFormat::Rgba32Sint,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::default(),
+                cull_mode: Some(Face::Back),
+                unclipped_depth: false,
+                polygon_mode: Default::default(),
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
 
         Self {
             mesh_bind_group_layout,
+            metadata_bind_group_layout: metadata_bind_group_layout,
             mesh_render_pipeline: mesh_render_pipeline,
+            compact_bind_group_layout: compact_bind_group_layout,
+            compact_mesh_render_pipeline: compact_mesh_render_pipeline,
+            compact_selection_render_pipeline: compact_selection_render_pipeline,
+            instanced_mesh_render_pipeline: instanced_mesh_render_pipeline,
+            instanced_selection_render_pipeline: instanced_selection_render_pipeline,
+            cap_render_pipeline: cap_render_pipeline,
+            compact_cap_render_pipeline: compact_cap_render_pipeline,
+            instanced_cap_render_pipeline: instanced_cap_render_pipeline,
+            translucent_render_pipeline: translucent_render_pipeline,
+            compact_translucent_render_pipeline: compact_translucent_render_pipeline,
+            instanced_translucent_render_pipeline: instanced_translucent_render_pipeline,
+            shadow_map: shadow_map,
+            shadow_render_pipeline: shadow_render_pipeline,
+            compact_shadow_render_pipeline: compact_shadow_render_pipeline,
+            instanced_shadow_render_pipeline: instanced_shadow_render_pipeline,
             snap_bind_group_layout: snap_bind_group_layout,
             snap_render_pipeline: snap_render_pipeline,
             selection_bind_group_layout: selection_bind_group_layout,
@@ -653,14 +898,8 @@ impl MeshPipeLine {
         let mode_buffer = shred_buffers.mode_buffer.clone();
         let slice_buffer = shred_buffers.slice_buffer.clone();
         let snap_buffer = shred_buffers.snap_buffer.clone();
-        let metadata_buffer0 = shred_buffers.metadata_buffer0.clone();
-        let metadata_buffer1 = shred_buffers.metadata_buffer1.clone();
-        let metadata_buffer2 = shred_buffers.metadata_buffer2.clone();
-        let metadata_buffer3 = shred_buffers.metadata_buffer3.clone();
-        let metadata_buffer4 = shred_buffers.metadata_buffer4.clone();
-        let metadata_buffer5 = shred_buffers.metadata_buffer5.clone();
-        let metadata_buffer6 = shred_buffers.metadata_buffer6.clone();
-        let metadata_buffer7 = shred_buffers.metadata_buffer7.clone();
+        let cap_buffer = shred_buffers.cap_buffer.clone();
+        let sun_buffer = shred_buffers.sun_buffer.clone();
 
         let mesh_uniform_bind_group: BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &self.mesh_bind_group_layout,
@@ -691,40 +930,60 @@ impl MeshPipeLine {
                 },
                 wgpu::BindGroupEntry {
                     binding: 6,
-                    resource: metadata_buffer0.read().as_entire_binding(),
+                    resource: cap_buffer.read().as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 7,
-                    resource: metadata_buffer1.read().as_entire_binding(),
+                    resource: sun_buffer.read().as_entire_binding(),
                 },
+            ],
+            label: Some("Mesh Bind Group"),
+        });
+        mesh_uniform_bind_group
+    }
+
+    pub fn bind_metadata_group(&self, device: &Device, metadata_buffer: &Buffer) -> BindGroup {
+        let metadata_bind_group: BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.metadata_bind_group_layout,
+            entries: &[
                 wgpu::BindGroupEntry {
-                    binding: 8,
-                    resource: metadata_buffer2.read().as_entire_binding(),
+                    binding: 0,
+                    resource: metadata_buffer.as_entire_binding(),
                 },
+            ],
+            label: Some("Metadata Bind Group"),
+        });
+        metadata_bind_group
+    }
+
+    pub fn bind_compact_group(&self, device: &Device, metadata_buffer: &Buffer, quantization_buffer: &Buffer, parts_buffer: &Buffer, v_buffer: &Buffer, i_buffer: &Buffer) -> BindGroup {
+        let compact_bind_group: BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.compact_bind_group_layout,
+            entries: &[
                 wgpu::BindGroupEntry {
-                    binding: 9,
-                    resource: metadata_buffer3.read().as_entire_binding(),
+                    binding: 0,
+                    resource: metadata_buffer.as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
-                    binding: 10,
-                    resource: metadata_buffer4.read().as_entire_binding(),
+                    binding: 1,
+                    resource: quantization_buffer.as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
-                    binding: 11,
-                    resource: metadata_buffer5.read().as_entire_binding(),
+                    binding: 2,
+                    resource: parts_buffer.as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
-                    binding: 12,
-                    resource: metadata_buffer6.read().as_entire_binding(),
+                    binding: 3,
+                    resource: v_buffer.as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
-                    binding: 13,
-                    resource: metadata_buffer7.read().as_entire_binding(),
+                    binding: 4,
+                    resource: i_buffer.as_entire_binding(),
                 },
             ],
-            label: Some("Mesh Bind Group"),
+            label: Some("Compact Bind Group"),
         });
-        mesh_uniform_bind_group
+        compact_bind_group
     }
 
     pub fn bind_selection_group(&self, device: &Device, shred_buffers: &SharedBuffers) -> BindGroup {
@@ -734,15 +993,6 @@ impl MeshPipeLine {
         let mode_buffer = shred_buffers.mode_buffer.clone();
         let slice_buffer = shred_buffers.slice_buffer.clone();
         let snap_buffer = shred_buffers.snap_buffer.clone();
-        let metadata_buffer0 = shred_buffers.metadata_buffer0.clone();
-        let metadata_buffer1 = shred_buffers.metadata_buffer1.clone();
-        let metadata_buffer2 = shred_buffers.metadata_buffer2.clone();
-        let metadata_buffer3 = shred_buffers.metadata_buffer3.clone();
-
-        let metadata_buffer4 = shred_buffers.metadata_buffer4.clone();
-        let metadata_buffer5 = shred_buffers.metadata_buffer5.clone();
-        let metadata_buffer6 = shred_buffers.metadata_buffer6.clone();
-        let metadata_buffer7 = shred_buffers.metadata_buffer7.clone();
 
         let selection_uniform_bind_group: BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &self.selection_bind_group_layout,
@@ -771,38 +1021,6 @@ impl MeshPipeLine {
                     binding: 5,
                     resource: snap_buffer.read().as_entire_binding(),
                 },
-                wgpu::BindGroupEntry {
-                    binding: 6,
-                    resource: metadata_buffer0.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 7,
-                    resource: metadata_buffer1.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 8,
-                    resource: metadata_buffer2.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 9,
-                    resource: metadata_buffer3.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 10,
-                    resource: metadata_buffer4.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 11,
-                    resource: metadata_buffer5.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 12,
-                    resource: metadata_buffer6.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 13,
-                    resource: metadata_buffer7.read().as_entire_binding(),
-                },
             ],
             label: Some("Selection Bind Group"),
         });
@@ -817,14 +1035,6 @@ impl MeshPipeLine {
         let mode_buffer = shred_buffers.mode_buffer.clone();
         let slice_buffer = shred_buffers.slice_buffer.clone();
         let snap_buffer = shred_buffers.snap_buffer.clone();
-        let metadata_buffer0 = shred_buffers.metadata_buffer0.clone();
-        let metadata_buffer1 = shred_buffers.metadata_buffer1.clone();
-        let metadata_buffer2 = shred_buffers.metadata_buffer2.clone();
-        let metadata_buffer3 = shred_buffers.metadata_buffer3.clone();
-        let metadata_buffer4 = shred_buffers.metadata_buffer4.clone();
-        let metadata_buffer5 = shred_buffers.metadata_buffer5.clone();
-        let metadata_buffer6 = shred_buffers.metadata_buffer6.clone();
-        let metadata_buffer7 = shred_buffers.metadata_buffer7.clone();
         let snap_uniform_bind_group: BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &self.snap_bind_group_layout,
             entries: &[
@@ -852,38 +1062,6 @@ impl MeshPipeLine {
                     binding: 5,
                     resource: snap_buffer.read().as_entire_binding(),
                 },
-                wgpu::BindGroupEntry {
-                    binding: 6,
-                    resource: metadata_buffer0.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 7,
-                    resource: metadata_buffer1.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 8,
-                    resource: metadata_buffer2.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 9,
-                    resource: metadata_buffer3.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 10,
-                    resource: metadata_buffer4.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 11,
-                    resource: metadata_buffer5.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 12,
-                    resource: metadata_buffer6.read().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 13,
-                    resource: metadata_buffer7.read().as_entire_binding(),
-                },
             ],
             label: Some("Snap Bind Group"),
         });
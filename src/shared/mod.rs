@@ -11,7 +11,16 @@ pub mod primitives_sphere;
 pub mod mesh_common;
 pub mod wasm_remote;
 pub mod highlight_pipeline;
+pub mod oit_pipeline;
+pub mod outline_pipeline;
+pub mod shadow_map;
+pub mod ssao_pipeline;
 pub mod primitives_pipe;
+pub mod primitives_mesh;
+pub mod primitives_bend;
+pub mod primitives_reducer;
+pub mod primitives_tee;
+pub mod primitives_flange;
 pub mod shared_buffers;
 pub mod screen_capture;
 pub mod text_layout;
@@ -19,10 +28,12 @@ pub mod dimension;
 
 pub const SPHERE_PRIME_TYPE: i32 = 700;
 pub const PIPE_PRIME_TYPE: i32 = 701;
+pub const BEND_PRIME_TYPE: i32 = 702;
+pub const REDUCER_PRIME_TYPE: i32 = 703;
+pub const TEE_PRIME_TYPE: i32 = 704;
+pub const FLANGE_PRIME_TYPE: i32 = 705;
 pub const CABLE_NODE_SPHERE_RADIUS: f32 = 5.0;
 pub const CABLE_EDGE_RADIUS: f32 = 1.0;
-pub const CABLE_NODE_COLOR: i32 = 2;
-pub const CABLE_EDGE_COLOR: i32 = 2;
 pub const ANGLE_SUBDIVISIONS: u32 = 8;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
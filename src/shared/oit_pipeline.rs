@@ -0,0 +1,132 @@
+use std::rc::Rc;
+use parking_lot::RwLock;
+use wgpu::{BindGroup, BindGroupLayout, BlendFactor, BlendOperation, Device, PipelineLayout, RenderPipeline, Texture, TextureFormat, TextureView};
+
+//targets of the translucent pass, see OitTargets in shader.wgsl
+pub const ACCUM_BUFFER_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+pub const REVEALAGE_BUFFER_FORMAT: TextureFormat = TextureFormat::R8Unorm;
+
+pub struct OitPipeLine {
+    bind_group_layout: BindGroupLayout,
+    pub composite_render_pipeline: RenderPipeline,
+    //kept between frames, made again when the surface size changes
+    accum_buffer: Texture,
+    pub accum_view: TextureView,
+    revealage_buffer: Texture,
+    pub revealage_view: TextureView,
+}
+
+impl OitPipeLine {
+    pub fn new(_device: Rc<RwLock<Device>>, format: TextureFormat) -> Self {
+        let device = _device.write();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Oit Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/oit.wgsl").into()),
+        });
+        let texture_entry = |binding: u32| wgpu::BindGroupLayoutEntry {
+            binding: binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout: BindGroupLayout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[texture_entry(0), texture_entry(1)],
+            label: Some("oit Bind Group Layout"),
+        });
+        let pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Oit Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        //a full screen triangle blending the average translucent colour over the opaque frame
+        let composite_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Oit Composite Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: BlendFactor::SrcAlpha,
+                            dst_factor: BlendFactor::OneMinusSrcAlpha,
+                            operation: BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::OVER,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let accum_buffer = create_target(&device, ACCUM_BUFFER_FORMAT, "Accum Buffer", 1, 1);
+        let accum_view = accum_buffer.create_view(&wgpu::TextureViewDescriptor::default());
+        let revealage_buffer = create_target(&device, REVEALAGE_BUFFER_FORMAT, "Revealage Buffer", 1, 1);
+        let revealage_view = revealage_buffer.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            bind_group_layout: bind_group_layout,
+            composite_render_pipeline: composite_render_pipeline,
+            accum_buffer: accum_buffer,
+            accum_view: accum_view,
+            revealage_buffer: revealage_buffer,
+            revealage_view: revealage_view,
+        }
+    }
+    pub fn fit_targets(&mut self, device: &Device, width: u32, height: u32) {
+        if (self.accum_buffer.width() != width.max(1) || self.accum_buffer.height() != height.max(1)) {
+            self.accum_buffer = create_target(device, ACCUM_BUFFER_FORMAT, "Accum Buffer", width, height);
+            self.accum_view = self.accum_buffer.create_view(&wgpu::TextureViewDescriptor::default());
+            self.revealage_buffer = create_target(device, REVEALAGE_BUFFER_FORMAT, "Revealage Buffer", width, height);
+            self.revealage_view = self.revealage_buffer.create_view(&wgpu::TextureViewDescriptor::default());
+        }
+    }
+    pub fn bind_composite_group(&self, device: &Device) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.accum_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&self.revealage_view),
+                },
+            ],
+            label: Some("Oit Bind Group"),
+        })
+    }
+}
+
+fn create_target(device: &Device, format: TextureFormat, label: &str, width: u32, height: u32) -> Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        label: Some(label),
+        view_formats: &[],
+    })
+}
@@ -0,0 +1,147 @@
+use std::mem::size_of;
+use std::rc::Rc;
+use parking_lot::RwLock;
+use wgpu::{BindGroup, BindGroupLayout, BlendFactor, BlendOperation, Buffer, BufferAddress, Device, PipelineLayout, Queue, RenderPipeline, Texture, TextureFormat, TextureView};
+use crate::gui::outline::OutlineStyle;
+
+//second target of the hull pass, see MeshTargets in shader.wgsl. Not blendable, so it keeps the nearest face
+pub const EDGE_BUFFER_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
+
+pub struct OutlinePipeLine {
+    bind_group_layout: BindGroupLayout,
+    pub outline_render_pipeline: RenderPipeline,
+    pub style_buffer: Buffer,
+    //kept between frames, made again when the surface size changes
+    edge_buffer: Texture,
+    pub edge_view: TextureView,
+}
+
+impl OutlinePipeLine {
+    pub fn new(_device: Rc<RwLock<Device>>, format: TextureFormat) -> Self {
+        let device = _device.write();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Outline Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/outline.wgsl").into()),
+        });
+        let style_buffer: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Outline Style Buffer"),
+            size: size_of::<OutlineStyle>() as BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout: BindGroupLayout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("outline Bind Group Layout"),
+        });
+        let pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Outline Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        //a full screen triangle, no depth test, edges are found in the edge buffer
+        let outline_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Outline Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: BlendFactor::SrcAlpha,
+                            dst_factor: BlendFactor::OneMinusSrcAlpha,
+                            operation: BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::OVER,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let edge_buffer = create_edge_buffer(&device, 1, 1);
+        let edge_view = edge_buffer.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            bind_group_layout: bind_group_layout,
+            outline_render_pipeline: outline_render_pipeline,
+            style_buffer: style_buffer,
+            edge_buffer: edge_buffer,
+            edge_view: edge_view,
+        }
+    }
+    pub fn fit_edge_buffer(&mut self, device: &Device, width: u32, height: u32) {
+        if (self.edge_buffer.width() != width.max(1) || self.edge_buffer.height() != height.max(1)) {
+            self.edge_buffer = create_edge_buffer(device, width, height);
+            self.edge_view = self.edge_buffer.create_view(&wgpu::TextureViewDescriptor::default());
+        }
+    }
+    pub fn update_style(&self, queue: &Queue, style: &OutlineStyle) {
+        queue.write_buffer(&self.style_buffer, 0, bytemuck::cast_slice(&[*style]));
+    }
+    pub fn bind_outline_group(&self, device: &Device) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.edge_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.style_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("Outline Bind Group"),
+        })
+    }
+}
+
+fn create_edge_buffer(device: &Device, width: u32, height: u32) -> Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: EDGE_BUFFER_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        label: Some("Edge Buffer"),
+        view_formats: &[],
+    })
+}
@@ -0,0 +1,95 @@
+use std::ops::Sub;
+use cgmath::{Basis3, InnerSpace, Point3, Rad, Rotation, Rotation3, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::scene::RawMesh;
+use crate::shared::{ANGLE_SUBDIVISIONS, BEND_PRIME_TYPE};
+use crate::shared::primitives_mesh::{ring_in_frame, PrimitiveMesh, PrimitiveVertex};
+
+//Torus segment: the pipe axis starts at p_a and turns around the line through center along axis by angle
+//radians, counter clockwise seen from the axis end. The bend radius is the distance from p_a to that line
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BendPrimitive {
+    pub id: i32,
+    pub center: Point3<f32>,
+    pub axis: Vector3<f32>,
+    pub p_a: Point3<f32>,
+    pub angle: f32,
+    pub radius: f32,
+    pub mat_idx: i32,
+}
+
+impl BendPrimitive {
+    pub fn new(id: i32, center: Point3<f32>, axis: Vector3<f32>, p_a: Point3<f32>, angle: f32, radius: f32, mat_idx: i32) -> Self {
+        Self {
+            id: id,
+            center: center,
+            axis: axis,
+            p_a: p_a,
+            angle: angle,
+            radius: radius,
+            mat_idx: mat_idx,
+        }
+    }
+
+    //end of the pipe axis
+    pub fn p_b(&self) -> Point3<f32> {
+        let rot: Basis3<f32> = Rotation3::from_axis_angle(self.axis.normalize(), Rad(self.angle));
+        self.center + rot.rotate_vector(self.p_a.sub(self.center))
+    }
+
+    pub fn triangulate(&self) -> RawMesh {
+        self.triangulate_with(ANGLE_SUBDIVISIONS)
+    }
+    //segments around the pipe, the arc gets as many per full turn
+    pub fn triangulate_with(&self, segments: u32) -> RawMesh {
+        let axis = self.axis.normalize();
+        //arm from the bend axis to p_a, without its part along the axis
+        let arm = {
+            let v = self.p_a.sub(self.center);
+            v - axis * v.dot(axis)
+        };
+        let base = self.p_a - arm;
+        let arc_segments = ((segments.max(3) as f32 * self.angle.abs() / (std::f32::consts::PI * 2.0)).ceil() as u32).max(1);
+        let rings: Vec<Vec<PrimitiveVertex>> = (0..=arc_segments).map(|i| {
+            let rot: Basis3<f32> = Rotation3::from_axis_angle(axis, Rad(self.angle * i as f32 / arc_segments as f32));
+            let r = rot.rotate_vector(arm);
+            //the pipe section lies in the plane of the arm and the bend axis
+            ring_in_frame(base + r, r.normalize(), axis, self.radius, segments)
+        }).collect();
+        let mut mesh = PrimitiveMesh::new();
+        rings.windows(2).for_each(|w| mesh.push_band(&w[0], &w[1]));
+        mesh.into_raw_mesh(self.id, BEND_PRIME_TYPE, self.mat_idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::primitives_mesh::{assert_bbx, corners};
+
+    fn quarter_bend() -> BendPrimitive {
+        BendPrimitive::new(1, Point3::new(0.0, 0.0, 0.0), Vector3::unit_z(), Point3::new(3.0, 0.0, 0.0), std::f32::consts::FRAC_PI_2, 0.5, 0)
+    }
+
+    //a torus normal points from the nearest point of the bend arc to the vertex
+    #[test]
+    fn normals_follow_torus() {
+        let bend = quarter_bend();
+        let mesh = bend.triangulate_with(256);
+        corners(&mesh).iter().for_each(|(p, n)| {
+            let q = p.sub(bend.center);
+            let q = q - bend.axis * q.dot(bend.axis);
+            let on_arc = bend.center + q.normalize() * 3.0;
+            let expected = p.sub(on_arc);
+            assert!((expected.magnitude() - bend.radius).abs() < 1e-4);
+            assert!(n.dot(expected.normalize()) > 0.999, "normal {:?} at {:?}", n, p);
+        });
+    }
+
+    #[test]
+    fn bbx_of_quarter_bend() {
+        let mesh = quarter_bend().triangulate_with(16);
+        assert_bbx(&mesh, [0.0, 0.0, -0.5], [3.5, 3.5, 0.5]);
+    }
+}
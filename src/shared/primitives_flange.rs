@@ -0,0 +1,75 @@
+use std::ops::Sub;
+use cgmath::{InnerSpace, Point3, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::scene::RawMesh;
+use crate::shared::{ANGLE_SUBDIVISIONS, FLANGE_PRIME_TYPE};
+use crate::shared::primitives_mesh::{inverted, ring, PrimitiveMesh, PrimitiveVertex};
+
+//Closed disk from p_a to p_b with a bore of radius for the pipe and the rim at outer_radius,
+//the distance from p_a to p_b is its thickness
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FlangePrimitive {
+    pub id: i32,
+    pub p_a: Point3<f32>,
+    pub p_b: Point3<f32>,
+    pub radius: f32,
+    pub outer_radius: f32,
+    pub mat_idx: i32,
+}
+
+impl FlangePrimitive {
+    pub fn new(id: i32, p_a: Point3<f32>, p_b: Point3<f32>, radius: f32, outer_radius: f32, mat_idx: i32) -> Self {
+        Self {
+            id: id,
+            p_a: p_a,
+            p_b: p_b,
+            radius: radius,
+            outer_radius: outer_radius,
+            mat_idx: mat_idx,
+        }
+    }
+
+    pub fn triangulate(&self) -> RawMesh {
+        self.triangulate_with(ANGLE_SUBDIVISIONS)
+    }
+    //rim, bore and both faces, segments around the axis
+    pub fn triangulate_with(&self, segments: u32) -> RawMesh {
+        let t: Vector3<f32> = self.p_b.sub(self.p_a);
+        let v = t.normalize();
+        let mut mesh = PrimitiveMesh::new();
+
+        let rim_a = ring(self.p_a, v, self.outer_radius, segments);
+        let rim_b: Vec<PrimitiveVertex> = rim_a.iter().map(|(p, n)| (p + t, *n)).collect();
+        mesh.push_band(&rim_a, &rim_b);
+
+        if (self.radius > 0.0) {
+            let bore_a = inverted(ring(self.p_a, v, self.radius, segments));
+            let bore_b: Vec<PrimitiveVertex> = bore_a.iter().map(|(p, n)| (p + t, *n)).collect();
+            mesh.push_band(&bore_a, &bore_b);
+        }
+
+        mesh.push_annulus(self.p_a, -v, self.radius.max(0.0), self.outer_radius, segments);
+        mesh.push_annulus(self.p_b, v, self.radius.max(0.0), self.outer_radius, segments);
+        mesh.into_raw_mesh(self.id, FLANGE_PRIME_TYPE, self.mat_idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::primitives_mesh::{assert_bbx, corners};
+
+    #[test]
+    fn bbx_of_flange() {
+        let flange = FlangePrimitive::new(1, Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, 0.25), 1.0, 2.0, 0);
+        let mesh = flange.triangulate_with(16);
+        assert_bbx(&mesh, [-2.0, -2.0, 0.0], [2.0, 2.0, 0.25]);
+        //bore normals point to the axis, rim normals away from it
+        corners(&mesh).iter().filter(|(_, n)| n.z.abs() < 1e-4).for_each(|(p, n)| {
+            let radial = Vector3::new(p.x, p.y, 0.0);
+            let expected = if (radial.magnitude() < 1.5) { -radial.normalize() } else { radial.normalize() };
+            assert!(n.dot(expected) > 0.9999, "normal {:?} at {:?}", n, p);
+        });
+    }
+}
@@ -0,0 +1,148 @@
+use std::ops::Sub;
+use cgmath::{InnerSpace, Point3, Vector3};
+use truck_base::bounding_box::BoundingBox;
+
+use crate::scene::RawMesh;
+use crate::shared::Triangle;
+
+//corner of a primitive surface, the normal points out of the solid
+pub(crate) type PrimitiveVertex = (Point3<f32>, Vector3<f32>);
+
+//Triangles of a primitive in the RawMesh layout: position and normal of every corner, no shared vertices.
+//The bbx grows with every corner, so it is the box of the triangulated surface
+pub(crate) struct PrimitiveMesh {
+    vertex_normal: Vec<f32>,
+    indx: Vec<i32>,
+    triangles: Vec<Triangle>,
+    bbx: BoundingBox<Point3<f64>>,
+}
+
+impl PrimitiveMesh {
+    pub fn new() -> Self {
+        Self {
+            vertex_normal: vec![],
+            indx: vec![],
+            triangles: vec![],
+            bbx: BoundingBox::default(),
+        }
+    }
+
+    //winding is turned counter clockwise seen from the side the normals point to, back faces are culled
+    pub fn push_triangle(&mut self, a: PrimitiveVertex, b: PrimitiveVertex, c: PrimitiveVertex) {
+        let triangle: Triangle = Triangle::new(a.0, b.0, c.0);
+        let corners = if (triangle.normal.dot(a.1 + b.1 + c.1) < 0.0) { [a, c, b] } else { [a, b, c] };
+        corners.iter().for_each(|(p, n)| {
+            self.vertex_normal.extend_from_slice(&[p.x, p.y, p.z, n.x, n.y, n.z]);
+            self.indx.push(self.indx.len() as i32);
+            self.bbx.push(Point3::new(p.x as f64, p.y as f64, p.z as f64));
+        });
+        self.triangles.push(Triangle::new(corners[0].0, corners[1].0, corners[2].0));
+    }
+
+    //side surface between two closed rings of the same length, a vertex normal is square to the ring
+    //and to the band, on the side of the ring normal, so a cone gets its slope
+    pub fn push_band(&mut self, ring_a: &[PrimitiveVertex], ring_b: &[PrimitiveVertex]) {
+        let count = ring_a.len().min(ring_b.len());
+        let normal = |i: usize, n: Vector3<f32>| -> Vector3<f32> {
+            let g = ring_b[i].0.sub(ring_a[i].0);
+            let c = ring_a[(i + 1) % count].0.sub(ring_a[(i + count - 1) % count].0);
+            let s = c.cross(g);
+            if (s.magnitude2() > 0.0) {
+                let s = s.normalize();
+                if (s.dot(n) < 0.0) { -s } else { s }
+            } else {
+                n
+            }
+        };
+        for i in 0..count {
+            let j = (i + 1) % count;
+            let a0 = (ring_a[i].0, normal(i, ring_a[i].1));
+            let a1 = (ring_a[j].0, normal(j, ring_a[j].1));
+            let b0 = (ring_b[i].0, normal(i, ring_b[i].1));
+            let b1 = (ring_b[j].0, normal(j, ring_b[j].1));
+            self.push_triangle(a0, b0, b1);
+            self.push_triangle(a0, b1, a1);
+        }
+    }
+
+    //flat ring facing the normal, a disk when r_in is 0
+    pub fn push_annulus(&mut self, center: Point3<f32>, normal: Vector3<f32>, r_in: f32, r_out: f32, segments: u32) {
+        let n = normal.normalize();
+        let inner = ring(center, n, r_in, segments);
+        let outer = ring(center, n, r_out, segments);
+        let count = outer.len();
+        for i in 0..count {
+            let j = (i + 1) % count;
+            if (r_in > 0.0) {
+                self.push_triangle((inner[i].0, n), (outer[i].0, n), (outer[j].0, n));
+                self.push_triangle((inner[i].0, n), (outer[j].0, n), (inner[j].0, n));
+            } else {
+                self.push_triangle((center, n), (outer[i].0, n), (outer[j].0, n));
+            }
+        }
+    }
+
+    pub fn into_raw_mesh(self, id: i32, ty: i32, mat_idx: i32) -> RawMesh {
+        RawMesh {
+            id: id,
+            ty: ty,
+            name: id.to_string(),
+            vertex_normal: self.vertex_normal,
+            indx: self.indx,
+            color_indx: mat_idx,
+            bbx: self.bbx,
+            bvh_index: id as usize,
+            triangles: self.triangles,
+        }
+    }
+}
+
+//unit vector perpendicular to v, taken from the axis v is least aligned with
+pub(crate) fn perpendicular(v: Vector3<f32>) -> Vector3<f32> {
+    let axis = if (v.x.abs() <= v.y.abs() && v.x.abs() <= v.z.abs()) {
+        Vector3::unit_x()
+    } else if (v.y.abs() <= v.z.abs()) {
+        Vector3::unit_y()
+    } else {
+        Vector3::unit_z()
+    };
+    v.cross(axis).normalize()
+}
+
+//circle around the axis with radial normals, it starts at perpendicular(axis)
+pub(crate) fn ring(center: Point3<f32>, axis: Vector3<f32>, radius: f32, segments: u32) -> Vec<PrimitiveVertex> {
+    let axis = axis.normalize();
+    let u = perpendicular(axis);
+    ring_in_frame(center, u, axis.cross(u), radius, segments)
+}
+
+//circle in the plane of the unit vectors u and w, counter clockwise from u
+pub(crate) fn ring_in_frame(center: Point3<f32>, u: Vector3<f32>, w: Vector3<f32>, radius: f32, segments: u32) -> Vec<PrimitiveVertex> {
+    let segments = segments.max(3);
+    let step_angle = std::f32::consts::PI * 2.0 / segments as f32;
+    (0..segments).map(|i| {
+        let angle = step_angle * i as f32;
+        let n = u * angle.cos() + w * angle.sin();
+        (center + n * radius, n)
+    }).collect()
+}
+
+//same ring with the normals turned inside, for bores
+pub(crate) fn inverted(ring: Vec<PrimitiveVertex>) -> Vec<PrimitiveVertex> {
+    ring.into_iter().map(|(p, n)| (p, -n)).collect()
+}
+
+//corners of a triangulated primitive, for the checks of every primitive
+#[cfg(test)]
+pub(crate) fn corners(mesh: &RawMesh) -> Vec<PrimitiveVertex> {
+    mesh.vertex_normal.chunks_exact(6).map(|c| (Point3::new(c[0], c[1], c[2]), Vector3::new(c[3], c[4], c[5]))).collect()
+}
+
+#[cfg(test)]
+pub(crate) fn assert_bbx(mesh: &RawMesh, min: [f64; 3], max: [f64; 3]) {
+    let (b_min, b_max) = (mesh.bbx.min(), mesh.bbx.max());
+    (0..3).for_each(|i| {
+        assert!((b_min[i] - min[i]).abs() < 1e-4, "bbx min {:?} expected {:?}", b_min, min);
+        assert!((b_max[i] - max[i]).abs() < 1e-4, "bbx max {:?} expected {:?}", b_max, max);
+    });
+}
@@ -0,0 +1,108 @@
+use std::ops::Sub;
+use cgmath::{InnerSpace, Point3, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::scene::RawMesh;
+use crate::shared::{ANGLE_SUBDIVISIONS, REDUCER_PRIME_TYPE};
+use crate::shared::primitives_mesh::{perpendicular, ring_in_frame, PrimitiveMesh};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ReducerKind {
+    Concentric,
+    //both ends touch the flat side, the vector points from the axis to it
+    Eccentric(Vector3<f32>),
+}
+
+//Cone from radius_a at p_a to radius_b at p_b. An eccentric reducer keeps p_a on the axis of end a
+//and moves the centre of end b towards the flat side by the difference of the radii
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReducerPrimitive {
+    pub id: i32,
+    pub p_a: Point3<f32>,
+    pub p_b: Point3<f32>,
+    pub radius_a: f32,
+    pub radius_b: f32,
+    pub kind: ReducerKind,
+    pub mat_idx: i32,
+}
+
+impl ReducerPrimitive {
+    pub fn new(id: i32, p_a: Point3<f32>, p_b: Point3<f32>, radius_a: f32, radius_b: f32, kind: ReducerKind, mat_idx: i32) -> Self {
+        Self {
+            id: id,
+            p_a: p_a,
+            p_b: p_b,
+            radius_a: radius_a,
+            radius_b: radius_b,
+            kind: kind,
+            mat_idx: mat_idx,
+        }
+    }
+
+    //centre of end b, off the axis for an eccentric reducer
+    pub fn center_b(&self) -> Point3<f32> {
+        let v = self.p_b.sub(self.p_a).normalize();
+        match self.kind {
+            ReducerKind::Concentric => self.p_b,
+            ReducerKind::Eccentric(flat) => {
+                let flat = flat - v * flat.dot(v);
+                let flat = if (flat.magnitude2() > 0.0) { flat.normalize() } else { perpendicular(v) };
+                self.p_b + flat * (self.radius_a - self.radius_b)
+            }
+        }
+    }
+
+    pub fn triangulate(&self) -> RawMesh {
+        self.triangulate_with(ANGLE_SUBDIVISIONS)
+    }
+    //open cone, segments around the axis
+    pub fn triangulate_with(&self, segments: u32) -> RawMesh {
+        let v: Vector3<f32> = self.p_b.sub(self.p_a).normalize();
+        let u = perpendicular(v);
+        let w = v.cross(u);
+        //both rings are square to the axis of end a, so they stay parallel when end b is moved
+        let ring_a = ring_in_frame(self.p_a, u, w, self.radius_a, segments);
+        let ring_b = ring_in_frame(self.center_b(), u, w, self.radius_b, segments);
+        let mut mesh = PrimitiveMesh::new();
+        mesh.push_band(&ring_a, &ring_b);
+        mesh.into_raw_mesh(self.id, REDUCER_PRIME_TYPE, self.mat_idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::primitives_mesh::{assert_bbx, corners};
+
+    fn reducer(kind: ReducerKind) -> ReducerPrimitive {
+        ReducerPrimitive::new(1, Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, 4.0), 2.0, 1.0, kind, 0)
+    }
+
+    //a cone normal is square to the generator, tilted from the radial direction towards the narrow end
+    #[test]
+    fn normals_follow_cone() {
+        let cone = reducer(ReducerKind::Concentric);
+        let mesh = cone.triangulate_with(32);
+        let v = Vector3::unit_z();
+        corners(&mesh).iter().for_each(|(p, n)| {
+            let q = p.sub(cone.p_a);
+            let radial = (q - v * q.dot(v)).normalize();
+            let expected = (radial * 4.0 + v * (cone.radius_a - cone.radius_b)).normalize();
+            assert!(n.dot(expected) > 0.9999, "normal {:?} at {:?}", n, p);
+        });
+    }
+
+    #[test]
+    fn bbx_of_concentric_reducer() {
+        let mesh = reducer(ReducerKind::Concentric).triangulate_with(16);
+        assert_bbx(&mesh, [-2.0, -2.0, 0.0], [2.0, 2.0, 4.0]);
+    }
+
+    //both ends touch the flat side at x = 2
+    #[test]
+    fn bbx_of_eccentric_reducer() {
+        let mesh = reducer(ReducerKind::Eccentric(Vector3::unit_x())).triangulate_with(16);
+        assert_bbx(&mesh, [-2.0, -2.0, 0.0], [2.0, 2.0, 4.0]);
+        corners(&mesh).iter().filter(|(p, _)| p.z > 2.0).for_each(|(p, _)| assert!(p.x > -1e-4));
+    }
+}
@@ -26,8 +26,12 @@ impl SpherePrimitive {
         }
     }
     pub fn triangulate(&self) -> RawMesh {
+        self.triangulate_with(ANGLE_SUBDIVISIONS)
+    }
+    //fewer subdivisions for small markers drawn in thousands, e.g. cable nodes
+    pub fn triangulate_with(&self, subdivisions: u32) -> RawMesh {
         let mut triangles: Vec<Triangle> = vec![];
-        let sphere = sphere(self.radius, self.center.x, self.center.y, self.center.z);
+        let sphere = sphere(self.radius, self.center.x, self.center.y, self.center.z, subdivisions.max(2));
         let vrtxs = sphere.positions;
         let normals = sphere.normals;
         let mut indx: Vec<i32> = vec![];
@@ -78,7 +82,7 @@ impl SpherePrimitive {
     }
 }
 
-fn sphere(r: f32, cx: f32, cy: f32, cz: f32) -> TriMesh {
+fn sphere(r: f32, cx: f32, cy: f32, cz: f32, subdivisions: u32) -> TriMesh {
     let mut positions: Vec<Vector3<f32>> = Vec::new();
     let mut indices: Vec<i32> = Vec::new();
     let mut normals: Vec<Vector3<f32>> = Vec::new();
@@ -87,30 +91,30 @@ fn sphere(r: f32, cx: f32, cy: f32, cz: f32) -> TriMesh {
     positions.push(Vector3::new(cx, cy, r + cz));
     normals.push(Vector3::new(cx, cy, r + cz).sub(center).normalize());
 
-    for j in 0..ANGLE_SUBDIVISIONS * 2 {
-        let j1 = (j + 1) % (ANGLE_SUBDIVISIONS * 2);
+    for j in 0..subdivisions * 2 {
+        let j1 = (j + 1) % (subdivisions * 2);
         indices.push(0);
         indices.push((1 + j) as i32);
         indices.push((1 + j1) as i32);
     }
 
-    for i in 0..ANGLE_SUBDIVISIONS - 1 {
-        let theta = std::f32::consts::PI * (i + 1) as f32 / ANGLE_SUBDIVISIONS as f32;
+    for i in 0..subdivisions - 1 {
+        let theta = std::f32::consts::PI * (i + 1) as f32 / subdivisions as f32;
         let sin_theta = theta.sin();
         let cos_theta = theta.cos();
-        let i0 = 1 + i * ANGLE_SUBDIVISIONS * 2;
-        let i1 = 1 + (i + 1) * ANGLE_SUBDIVISIONS * 2;
+        let i0 = 1 + i * subdivisions * 2;
+        let i1 = 1 + (i + 1) * subdivisions * 2;
 
-        for j in 0..ANGLE_SUBDIVISIONS * 2 {
-            let phi = std::f32::consts::PI * j as f32 / ANGLE_SUBDIVISIONS as f32;
+        for j in 0..subdivisions * 2 {
+            let phi = std::f32::consts::PI * j as f32 / subdivisions as f32;
             let x = sin_theta * phi.cos() * r + cx;
             let y = sin_theta * phi.sin() * r + cy;
             let z = cos_theta * r + cz;
             positions.push(Vector3::new(x, y, z));
             normals.push(Vector3::new(x, y, z).sub(center).normalize());
 
-            if i != ANGLE_SUBDIVISIONS - 2 {
-                let j1 = (j + 1) % (ANGLE_SUBDIVISIONS * 2);
+            if i != subdivisions - 2 {
+                let j1 = (j + 1) % (subdivisions * 2);
                 indices.push((i0 + j) as i32);
                 indices.push((i1 + j1) as i32);
                 indices.push((i0 + j1) as i32);
@@ -124,13 +128,13 @@ fn sphere(r: f32, cx: f32, cy: f32, cz: f32) -> TriMesh {
     positions.push(Vector3::new(cx, cy, -r + cz));
     normals.push(Vector3::new(cx, cy, -r + cz).sub(center).normalize());
 
-    let i = 1 + (ANGLE_SUBDIVISIONS - 2) * ANGLE_SUBDIVISIONS * 2;
-    for j in 0..ANGLE_SUBDIVISIONS * 2 {
-        let j1 = (j + 1) % (ANGLE_SUBDIVISIONS * 2);
+    let i = 1 + (subdivisions - 2) * subdivisions * 2;
+    for j in 0..subdivisions * 2 {
+        let j1 = (j + 1) % (subdivisions * 2);
 
 
             indices.push((i + j) as i32);
-            indices.push(((ANGLE_SUBDIVISIONS - 1) * ANGLE_SUBDIVISIONS * 2 + 1) as i32);
+            indices.push(((subdivisions - 1) * subdivisions * 2 + 1) as i32);
             indices.push((i + j1) as i32);
 
 
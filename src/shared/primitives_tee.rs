@@ -0,0 +1,87 @@
+use std::ops::Sub;
+use cgmath::{InnerSpace, Point3, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::scene::RawMesh;
+use crate::shared::{ANGLE_SUBDIVISIONS, TEE_PRIME_TYPE};
+use crate::shared::primitives_mesh::{perpendicular, ring, PrimitiveMesh, PrimitiveVertex};
+
+//Run pipe of radius from p_a to p_b and a branch of branch_radius out to p_c. The branch starts square
+//to the run at the foot of p_c on it and is cut by the run surface, so it is not seen inside the run
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TeePrimitive {
+    pub id: i32,
+    pub p_a: Point3<f32>,
+    pub p_b: Point3<f32>,
+    pub p_c: Point3<f32>,
+    pub radius: f32,
+    pub branch_radius: f32,
+    pub mat_idx: i32,
+}
+
+impl TeePrimitive {
+    pub fn new(id: i32, p_a: Point3<f32>, p_b: Point3<f32>, p_c: Point3<f32>, radius: f32, branch_radius: f32, mat_idx: i32) -> Self {
+        Self {
+            id: id,
+            p_a: p_a,
+            p_b: p_b,
+            p_c: p_c,
+            radius: radius,
+            branch_radius: branch_radius,
+            mat_idx: mat_idx,
+        }
+    }
+
+    //foot of p_c on the run axis, where the branch axis starts
+    pub fn branch_base(&self) -> Point3<f32> {
+        let v = self.p_b.sub(self.p_a).normalize();
+        self.p_a + v * self.p_c.sub(self.p_a).dot(v)
+    }
+
+    pub fn triangulate(&self) -> RawMesh {
+        self.triangulate_with(ANGLE_SUBDIVISIONS)
+    }
+    //open run and branch, segments around both axes
+    pub fn triangulate_with(&self, segments: u32) -> RawMesh {
+        let run: Vector3<f32> = self.p_b.sub(self.p_a);
+        let v = run.normalize();
+        let base = self.branch_base();
+        let branch = self.p_c.sub(base);
+        let d = if (branch.magnitude2() > 0.0) { branch.normalize() } else { perpendicular(v) };
+        let mut mesh = PrimitiveMesh::new();
+
+        let run_a = ring(self.p_a, v, self.radius, segments);
+        let run_b: Vec<PrimitiveVertex> = run_a.iter().map(|(p, n)| (p + run, *n)).collect();
+        mesh.push_band(&run_a, &run_b);
+
+        //a branch corner at side offset s across the run meets the run surface at sqrt(r^2 - s^2) from its axis
+        let r_branch = self.branch_radius.min(self.radius);
+        let w = v.cross(d);
+        let outer = ring(self.p_c, d, r_branch, segments);
+        let saddle: Vec<PrimitiveVertex> = outer.iter().map(|(p, n)| {
+            let s = n.dot(w) * r_branch;
+            let h = (self.radius * self.radius - s * s).max(0.0).sqrt();
+            (base + d * h + (p.sub(self.p_c)), *n)
+        }).collect();
+        mesh.push_band(&saddle, &outer);
+        mesh.into_raw_mesh(self.id, TEE_PRIME_TYPE, self.mat_idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::primitives_mesh::{assert_bbx, corners};
+
+    //run along x, branch up to y = 3
+    #[test]
+    fn bbx_of_tee() {
+        let tee = TeePrimitive::new(1, Point3::new(-2.0, 0.0, 0.0), Point3::new(2.0, 0.0, 0.0), Point3::new(0.0, 3.0, 0.0), 1.0, 0.5, 0);
+        let mesh = tee.triangulate_with(16);
+        assert_bbx(&mesh, [-2.0, -1.0, -1.0], [2.0, 3.0, 1.0]);
+        //the branch starts on the run surface, not inside it
+        corners(&mesh).iter().filter(|(p, _)| p.x.abs() <= 0.5 && p.y > 0.0).for_each(|(p, _)| {
+            assert!(p.y * p.y + p.z * p.z > 1.0 - 1e-4, "branch corner {:?} inside the run", p);
+        });
+    }
+}
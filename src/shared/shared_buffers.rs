@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::mem::size_of;
 use std::rc::Rc;
 use cgmath::Point3;
@@ -5,6 +6,9 @@ use parking_lot::RwLock;
 
 use wgpu::{Buffer, BufferAddress, Device, Queue};
 use crate::device::message_controller::SnapMode;
+use crate::gui::clipping::{ClipUniform, CLIP_UNIFORM_OFFSET};
+use crate::gui::section_caps::CapStyle;
+use crate::gui::sun::SunUniform;
 use crate::shared::dimension::Dimension;
 use crate::shared::materials_lib::{Material, MATERIALS_COUNT};
 
@@ -15,14 +19,9 @@ pub struct SharedBuffers {
     pub mode_buffer: Rc<RwLock<Buffer>>,
     pub slice_buffer: Rc<RwLock<Buffer>>,
     pub snap_buffer: Rc<RwLock<Buffer>>,
-    pub metadata_buffer0: Rc<RwLock<Buffer>>,
-    pub metadata_buffer1: Rc<RwLock<Buffer>>,
-    pub metadata_buffer2: Rc<RwLock<Buffer>>,
-    pub metadata_buffer3: Rc<RwLock<Buffer>>,
-    pub metadata_buffer4: Rc<RwLock<Buffer>>,
-    pub metadata_buffer5: Rc<RwLock<Buffer>>,
-    pub metadata_buffer6: Rc<RwLock<Buffer>>,
-    pub metadata_buffer7: Rc<RwLock<Buffer>>,
+    pub cap_buffer: Rc<RwLock<Buffer>>,
+    pub sun_buffer: Rc<RwLock<Buffer>>,
+    pub metadata_buffers: HashMap<u32, Rc<RwLock<Buffer>>>,
 }
 
 impl SharedBuffers {
@@ -48,13 +47,14 @@ impl SharedBuffers {
         });
         let light_buffer: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Light Uniform Buffer"),
-            size: 48,
+            size: 64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
         let slice_buffer: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("slice Uniform Buffer"),
-            size: 32, //size: (size_of::<f32>() * 6) as BufferAddress,
+            //axis planes of the Slicer, then the ClipUniform
+            size: CLIP_UNIFORM_OFFSET + size_of::<ClipUniform>() as BufferAddress,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -64,52 +64,16 @@ impl SharedBuffers {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
-        let metadata_buffer0: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("metadata_buffer Buffer0"),
-            size: 16,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-        let metadata_buffer1: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("metadata_buffer Buffer1"),
-            size: 16,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-        let metadata_buffer2: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("metadata_buffer Buffer2"),
-            size: 16,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-        let metadata_buffer3: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("metadata_buffer Buffer3"),
-            size: 16,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-        let metadata_buffer4: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("metadata_buffer Buffer4"),
-            size: 16,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-        let metadata_buffer5: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("metadata_buffer Buffer5"),
-            size: 16,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-        let metadata_buffer6: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("metadata_buffer Buffer6"),
-            size: 16,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        let cap_buffer: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("cap Uniform Buffer"),
+            size: (size_of::<CapStyle>() * MATERIALS_COUNT) as BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
-        let metadata_buffer7: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("metadata_buffer Buffer7"),
-            size: 16,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        let sun_buffer: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sun Uniform Buffer"),
+            size: size_of::<SunUniform>() as BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
@@ -120,14 +84,9 @@ impl SharedBuffers {
             mode_buffer: Rc::new(RwLock::new(mode_buffer)),
             slice_buffer: Rc::new(RwLock::new(slice_buffer)),
             snap_buffer: Rc::new(RwLock::new(snap_buffer)),
-            metadata_buffer0: Rc::new(RwLock::new(metadata_buffer0)),
-            metadata_buffer1: Rc::new(RwLock::new(metadata_buffer1)),
-            metadata_buffer2: Rc::new(RwLock::new(metadata_buffer2)),
-            metadata_buffer3: Rc::new(RwLock::new(metadata_buffer3)),
-            metadata_buffer4: Rc::new(RwLock::new(metadata_buffer4)),
-            metadata_buffer5: Rc::new(RwLock::new(metadata_buffer5)),
-            metadata_buffer6: Rc::new(RwLock::new(metadata_buffer6)),
-            metadata_buffer7: Rc::new(RwLock::new(metadata_buffer7)),
+            cap_buffer: Rc::new(RwLock::new(cap_buffer)),
+            sun_buffer: Rc::new(RwLock::new(sun_buffer)),
+            metadata_buffers: HashMap::new(),
         }
     }
 
@@ -139,13 +98,14 @@ impl SharedBuffers {
         q.write_buffer(&buff.write(), 128, bytemuck::cast_slice(forward));
     }
 
-    pub fn update_lights(&mut self, queue: Rc<RwLock<Queue>>, light_position: &[f32; 3], eye_position: &[f32; 3], w: f32, h: f32) {
+    pub fn update_lights(&mut self, queue: Rc<RwLock<Queue>>, light_position: &[f32; 3], eye_position: &[f32; 3], w: f32, h: f32, light_color: &[f32; 4]) {
         let q = queue.write();
         let buff = self.light_buffer.clone();
         let resolution: [f32; 4] = [w, h, 0.0, 0.0];
         q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(light_position));
         q.write_buffer(&buff.write(), 16, bytemuck::cast_slice(eye_position));
         q.write_buffer(&buff.write(), 32, bytemuck::cast_slice(&resolution));
+        q.write_buffer(&buff.write(), 48, bytemuck::cast_slice(light_color));
     }
 
     pub fn update_slicer(&mut self, queue: Rc<RwLock<Queue>>, slicer_pos: &[f32; 6]) {
@@ -154,6 +114,24 @@ impl SharedBuffers {
         q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(slicer_pos));
     }
 
+    pub fn update_clipping(&mut self, queue: Rc<RwLock<Queue>>, clip: &ClipUniform) {
+        let q = queue.write();
+        let buff = self.slice_buffer.clone();
+        q.write_buffer(&buff.write(), CLIP_UNIFORM_OFFSET, bytemuck::bytes_of(clip));
+    }
+
+    pub fn update_caps(&self, queue: Rc<RwLock<Queue>>, styles: &Vec<CapStyle>) {
+        let q = queue.write();
+        let buff = self.cap_buffer.clone();
+        q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(styles));
+    }
+
+    pub fn update_sun(&self, queue: Rc<RwLock<Queue>>, sun: &SunUniform) {
+        let q = queue.write();
+        let buff = self.sun_buffer.clone();
+        q.write_buffer(&buff.write(), 0, bytemuck::bytes_of(sun));
+    }
+
     pub fn update_material(&self, queue: Rc<RwLock<Queue>>, materials: &Vec<Material>) {
         let q = queue.write();
         let buff = self.material_buffer.clone();
@@ -178,189 +156,36 @@ impl SharedBuffers {
         q.write_buffer(&buff.write(), 64, bytemuck::cast_slice(&mode));
     }
 
-    pub fn update_metadata0(&mut self, device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, h_m_d: &Vec<i32>) {
-        {
-            let buff = self.metadata_buffer0.clone();
-            let new_size: usize = if h_m_d.len() == 0 { 16 } else {
-                let curr_size = (h_m_d.len() * 4) as f32;
-                (((curr_size / 16.0).ceil() + 1.0) * 16.0) as usize
-            };
-            let currsize = buff.read().size();
-            if currsize != new_size as u64 {
-                let d = device.clone();
-                let new_buff: Buffer = d.read().create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("metadata_buffer0"),
-                    size: (new_size) as BufferAddress,
-                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-                    mapped_at_creation: false,
-                });
-                self.metadata_buffer0 = Rc::new(RwLock::new(new_buff));
-            }
+    //metadata buffer of a pack is created on first update and recreated when the pack grows, true if it was
+    pub fn update_metadata(&mut self, device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, pack_id: u32, h_m_d: &Vec<i32>) -> bool {
+        let new_size: usize = if h_m_d.len() == 0 { 16 } else {
+            let curr_size = (h_m_d.len() * 4) as f32;
+            (((curr_size / 16.0).ceil() + 1.0) * 16.0) as usize
+        };
+        let is_resize_needed = match self.metadata_buffers.get(&pack_id) {
+            None => { true }
+            Some(buff) => { buff.read().size() != new_size as u64 }
+        };
+        if is_resize_needed {
+            let new_buff: Buffer = device.read().create_buffer(&wgpu::BufferDescriptor {
+                label: Some(format!("metadata_buffer{}", pack_id).as_str()),
+                size: (new_size) as BufferAddress,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            self.metadata_buffers.insert(pack_id, Rc::new(RwLock::new(new_buff)));
         }
         let q = queue.write();
-        let buff = self.metadata_buffer0.clone();
-        q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(h_m_d.as_ref()));
-    }
-    pub fn update_metadata1(&mut self, device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, h_m_d: &Vec<i32>) {
-        {
-            let buff = self.metadata_buffer1.clone();
-            let new_size: usize = if h_m_d.len() == 0 { 16 } else {
-                let curr_size = (h_m_d.len() * 4) as f32;
-                (((curr_size / 16.0).ceil() + 1.0) * 16.0) as usize
-            };
-            let currsize = buff.read().size();
-            if currsize != new_size as u64 {
-                let d = device.clone();
-                let new_buff: Buffer = d.read().create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("metadata_buffer1"),
-                    size: (new_size) as BufferAddress,
-                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-                    mapped_at_creation: false,
-                });
-                self.metadata_buffer1 = Rc::new(RwLock::new(new_buff));
+        match self.metadata_buffers.get(&pack_id) {
+            None => {}
+            Some(buff) => {
+                q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(h_m_d.as_ref()));
             }
         }
-        let q = queue.write();
-        let buff = self.metadata_buffer1.clone();
-        q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(h_m_d.as_ref()));
+        is_resize_needed
     }
-    pub fn update_metadata2(&mut self, device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, h_m_d: &Vec<i32>) {
-        {
-            let buff = self.metadata_buffer2.clone();
-            let new_size: usize = if h_m_d.len() == 0 { 16 } else {
-                let curr_size = (h_m_d.len() * 4) as f32;
-                (((curr_size / 16.0).ceil() + 1.0) * 16.0) as usize
-            };
-            let currsize = buff.read().size();
-            if currsize != new_size as u64 {
-                let d = device.clone();
-                let new_buff: Buffer = d.read().create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("metadata_buffer2"),
-                    size: (new_size) as BufferAddress,
-                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-                    mapped_at_creation: false,
-                });
-                self.metadata_buffer2 = Rc::new(RwLock::new(new_buff));
-            }
-        }
-        let q = queue.write();
-        let buff = self.metadata_buffer2.clone();
-        q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(h_m_d.as_ref()));
-    }
-    pub fn update_metadata3(&mut self, device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, h_m_d: &Vec<i32>) {
-        {
-            let buff = self.metadata_buffer3.clone();
-            let new_size: usize = if h_m_d.len() == 0 { 16 } else {
-                let curr_size = (h_m_d.len() * 4) as f32;
-                (((curr_size / 16.0).ceil() + 1.0) * 16.0) as usize
-            };
-            let currsize = buff.read().size();
-            if currsize != new_size as u64 {
-                let d = device.clone();
-                let new_buff: Buffer = d.read().create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("metadata_buffer0"),
-                    size: (new_size) as BufferAddress,
-                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-                    mapped_at_creation: false,
-                });
-                self.metadata_buffer3 = Rc::new(RwLock::new(new_buff));
-            }
-        }
-        let q = queue.write();
-        let buff = self.metadata_buffer3.clone();
-        q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(h_m_d.as_ref()));
-    }
-    pub fn update_metadata4(&mut self, device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, h_m_d: &Vec<i32>) {
-        {
-            let buff = self.metadata_buffer4.clone();
-            let new_size: usize = if h_m_d.len() == 0 { 16 } else {
-                let curr_size = (h_m_d.len() * 4) as f32;
-                (((curr_size / 16.0).ceil() + 1.0) * 16.0) as usize
-            };
-            let currsize = buff.read().size();
-            if currsize != new_size as u64 {
-                let d = device.clone();
-                let new_buff: Buffer = d.read().create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("metadata_buffer4"),
-                    size: (new_size) as BufferAddress,
-                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-                    mapped_at_creation: false,
-                });
-                self.metadata_buffer4 = Rc::new(RwLock::new(new_buff));
-            }
-        }
-        let q = queue.write();
-        let buff = self.metadata_buffer4.clone();
-        q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(h_m_d.as_ref()));
-    }
-    pub fn update_metadata5(&mut self, device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, h_m_d: &Vec<i32>) {
-        {
-            let buff = self.metadata_buffer5.clone();
-            let new_size: usize = if h_m_d.len() == 0 { 16 } else {
-                let curr_size = (h_m_d.len() * 4) as f32;
-                (((curr_size / 16.0).ceil() + 1.0) * 16.0) as usize
-            };
-            let currsize = buff.read().size();
-            if currsize != new_size as u64 {
-                let d = device.clone();
-                let new_buff: Buffer = d.read().create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("metadata_buffer5"),
-                    size: (new_size) as BufferAddress,
-                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-                    mapped_at_creation: false,
-                });
-                self.metadata_buffer5 = Rc::new(RwLock::new(new_buff));
-            }
-        }
-        let q = queue.write();
-        let buff = self.metadata_buffer5.clone();
-        q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(h_m_d.as_ref()));
-    }
-    pub fn update_metadata6(&mut self, device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, h_m_d: &Vec<i32>) {
-        {
-            let buff = self.metadata_buffer6.clone();
-            let new_size: usize = if h_m_d.len() == 0 { 16 } else {
-                let curr_size = (h_m_d.len() * 4) as f32;
-                (((curr_size / 16.0).ceil() + 1.0) * 16.0) as usize
-            };
-            let currsize = buff.read().size();
-            if currsize != new_size as u64 {
-                let d = device.clone();
-                let new_buff: Buffer = d.read().create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("metadata_buffer6"),
-                    size: (new_size) as BufferAddress,
-                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-                    mapped_at_creation: false,
-                });
-                self.metadata_buffer6 = Rc::new(RwLock::new(new_buff));
-            }
-        }
-        let q = queue.write();
-        let buff = self.metadata_buffer6.clone();
-        q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(h_m_d.as_ref()));
-    }
-    pub fn update_metadata7(&mut self, device: Rc<RwLock<Device>>, queue: Rc<RwLock<Queue>>, h_m_d: &Vec<i32>) {
-        {
-            let buff = self.metadata_buffer7.clone();
-            let new_size: usize = if h_m_d.len() == 0 { 16 } else {
-                let curr_size = (h_m_d.len() * 4) as f32;
-                (((curr_size / 16.0).ceil() + 1.0) * 16.0) as usize
-            };
-            let currsize = buff.read().size();
-            if currsize != new_size as u64 {
-                let d = device.clone();
-                let new_buff: Buffer = d.read().create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("metadata_buffer0"),
-                    size: (new_size) as BufferAddress,
-                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-                    mapped_at_creation: false,
-                });
-                self.metadata_buffer7 = Rc::new(RwLock::new(new_buff));
-            }
-        }
-        let q = queue.write();
-        let buff = self.metadata_buffer7.clone();
-        q.write_buffer(&buff.write(), 0, bytemuck::cast_slice(h_m_d.as_ref()));
+    pub fn remove_metadata(&mut self, pack_id: u32) {
+        self.metadata_buffers.remove(&pack_id);
     }
 
 }
\ No newline at end of file
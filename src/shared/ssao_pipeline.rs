@@ -0,0 +1,260 @@
+use std::mem::size_of;
+use std::rc::Rc;
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Matrix4, Point3, SquareMatrix};
+use parking_lot::RwLock;
+use wgpu::{BindGroup, BindGroupLayout, BlendFactor, BlendOperation, Buffer, BufferAddress, Device, PipelineLayout, Queue, RenderPipeline, Texture, TextureFormat, TextureView};
+use crate::gui::ssao::SsaoStyle;
+
+const OCCLUSION_BUFFER_FORMAT: TextureFormat = TextureFormat::R8Unorm;
+const KERNEL_SIZE: usize = 16;
+
+//see SsaoUniforms in ssao.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct SsaoUniforms {
+    vp: [[f32; 4]; 4],
+    inv_vp: [[f32; 4]; 4],
+    eye_position: [f32; 4],
+    params: [f32; 4],
+    kernel: [[f32; 4]; KERNEL_SIZE],
+}
+
+pub struct SsaoPipeLine {
+    ssao_bind_group_layout: BindGroupLayout,
+    blur_bind_group_layout: BindGroupLayout,
+    pub ssao_render_pipeline: RenderPipeline,
+    pub blur_render_pipeline: RenderPipeline,
+    pub uniform_buffer: Buffer,
+    kernel: [[f32; 4]; KERNEL_SIZE],
+    //kept between frames, made again when the surface size changes
+    occlusion_buffer: Texture,
+    pub occlusion_view: TextureView,
+}
+
+impl SsaoPipeLine {
+    pub fn new(_device: Rc<RwLock<Device>>, format: TextureFormat) -> Self {
+        let device = _device.write();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Ssao Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/ssao.wgsl").into()),
+        });
+        let uniform_buffer: Buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ssao Uniform Buffer"),
+            size: size_of::<SsaoUniforms>() as BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniform_entry = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let texture_entry = |binding: u32, sample_type: wgpu::TextureSampleType| wgpu::BindGroupLayoutEntry {
+            binding: binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: sample_type,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        //the occlusion pass reads the edge and depth buffers, the blur the edge buffer and the occlusion
+        let ssao_bind_group_layout: BindGroupLayout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                uniform_entry,
+                texture_entry(1, wgpu::TextureSampleType::Float { filterable: false }),
+                texture_entry(2, wgpu::TextureSampleType::Depth),
+            ],
+            label: Some("ssao Bind Group Layout"),
+        });
+        let blur_bind_group_layout: BindGroupLayout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                uniform_entry,
+                texture_entry(1, wgpu::TextureSampleType::Float { filterable: false }),
+                texture_entry(3, wgpu::TextureSampleType::Float { filterable: false }),
+            ],
+            label: Some("ssao blur Bind Group Layout"),
+        });
+        let ssao_pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Ssao Pipeline Layout"),
+            bind_group_layouts: &[&ssao_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let blur_pipeline_layout: PipelineLayout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Ssao Blur Pipeline Layout"),
+            bind_group_layouts: &[&blur_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        //full screen triangles without depth test, the occlusion goes to its own buffer
+        let ssao_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Ssao Pipeline"),
+            layout: Some(&ssao_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_ssao"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: OCCLUSION_BUFFER_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        //the blurred occlusion multiplies the colour of the frame, alpha is kept
+        let blur_render_pipeline: RenderPipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Ssao Blur Pipeline"),
+            layout: Some(&blur_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_blur"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: BlendFactor::Dst,
+                            dst_factor: BlendFactor::Zero,
+                            operation: BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: BlendFactor::Zero,
+                            dst_factor: BlendFactor::One,
+                            operation: BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let occlusion_buffer = create_occlusion_buffer(&device, 1, 1);
+        let occlusion_view = occlusion_buffer.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            ssao_bind_group_layout: ssao_bind_group_layout,
+            blur_bind_group_layout: blur_bind_group_layout,
+            ssao_render_pipeline: ssao_render_pipeline,
+            blur_render_pipeline: blur_render_pipeline,
+            uniform_buffer: uniform_buffer,
+            kernel: hemisphere_kernel(),
+            occlusion_buffer: occlusion_buffer,
+            occlusion_view: occlusion_view,
+        }
+    }
+    pub fn fit_occlusion_buffer(&mut self, device: &Device, width: u32, height: u32) {
+        if (self.occlusion_buffer.width() != width.max(1) || self.occlusion_buffer.height() != height.max(1)) {
+            self.occlusion_buffer = create_occlusion_buffer(device, width, height);
+            self.occlusion_view = self.occlusion_buffer.create_view(&wgpu::TextureViewDescriptor::default());
+        }
+    }
+    //every frame the pass is on, the camera moves
+    pub fn update_uniforms(&self, queue: &Queue, vp: Matrix4<f32>, eye: Point3<f32>, style: &SsaoStyle) {
+        let inv_vp = vp.invert().unwrap_or(Matrix4::identity());
+        let uniforms = SsaoUniforms {
+            vp: vp.into(),
+            inv_vp: inv_vp.into(),
+            eye_position: [eye.x, eye.y, eye.z, 1.0],
+            params: style.params,
+            kernel: self.kernel,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+    pub fn bind_ssao_group(&self, device: &Device, edge_view: &TextureView, depth_view: &TextureView) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.ssao_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(edge_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(depth_view),
+                },
+            ],
+            label: Some("Ssao Bind Group"),
+        })
+    }
+    pub fn bind_blur_group(&self, device: &Device, edge_view: &TextureView) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.blur_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(edge_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&self.occlusion_view),
+                },
+            ],
+            label: Some("Ssao Blur Bind Group"),
+        })
+    }
+}
+
+//golden angle spiral over the hemisphere, more samples close to the point
+fn hemisphere_kernel() -> [[f32; 4]; KERNEL_SIZE] {
+    let mut kernel = [[0.0; 4]; KERNEL_SIZE];
+    kernel.iter_mut().enumerate().for_each(|(i, k)| {
+        let t = (i as f32 + 0.5) / KERNEL_SIZE as f32;
+        let cos_theta = 1.0 - t * 0.9;
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = i as f32 * 2.399963;
+        let scale = 0.1 + 0.9 * t * t;
+        *k = [sin_theta * phi.cos() * scale, sin_theta * phi.sin() * scale, cos_theta * scale, 0.0];
+    });
+    kernel
+}
+
+fn create_occlusion_buffer(device: &Device, width: u32, height: u32) -> Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: OCCLUSION_BUFFER_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        label: Some("Occlusion Buffer"),
+        view_formats: &[],
+    })
+}
@@ -10,6 +10,8 @@ use parking_lot::RwLock;
 use wgpu::{Device, MultisampleState, Queue, SurfaceConfiguration};
 use winit::dpi::PhysicalPosition;
 use crate::device::message_controller::SnapMode;
+use crate::scene::pack_format::PartInfo;
+use crate::shared::materials_lib::HullPartTypes;
 use crate::shared::dimension::{Dimension, DimensionMode};
 
 const SANSSERIF: &[u8] = include_bytes!("../fonts/ARIALUNI.TTF");
@@ -71,6 +73,7 @@ pub struct TextLayout {
     snap_mode: SnapMode,
     pub active_id: i32,
     pub active_point: Point3<f32>,
+    active_part: Option<PartInfo>,
     scale_factor: f64,
     dimension_value: f32,
     dimension_pos: Vector2<f32>,
@@ -94,7 +97,7 @@ impl TextLayout {
 
 
         let mut snap_value_buff: Buffer = glyphon::Buffer::new(&mut font_system, Metrics::new(15.0, 15.0));
-        snap_value_buff.set_size(&mut font_system, Some(200.0), Some(200.0));
+        snap_value_buff.set_size(&mut font_system, Some(320.0), Some(200.0));
         snap_value_buff.set_text(&mut font_system, "BBBBBB", &Attrs::new().family(Family::Name("Arial")), Shaping::Basic);
         snap_value_buff.shape_until_scroll(&mut font_system, false);
 
@@ -132,6 +135,7 @@ impl TextLayout {
             snap_mode: SnapMode::NotSet,
             active_id: 0,
             active_point: Point3::new(0.0, 0.0, 0.0),
+            active_part: None,
             scale_factor: 1.0,
             dimension_value: 0.0,
             dimension_pos: Vector2::new(0.0, 0.0),
@@ -167,6 +171,7 @@ impl TextLayout {
                      snap_mode: SnapMode,
                      active_id: i32,
                      active_point: Point3<f32>,
+                     active_part: Option<PartInfo>,
                      mouse_position: PhysicalPosition<f64>,
                      dimension: Dimension,
                      mvp: Matrix4<f32>) {
@@ -182,9 +187,10 @@ impl TextLayout {
             self.is_dirty = true;
         }
 
-        if self.active_id != active_id || self.active_point != active_point {
+        if self.active_id != active_id || self.active_point != active_point || self.active_part != active_part {
             self.active_id = active_id;
             self.active_point = active_point;
+            self.active_part = active_part;
             self.gen_snap_value_area();
             self.is_dirty = true;
         }
@@ -317,7 +323,10 @@ impl TextLayout {
             let y = format!("{:6.3}", self.active_point.y / 100.0);
             let z = format!("{:6.3}", self.active_point.z / 100.0);
             let str = if self.active_point.x < f32::max_value() {
-                format!("ID {} \n X {}\n Y {} \n Z {}", id, x, y, z)
+                match &self.active_part {
+                    None => format!("ID {} \n X {}\n Y {} \n Z {}", id, x, y, z),
+                    Some(part) => format!("{}\n X {}\n Y {} \n Z {}", part_info_text(part), x, y, z),
+                }
             } else {
                 "".to_string()
             };
@@ -335,4 +344,45 @@ impl TextLayout {
     pub fn clear_dimension_value(&mut self){
         self.dimension_value = 0.0;
     }
-}
\ No newline at end of file
+}
+
+//lines for the fields that are set: id and name, type, block/section, grade, thickness and weight
+fn part_info_text(part: &PartInfo) -> String {
+    let mut lines: Vec<String> = vec![];
+    match &part.name {
+        None => lines.push(format!("ID {}", part.id)),
+        Some(name) => lines.push(format!("ID {} {}", part.id, name)),
+    }
+    match part.part_type {
+        None => {}
+        Some(code) => {
+            match HullPartTypes::from_code(code) {
+                None => lines.push(format!(" TYPE {}", code)),
+                Some(t) => lines.push(format!(" {}", t.name())),
+            }
+        }
+    }
+    match (&part.block, &part.section) {
+        (None, None) => {}
+        (block, section) => {
+            lines.push(format!(" BLOCK {} SECTION {}", block.as_deref().unwrap_or("-"), section.as_deref().unwrap_or("-")))
+        }
+    }
+    let mut material: Vec<String> = vec![];
+    match &part.grade {
+        None => {}
+        Some(grade) => material.push(grade.clone()),
+    }
+    match part.thickness {
+        None => {}
+        Some(thickness) => material.push(format!("{} mm", thickness)),
+    }
+    match part.weight {
+        None => {}
+        Some(weight) => material.push(format!("{:.1} kg", weight)),
+    }
+    if (!material.is_empty()) {
+        lines.push(format!(" {}", material.join(", ")));
+    }
+    lines.join("\n")
+}